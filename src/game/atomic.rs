@@ -0,0 +1,222 @@
+use serde::{Deserialize, Serialize};
+
+use super::bitboard::{between, king_attacks};
+use super::movegen::{attackers_to, pseudo_legal_moves};
+use super::{Board, Color, GameState, Move, Piece, PieceType, Position, Variant};
+
+/// Move legality for Atomic chess, where every capture explodes.
+///
+/// Kings cannot capture, as they would blow themselves up, and a king next
+/// to the enemy king is never in check, since taking it would take the
+/// capturer's own king along. Exploding the enemy king wins outright, even
+/// when the mover's king is left attacked.
+pub struct AtomicValidator;
+
+impl AtomicValidator {
+    pub fn generate_legal_moves(board: &Board) -> Vec<Move> {
+        pseudo_legal_moves(board)
+            .into_iter()
+            .filter(|chess_move| Self::is_legal(board, chess_move))
+            .collect()
+    }
+
+    pub fn is_in_check(board: &Board, color: Color) -> bool {
+        let them = color.opposite();
+        let (Some(king), Some(enemy_king)) = (
+            board.pieces(PieceType::King, color).lsb(),
+            board.pieces(PieceType::King, them).lsb(),
+        ) else {
+            return false;
+        };
+
+        !king_attacks(king).contains(enemy_king)
+            && !attackers_to(board, king, them, board.occupied()).is_empty()
+    }
+
+    /// The side whose king has been blown up, if either has.
+    pub fn exploded_king(board: &Board) -> Option<Color> {
+        [Color::White, Color::Black]
+            .into_iter()
+            .find(|&color| board.pieces(PieceType::King, color).is_empty())
+    }
+
+    fn is_legal(board: &Board, chess_move: &Move) -> bool {
+        let us = board.get_to_move();
+        let Some(king) = board.find_king(us) else {
+            return false;
+        };
+
+        if chess_move.from == king
+            && !chess_move.is_castle
+            && board.is_occupied_by(chess_move.to, us.opposite())
+        {
+            return false;
+        }
+
+        if chess_move.is_castle && !Self::is_castling_path_safe(board, chess_move) {
+            return false;
+        }
+
+        let mut after = board.clone();
+        if after.make_atomic_move(chess_move).is_err() || after.find_king(us).is_none() {
+            return false;
+        }
+
+        after.find_king(us.opposite()).is_none() || !Self::is_in_check(&after, us)
+    }
+
+    /// The king may not castle out of check or across an attacked square.
+    /// Its destination is checked along with the rest of the move.
+    fn is_castling_path_safe(board: &Board, chess_move: &Move) -> bool {
+        let us = board.get_to_move();
+        if Self::is_in_check(board, us) {
+            return false;
+        }
+
+        let king_file = match chess_move.to.file > chess_move.from.file {
+            true => 6,
+            false => 2,
+        };
+        let king_to = Position::new(king_file, chess_move.from.rank).unwrap();
+
+        between(chess_move.from.index(), king_to.index())
+            .squares()
+            .all(|square| {
+                let mut crossing = board.clone();
+                if let Some(king) = crossing.remove_piece(chess_move.from) {
+                    crossing.place_piece(Position::from_index(square).unwrap(), king);
+                }
+                !Self::is_in_check(&crossing, us)
+            })
+    }
+}
+
+/// What an Atomic capture blew up, for clients to animate.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Explosion {
+    pub center: Position,
+    /// Every piece taken off the board, including the captured piece and the
+    /// capturer.
+    pub pieces: Vec<(Position, Piece)>,
+}
+
+impl GameState {
+    /// The explosion set off by the last move, if it was an Atomic capture.
+    pub fn last_explosion(&self) -> Option<Explosion> {
+        if self.variant != Variant::Atomic {
+            return None;
+        }
+
+        let undo = self.undo_history.last()?;
+        let captured = undo.captured?;
+
+        let mut capturer = undo.moved_piece;
+        if let Some(promotion) = undo.chess_move.promotion {
+            capturer.piece_type = promotion;
+        }
+
+        // En passant explodes where the capturing pawn lands, not on the
+        // captured pawn's square
+        let center = undo.chess_move.to;
+        let mut pieces = vec![captured, (center, capturer)];
+        pieces.extend(undo.exploded.iter().copied());
+
+        Some(Explosion { center, pieces })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::perft::perft;
+    use crate::game::{AtomicRules, GameResult, VariantWinReason};
+
+    fn atomic_board(fen: &str) -> Board {
+        Variant::Atomic.board_from_fen(fen).unwrap()
+    }
+
+    fn legal(fen: &str) -> Vec<String> {
+        AtomicValidator::generate_legal_moves(&atomic_board(fen))
+            .iter()
+            .map(Move::to_algebraic)
+            .collect()
+    }
+
+    #[test]
+    fn test_explosions() {
+        let fen = "rn2k2r/1p4p1/4N3/8/8/8/8/R3K2R w KQkq - 0 1";
+        let mut board = atomic_board(fen);
+        let key = board.zobrist_key();
+
+        // The h8 rook goes up with the g7 pawn, and its castling right too
+        let undo = board
+            .make_atomic_move(&Move::from_algebraic("e6g7").unwrap())
+            .unwrap();
+        assert_eq!(board.to_fen(), "rn2k3/1p6/8/8/8/8/8/R3K2R b KQq - 0 1");
+        assert_eq!(
+            undo.exploded,
+            [(
+                Position::from_algebraic("h8").unwrap(),
+                Piece::new(PieceType::Rook, Color::Black)
+            )]
+        );
+
+        board.unmake_move(&undo);
+        assert_eq!(board.to_fen(), fen);
+        assert_eq!(board.zobrist_key(), key);
+
+        // Pawns next to the capture survive
+        board
+            .make_atomic_move(&Move::from_algebraic("a1a8").unwrap())
+            .unwrap();
+        assert_eq!(board.to_fen(), "4k2r/1p4p1/4N3/8/8/8/8/4K2R b Kk - 0 1");
+    }
+
+    #[test]
+    fn test_legality() {
+        // Kings cannot capture
+        assert!(!legal("4k3/8/8/8/8/8/3p4/4K3 w - - 0 1").contains(&"e1d2".to_string()));
+
+        // Capturing next to your own king blows it up
+        assert!(!legal("4k3/8/8/8/8/8/3p4/3QK3 w - - 0 1").contains(&"d1d2".to_string()));
+
+        // Touching kings cannot give check, so the king may walk up to its
+        // rival despite the rook
+        assert!(legal("8/8/8/3k4/8/3K4/8/3r4 w - - 0 1").contains(&"d3d4".to_string()));
+        assert!(AtomicValidator::is_in_check(
+            &atomic_board("8/8/8/3k4/8/3K4/8/3r4 w - - 0 1"),
+            Color::White
+        ));
+
+        // Blowing up the enemy king wins even from check
+        assert!(legal("4k3/4q3/8/8/8/8/4R3/r3K3 w - - 0 1").contains(&"e2e7".to_string()));
+    }
+
+    #[test]
+    fn test_perft() {
+        let board = Variant::Atomic.starting_board().unwrap();
+        assert_eq!(perft(&AtomicRules, &board, 3), 8902);
+    }
+
+    #[test]
+    fn test_king_explosion_ends_game() {
+        let mut game = GameState::for_variant(Variant::Atomic, None).unwrap();
+        for uci in ["g1f3", "a7a6", "f3g5", "a6a5", "g5f7"] {
+            game.apply_move(Move::from_algebraic(uci).unwrap()).unwrap();
+        }
+
+        assert_eq!(
+            game.result,
+            GameResult::VariantWin(Color::White, VariantWinReason::KingExploded)
+        );
+
+        // The f7 pawn, the knight and the e8 king, f8 bishop and g8 knight
+        let explosion = game.last_explosion().unwrap();
+        assert_eq!(explosion.center, Position::from_algebraic("f7").unwrap());
+        assert_eq!(explosion.pieces.len(), 5);
+        assert!(explosion.pieces.contains(&(
+            Position::from_algebraic("e8").unwrap(),
+            Piece::new(PieceType::King, Color::Black)
+        )));
+    }
+}
@@ -0,0 +1,332 @@
+use std::ops::{BitAnd, BitAndAssign, BitOr, BitOrAssign, BitXor, BitXorAssign, Not};
+use std::sync::OnceLock;
+
+use serde::{Deserialize, Serialize};
+
+use super::{Color, Position};
+
+/// A set of squares, one bit per square with a1 = bit 0 and h8 = bit 63.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub struct Bitboard(pub u64);
+
+impl Bitboard {
+    pub const EMPTY: Bitboard = Bitboard(0);
+    pub const ALL: Bitboard = Bitboard(!0);
+
+    pub const fn from_square(square: usize) -> Self {
+        Bitboard(1 << square)
+    }
+
+    pub fn from_position(pos: Position) -> Self {
+        Self::from_square(pos.index())
+    }
+
+    pub fn rank(rank: u8) -> Self {
+        Bitboard(0xff << (rank * 8))
+    }
+
+    pub fn file(file: u8) -> Self {
+        Bitboard(0x0101_0101_0101_0101 << file)
+    }
+
+    pub fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    pub fn contains(self, square: usize) -> bool {
+        self.0 & (1 << square) != 0
+    }
+
+    pub fn count(self) -> u32 {
+        self.0.count_ones()
+    }
+
+    /// Lowest set square, if any.
+    pub fn lsb(self) -> Option<usize> {
+        match self.0 {
+            0 => None,
+            bits => Some(bits.trailing_zeros() as usize),
+        }
+    }
+
+    /// Highest set square, if any.
+    pub fn msb(self) -> Option<usize> {
+        match self.0 {
+            0 => None,
+            bits => Some(63 - bits.leading_zeros() as usize),
+        }
+    }
+
+    pub fn has_more_than_one(self) -> bool {
+        self.0 & self.0.wrapping_sub(1) != 0
+    }
+
+    pub fn squares(self) -> Squares {
+        Squares(self.0)
+    }
+}
+
+pub struct Squares(u64);
+
+impl Iterator for Squares {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        if self.0 == 0 {
+            return None;
+        }
+        let square = self.0.trailing_zeros() as usize;
+        self.0 &= self.0 - 1;
+        Some(square)
+    }
+}
+
+impl BitAnd for Bitboard {
+    type Output = Bitboard;
+
+    fn bitand(self, rhs: Bitboard) -> Bitboard {
+        Bitboard(self.0 & rhs.0)
+    }
+}
+
+impl BitOr for Bitboard {
+    type Output = Bitboard;
+
+    fn bitor(self, rhs: Bitboard) -> Bitboard {
+        Bitboard(self.0 | rhs.0)
+    }
+}
+
+impl BitXor for Bitboard {
+    type Output = Bitboard;
+
+    fn bitxor(self, rhs: Bitboard) -> Bitboard {
+        Bitboard(self.0 ^ rhs.0)
+    }
+}
+
+impl Not for Bitboard {
+    type Output = Bitboard;
+
+    fn not(self) -> Bitboard {
+        Bitboard(!self.0)
+    }
+}
+
+impl BitAndAssign for Bitboard {
+    fn bitand_assign(&mut self, rhs: Bitboard) {
+        self.0 &= rhs.0;
+    }
+}
+
+impl BitOrAssign for Bitboard {
+    fn bitor_assign(&mut self, rhs: Bitboard) {
+        self.0 |= rhs.0;
+    }
+}
+
+impl BitXorAssign for Bitboard {
+    fn bitxor_assign(&mut self, rhs: Bitboard) {
+        self.0 ^= rhs.0;
+    }
+}
+
+// Ray directions as (file step, rank step). The first four run towards higher
+// square indices, so their nearest blocker is the lowest set bit.
+const DIRECTIONS: [(i8, i8); 8] = [
+    (0, 1),
+    (1, 0),
+    (1, 1),
+    (-1, 1),
+    (0, -1),
+    (-1, 0),
+    (-1, -1),
+    (1, -1),
+];
+const ROOK_DIRECTIONS: [usize; 4] = [0, 1, 4, 5];
+const BISHOP_DIRECTIONS: [usize; 4] = [2, 3, 6, 7];
+
+struct AttackTables {
+    knight: [Bitboard; 64],
+    king: [Bitboard; 64],
+    pawn: [[Bitboard; 64]; 2],
+    rays: [[Bitboard; 64]; 8],
+    between: Box<[[Bitboard; 64]; 64]>,
+    line: Box<[[Bitboard; 64]; 64]>,
+}
+
+fn tables() -> &'static AttackTables {
+    static TABLES: OnceLock<AttackTables> = OnceLock::new();
+    TABLES.get_or_init(AttackTables::build)
+}
+
+fn offset(square: usize, file_step: i8, rank_step: i8) -> Option<usize> {
+    let file = (square % 8) as i8 + file_step;
+    let rank = (square / 8) as i8 + rank_step;
+    match (0..8).contains(&file) && (0..8).contains(&rank) {
+        true => Some((rank * 8 + file) as usize),
+        false => None,
+    }
+}
+
+fn leaper_attacks(square: usize, steps: &[(i8, i8)]) -> Bitboard {
+    steps
+        .iter()
+        .filter_map(|&(file_step, rank_step)| offset(square, file_step, rank_step))
+        .fold(Bitboard::EMPTY, |acc, to| acc | Bitboard::from_square(to))
+}
+
+impl AttackTables {
+    fn build() -> Self {
+        const KNIGHT_STEPS: [(i8, i8); 8] = [
+            (1, 2),
+            (2, 1),
+            (2, -1),
+            (1, -2),
+            (-1, -2),
+            (-2, -1),
+            (-2, 1),
+            (-1, 2),
+        ];
+
+        let mut tables = AttackTables {
+            knight: [Bitboard::EMPTY; 64],
+            king: [Bitboard::EMPTY; 64],
+            pawn: [[Bitboard::EMPTY; 64]; 2],
+            rays: [[Bitboard::EMPTY; 64]; 8],
+            between: Box::new([[Bitboard::EMPTY; 64]; 64]),
+            line: Box::new([[Bitboard::EMPTY; 64]; 64]),
+        };
+
+        for square in 0..64 {
+            tables.knight[square] = leaper_attacks(square, &KNIGHT_STEPS);
+            tables.king[square] = leaper_attacks(square, &DIRECTIONS);
+            tables.pawn[Color::White.index()][square] = leaper_attacks(square, &[(-1, 1), (1, 1)]);
+            tables.pawn[Color::Black.index()][square] =
+                leaper_attacks(square, &[(-1, -1), (1, -1)]);
+
+            for (dir, &(file_step, rank_step)) in DIRECTIONS.iter().enumerate() {
+                let mut ray = Bitboard::EMPTY;
+                let mut current = square;
+                while let Some(next) = offset(current, file_step, rank_step) {
+                    ray |= Bitboard::from_square(next);
+                    tables.between[square][next] = ray ^ Bitboard::from_square(next);
+                    current = next;
+                }
+                tables.rays[dir][square] = ray;
+            }
+        }
+
+        for from in 0..64 {
+            for dir in 0..4 {
+                let line = tables.rays[dir][from]
+                    | tables.rays[dir + 4][from]
+                    | Bitboard::from_square(from);
+                for to in line.squares() {
+                    tables.line[from][to] = line;
+                }
+            }
+        }
+
+        tables
+    }
+}
+
+fn slider_attacks(square: usize, occupied: Bitboard, directions: [usize; 4]) -> Bitboard {
+    let tables = tables();
+    let mut attacks = Bitboard::EMPTY;
+
+    for dir in directions {
+        let ray = tables.rays[dir][square];
+        let blockers = ray & occupied;
+        let blocker = match dir < 4 {
+            true => blockers.lsb(),
+            false => blockers.msb(),
+        };
+
+        attacks |= match blocker {
+            Some(blocker) => ray ^ tables.rays[dir][blocker],
+            None => ray,
+        };
+    }
+
+    attacks
+}
+
+pub fn knight_attacks(square: usize) -> Bitboard {
+    tables().knight[square]
+}
+
+pub fn king_attacks(square: usize) -> Bitboard {
+    tables().king[square]
+}
+
+/// Squares a pawn of `color` on `square` attacks.
+pub fn pawn_attacks(color: Color, square: usize) -> Bitboard {
+    tables().pawn[color.index()][square]
+}
+
+pub fn rook_attacks(square: usize, occupied: Bitboard) -> Bitboard {
+    slider_attacks(square, occupied, ROOK_DIRECTIONS)
+}
+
+pub fn bishop_attacks(square: usize, occupied: Bitboard) -> Bitboard {
+    slider_attacks(square, occupied, BISHOP_DIRECTIONS)
+}
+
+pub fn queen_attacks(square: usize, occupied: Bitboard) -> Bitboard {
+    rook_attacks(square, occupied) | bishop_attacks(square, occupied)
+}
+
+/// Squares strictly between two squares on a shared rank, file or diagonal.
+pub fn between(from: usize, to: usize) -> Bitboard {
+    tables().between[from][to]
+}
+
+/// The full rank, file or diagonal through both squares, or empty if they are
+/// not aligned.
+pub fn line(from: usize, to: usize) -> Bitboard {
+    tables().line[from][to]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sq(name: &str) -> usize {
+        Position::from_algebraic(name).unwrap().index()
+    }
+
+    #[test]
+    fn test_leaper_attacks() {
+        assert_eq!(knight_attacks(sq("a1")).count(), 2);
+        assert_eq!(knight_attacks(sq("d4")).count(), 8);
+        assert_eq!(king_attacks(sq("h8")).count(), 3);
+        assert_eq!(
+            pawn_attacks(Color::White, sq("a2")),
+            Bitboard::from_square(sq("b3"))
+        );
+        assert_eq!(pawn_attacks(Color::Black, sq("e5")).count(), 2);
+    }
+
+    #[test]
+    fn test_slider_attacks() {
+        let occupied = Bitboard::from_square(sq("d6")) | Bitboard::from_square(sq("f4"));
+
+        let rook = rook_attacks(sq("d4"), occupied);
+        assert_eq!(rook.count(), 2 + 3 + 2 + 3);
+        assert!(rook.contains(sq("d6")) && !rook.contains(sq("d7")));
+        assert!(rook.contains(sq("f4")) && !rook.contains(sq("g4")));
+
+        assert_eq!(bishop_attacks(sq("a1"), Bitboard::EMPTY).count(), 7);
+        assert_eq!(queen_attacks(sq("d4"), Bitboard::EMPTY).count(), 27);
+    }
+
+    #[test]
+    fn test_between_and_line() {
+        assert_eq!(between(sq("a1"), sq("d4")).count(), 2);
+        assert_eq!(between(sq("a1"), sq("b3")), Bitboard::EMPTY);
+        assert_eq!(line(sq("b2"), sq("g7")).count(), 8);
+        assert_eq!(line(sq("a1"), sq("b3")), Bitboard::EMPTY);
+    }
+}
@@ -1,23 +1,174 @@
 use serde::{Deserialize, Serialize};
+use thiserror::Error;
 
+use super::bitboard::{Bitboard, between, king_attacks};
+use super::movegen::has_legal_en_passant;
 use super::piece::{Color, Move, Piece, PieceType, Position};
+use super::pocket::Pockets;
+use super::rules::MoveValidator;
+use super::ruleset::{FogOfWarRules, Ruleset, StandardRules};
+use super::zobrist::{castling_key, checks_key, en_passant_key, piece_key, pockets_key, side_key};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Board {
-    squares: [[Option<Piece>; 8]; 8],
+    pieces: [Bitboard; 6],
+    colors: [Bitboard; 2],
+    // Squares whose piece has not moved yet
+    unmoved: Bitboard,
+    // Zobrist key without the en passant component, see `zobrist_key`
+    hash: u64,
     to_move: Color,
     castling_rights: CastlingRights,
     en_passant_target: Option<Position>,
     halfmove_clock: u32,
     fullmove_number: u32,
+    // Castling moves are encoded as the king capturing its own rook
+    chess960: bool,
+    // Pieces in hand, for variants with drops
+    pockets: Option<Pockets>,
+    // Squares holding a promoted piece, which returns to a pocket as a pawn
+    promoted: Bitboard,
+    // Checks given so far, for Three-check
+    check_counts: Option<CheckCounts>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub struct CastlingRights {
     pub white_kingside: bool,
     pub white_queenside: bool,
     pub black_kingside: bool,
     pub black_queenside: bool,
+    /// Castling rook files by color, kingside first.
+    pub rook_files: [[u8; 2]; 2],
+}
+
+/// Checks each side has given, counted in Three-check.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CheckCounts {
+    pub white: u8,
+    pub black: u8,
+}
+
+/// Everything `Board::unmake_move` needs to restore the position a move was
+/// played from.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MoveUndo {
+    pub chess_move: Move,
+    pub moved_piece: Piece,
+    pub captured: Option<(Position, Piece)>,
+    /// Pieces around an Atomic capture that went up with it, besides the
+    /// capturer and the captured piece.
+    pub exploded: Vec<(Position, Piece)>,
+    pub castling_rights: CastlingRights,
+    pub en_passant_target: Option<Position>,
+    pub halfmove_clock: u32,
+    pub fullmove_number: u32,
+    pub hash: u64,
+    unmoved: Bitboard,
+    pockets: Option<Pockets>,
+    promoted: Bitboard,
+    check_counts: Option<CheckCounts>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum FenError {
+    #[error("expected 6 fields, found {0}")]
+    WrongFieldCount(usize),
+
+    #[error("invalid check counts: {0}")]
+    InvalidCheckCounts(String),
+
+    #[error("invalid piece placement: {0}")]
+    InvalidPlacement(String),
+
+    #[error("invalid side to move: {0}")]
+    InvalidSideToMove(String),
+
+    #[error("invalid castling rights: {0}")]
+    InvalidCastlingRights(String),
+
+    #[error("invalid en passant target: {0}")]
+    InvalidEnPassant(String),
+
+    #[error("invalid halfmove clock: {0}")]
+    InvalidHalfmoveClock(String),
+
+    #[error("invalid fullmove number: {0}")]
+    InvalidFullmoveNumber(String),
+
+    #[error("{0:?} king is missing")]
+    MissingKing(Color),
+
+    #[error("{0:?} has more than one king")]
+    TooManyKings(Color),
+
+    #[error("pawn on back rank at {0}")]
+    PawnOnBackRank(String),
+
+    #[error("side not to move is in check")]
+    OpponentInCheck,
+
+    #[error("no king may be in check in Racing Kings")]
+    RacingKingsCheck,
+}
+
+const STANDARD_BACK_RANK: [PieceType; 8] = [
+    PieceType::Rook,
+    PieceType::Knight,
+    PieceType::Bishop,
+    PieceType::Queen,
+    PieceType::King,
+    PieceType::Bishop,
+    PieceType::Knight,
+    PieceType::Rook,
+];
+
+// Knight placements on the five squares left after the bishops and queen
+const CHESS960_KNIGHTS: [(usize, usize); 10] = [
+    (0, 1),
+    (0, 2),
+    (0, 3),
+    (0, 4),
+    (1, 2),
+    (1, 3),
+    (1, 4),
+    (2, 3),
+    (2, 4),
+    (3, 4),
+];
+
+/// Decodes a Scharnagl number into a back rank, files a to h.
+fn chess960_back_rank(index: u16) -> Option<[PieceType; 8]> {
+    if index >= 960 {
+        return None;
+    }
+
+    let mut back_rank: [Option<PieceType>; 8] = [None; 8];
+    let mut n = index as usize;
+
+    back_rank[(n % 4) * 2 + 1] = Some(PieceType::Bishop);
+    n /= 4;
+    back_rank[(n % 4) * 2] = Some(PieceType::Bishop);
+    n /= 4;
+
+    let mut place_on_free = |nth: usize, piece_type: PieceType| {
+        let file = (0..8).filter(|&f| back_rank[f].is_none()).nth(nth).unwrap();
+        back_rank[file] = Some(piece_type);
+    };
+
+    place_on_free(n % 6, PieceType::Queen);
+    n /= 6;
+
+    // The second knight goes first so the first one's index is unaffected
+    let (first, second) = CHESS960_KNIGHTS[n];
+    place_on_free(second, PieceType::Knight);
+    place_on_free(first, PieceType::Knight);
+
+    for piece_type in [PieceType::Rook, PieceType::King, PieceType::Rook] {
+        place_on_free(0, piece_type);
+    }
+
+    Some(back_rank.map(Option::unwrap))
 }
 
 impl Default for CastlingRights {
@@ -27,144 +178,226 @@ impl Default for CastlingRights {
             white_queenside: true,
             black_kingside: true,
             black_queenside: true,
+            rook_files: [[7, 0], [7, 0]],
+        }
+    }
+}
+
+impl CastlingRights {
+    pub fn none() -> Self {
+        Self {
+            white_kingside: false,
+            white_queenside: false,
+            black_kingside: false,
+            black_queenside: false,
+            rook_files: [[7, 0], [7, 0]],
+        }
+    }
+
+    pub fn has(&self, color: Color, kingside: bool) -> bool {
+        match (color, kingside) {
+            (Color::White, true) => self.white_kingside,
+            (Color::White, false) => self.white_queenside,
+            (Color::Black, true) => self.black_kingside,
+            (Color::Black, false) => self.black_queenside,
         }
     }
+
+    pub fn set(&mut self, color: Color, kingside: bool, allowed: bool) {
+        let flag = match (color, kingside) {
+            (Color::White, true) => &mut self.white_kingside,
+            (Color::White, false) => &mut self.white_queenside,
+            (Color::Black, true) => &mut self.black_kingside,
+            (Color::Black, false) => &mut self.black_queenside,
+        };
+        *flag = allowed;
+    }
+
+    pub fn rook_file(&self, color: Color, kingside: bool) -> u8 {
+        self.rook_files[color.index()][usize::from(!kingside)]
+    }
+
+    pub fn set_rook_file(&mut self, color: Color, kingside: bool, file: u8) {
+        self.rook_files[color.index()][usize::from(!kingside)] = file;
+    }
+}
+
+impl CheckCounts {
+    pub fn given(&self, color: Color) -> u8 {
+        match color {
+            Color::White => self.white,
+            Color::Black => self.black,
+        }
+    }
+
+    fn add(&mut self, color: Color) {
+        let count = match color {
+            Color::White => &mut self.white,
+            Color::Black => &mut self.black,
+        };
+        *count = count.saturating_add(1);
+    }
+
+    /// The trailing `+W+B` FEN field.
+    pub fn to_fen(&self) -> String {
+        format!("+{}+{}", self.white, self.black)
+    }
+
+    pub fn from_fen(field: &str) -> Option<Self> {
+        let (white, black) = field.strip_prefix('+')?.split_once('+')?;
+        Some(Self {
+            white: white.parse().ok()?,
+            black: black.parse().ok()?,
+        })
+    }
 }
 
 impl Board {
     pub fn new() -> Self {
         let mut board = Self {
-            squares: [[None; 8]; 8],
+            pieces: [Bitboard::EMPTY; 6],
+            colors: [Bitboard::EMPTY; 2],
+            unmoved: Bitboard::EMPTY,
+            hash: 0,
             to_move: Color::White,
             castling_rights: CastlingRights::default(),
             en_passant_target: None,
             halfmove_clock: 0,
             fullmove_number: 1,
+            chess960: false,
+            pockets: None,
+            promoted: Bitboard::EMPTY,
+            check_counts: None,
         };
-        board.setup_starting_position();
+        board.setup_back_ranks(STANDARD_BACK_RANK);
+        board.hash = board.compute_hash();
         board
     }
 
+    /// Chess960 start position by its Scharnagl number, 518 being the
+    /// standard setup. Returns `None` for indices past 959.
+    pub fn new_chess960(index: u16) -> Option<Self> {
+        let back_rank = chess960_back_rank(index)?;
+
+        let mut board = Self::empty();
+        board.setup_back_ranks(back_rank);
+        board.castling_rights = CastlingRights {
+            white_kingside: true,
+            white_queenside: true,
+            black_kingside: true,
+            black_queenside: true,
+            ..CastlingRights::none()
+        };
+        for color in [Color::White, Color::Black] {
+            let mut rooks = (0..8u8).filter(|&file| back_rank[file as usize] == PieceType::Rook);
+            let queenside = rooks.next()?;
+            let kingside = rooks.next()?;
+            board.castling_rights.set_rook_file(color, true, kingside);
+            board.castling_rights.set_rook_file(color, false, queenside);
+        }
+        board.chess960 = true;
+        board.hash = board.compute_hash();
+        Some(board)
+    }
+
     pub fn empty() -> Self {
         Self {
-            squares: [[None; 8]; 8],
+            pieces: [Bitboard::EMPTY; 6],
+            colors: [Bitboard::EMPTY; 2],
+            unmoved: Bitboard::EMPTY,
+            hash: 0,
             to_move: Color::White,
-            castling_rights: CastlingRights {
-                white_kingside: false,
-                white_queenside: false,
-                black_kingside: false,
-                black_queenside: false,
-            },
+            castling_rights: CastlingRights::none(),
             en_passant_target: None,
             halfmove_clock: 0,
             fullmove_number: 1,
+            chess960: false,
+            pockets: None,
+            promoted: Bitboard::EMPTY,
+            check_counts: None,
         }
     }
 
-    fn setup_starting_position(&mut self) {
-        self.place_piece(
-            Position::new(0, 0).unwrap(),
-            Piece::new(PieceType::Rook, Color::White),
-        );
-        self.place_piece(
-            Position::new(1, 0).unwrap(),
-            Piece::new(PieceType::Knight, Color::White),
-        );
-        self.place_piece(
-            Position::new(2, 0).unwrap(),
-            Piece::new(PieceType::Bishop, Color::White),
-        );
-        self.place_piece(
-            Position::new(3, 0).unwrap(),
-            Piece::new(PieceType::Queen, Color::White),
-        );
-        self.place_piece(
-            Position::new(4, 0).unwrap(),
-            Piece::new(PieceType::King, Color::White),
-        );
-        self.place_piece(
-            Position::new(5, 0).unwrap(),
-            Piece::new(PieceType::Bishop, Color::White),
-        );
-        self.place_piece(
-            Position::new(6, 0).unwrap(),
-            Piece::new(PieceType::Knight, Color::White),
-        );
-        self.place_piece(
-            Position::new(7, 0).unwrap(),
-            Piece::new(PieceType::Rook, Color::White),
-        );
-
-        for file in 0..8 {
-            self.place_piece(
-                Position::new(file, 1).unwrap(),
-                Piece::new(PieceType::Pawn, Color::White),
-            );
-        }
-
-        self.place_piece(
-            Position::new(0, 7).unwrap(),
-            Piece::new(PieceType::Rook, Color::Black),
-        );
-        self.place_piece(
-            Position::new(1, 7).unwrap(),
-            Piece::new(PieceType::Knight, Color::Black),
-        );
-        self.place_piece(
-            Position::new(2, 7).unwrap(),
-            Piece::new(PieceType::Bishop, Color::Black),
-        );
-        self.place_piece(
-            Position::new(3, 7).unwrap(),
-            Piece::new(PieceType::Queen, Color::Black),
-        );
-        self.place_piece(
-            Position::new(4, 7).unwrap(),
-            Piece::new(PieceType::King, Color::Black),
-        );
-        self.place_piece(
-            Position::new(5, 7).unwrap(),
-            Piece::new(PieceType::Bishop, Color::Black),
-        );
-        self.place_piece(
-            Position::new(6, 7).unwrap(),
-            Piece::new(PieceType::Knight, Color::Black),
-        );
-        self.place_piece(
-            Position::new(7, 7).unwrap(),
-            Piece::new(PieceType::Rook, Color::Black),
-        );
-
-        for file in 0..8 {
-            self.place_piece(
-                Position::new(file, 6).unwrap(),
-                Piece::new(PieceType::Pawn, Color::Black),
-            );
+    fn setup_back_ranks(&mut self, back_rank: [PieceType; 8]) {
+        for (file, piece_type) in (0..8).zip(back_rank) {
+            for (color, piece_rank, pawn_rank) in [(Color::White, 0, 1), (Color::Black, 7, 6)] {
+                self.place_piece(
+                    Position::new(file, piece_rank).unwrap(),
+                    Piece::new(piece_type, color),
+                );
+                self.place_piece(
+                    Position::new(file, pawn_rank).unwrap(),
+                    Piece::new(PieceType::Pawn, color),
+                );
+            }
         }
     }
 
     pub fn get_piece(&self, pos: Position) -> Option<Piece> {
-        match pos.is_valid() {
-            true => self.squares[pos.rank as usize][pos.file as usize],
-            false => None,
+        if !pos.is_valid() {
+            return None;
         }
+
+        let square = pos.index();
+        let piece_type = self.piece_type_at(square)?;
+        let color = match self.colors[Color::White.index()].contains(square) {
+            true => Color::White,
+            false => Color::Black,
+        };
+
+        Some(Piece {
+            piece_type,
+            color,
+            has_moved: !self.unmoved.contains(square),
+        })
     }
 
     pub fn place_piece(&mut self, pos: Position, piece: Piece) {
         if pos.is_valid() {
-            self.squares[pos.rank as usize][pos.file as usize] = Some(piece);
+            self.remove_piece(pos);
+
+            let bit = Bitboard::from_position(pos);
+            self.hash ^= piece_key(piece.piece_type, piece.color, pos.index());
+            self.pieces[piece.piece_type.index()] |= bit;
+            self.colors[piece.color.index()] |= bit;
+            if !piece.has_moved {
+                self.unmoved |= bit;
+            }
         }
     }
 
     pub fn remove_piece(&mut self, pos: Position) -> Option<Piece> {
-        match pos.is_valid() {
-            true => {
-                let piece = self.squares[pos.rank as usize][pos.file as usize];
-                self.squares[pos.rank as usize][pos.file as usize] = None;
-                piece
-            }
-            false => None,
-        }
+        let piece = self.get_piece(pos)?;
+
+        let clear = !Bitboard::from_position(pos);
+        self.hash ^= piece_key(piece.piece_type, piece.color, pos.index());
+        self.pieces[piece.piece_type.index()] &= clear;
+        self.colors[piece.color.index()] &= clear;
+        self.unmoved &= clear;
+
+        Some(piece)
+    }
+
+    pub fn piece_type_at(&self, square: usize) -> Option<PieceType> {
+        PieceType::ALL
+            .into_iter()
+            .find(|piece_type| self.pieces[piece_type.index()].contains(square))
+    }
+
+    pub fn pieces(&self, piece_type: PieceType, color: Color) -> Bitboard {
+        self.pieces[piece_type.index()] & self.colors[color.index()]
+    }
+
+    pub fn pieces_of_type(&self, piece_type: PieceType) -> Bitboard {
+        self.pieces[piece_type.index()]
+    }
+
+    pub fn color_pieces(&self, color: Color) -> Bitboard {
+        self.colors[color.index()]
+    }
+
+    pub fn occupied(&self) -> Bitboard {
+        self.colors[0] | self.colors[1]
     }
 
     pub fn is_empty(&self, pos: Position) -> bool {
@@ -183,6 +416,7 @@ impl Board {
     }
 
     pub fn set_to_move(&mut self, color: Color) {
+        self.hash ^= side_key(self.to_move) ^ side_key(color);
         self.to_move = color;
     }
 
@@ -202,61 +436,129 @@ impl Board {
         self.fullmove_number
     }
 
-    pub fn find_king(&self, color: Color) -> Option<Position> {
-        for rank in 0..8 {
-            for file in 0..8 {
-                let pos = Position::new(file, rank).unwrap();
-                if let Some(piece) = self.get_piece(pos) {
-                    if piece.piece_type == PieceType::King && piece.color == color {
-                        return Some(pos);
-                    }
-                }
-            }
-        }
-        None
+    pub fn is_chess960(&self) -> bool {
+        self.chess960
     }
 
-    pub fn is_path_clear(&self, from: Position, to: Position) -> bool {
-        let file_diff = to.file as i8 - from.file as i8;
-        let rank_diff = to.rank as i8 - from.rank as i8;
+    /// Switches castling move encoding between the king's two-square step and
+    /// the king-takes-rook form Chess960 uses.
+    pub fn set_chess960(&mut self, chess960: bool) {
+        self.chess960 = chess960;
+    }
+
+    /// The position as `color` sees it through the fog: enemy pieces off
+    /// the `visible` squares are gone, along with the enemy's castling
+    /// rights and any en passant target `color` cannot capture on right now.
+    pub fn fogged(&self, color: Color, visible: Bitboard) -> Board {
+        let mut board = self.clone();
+        let them = color.opposite();
 
-        // Directly adjacent squares are always clear
-        if file_diff.abs() <= 1 && rank_diff.abs() <= 1 {
-            return true;
+        for square in (board.color_pieces(them) & !visible).squares() {
+            board.remove_piece(Position::from_index(square).unwrap());
         }
+        board.castling_rights.set(them, true, false);
+        board.castling_rights.set(them, false, false);
+        if board.to_move != color || !FogOfWarRules.has_legal_en_passant(&board) {
+            board.en_passant_target = None;
+        }
+
+        board.hash = board.compute_hash();
+        board
+    }
 
-        let file_step = file_diff.signum();
-        let rank_step = rank_diff.signum();
+    pub fn pockets(&self) -> Option<&Pockets> {
+        self.pockets.as_ref()
+    }
 
-        let mut curr_file = from.file as i8 + file_step;
-        let mut curr_rank = from.rank as i8 + rank_step;
+    /// Gives the board pockets, enabling drops, or takes them away.
+    pub fn set_pockets(&mut self, pockets: Option<Pockets>) {
+        self.hash ^= self.pockets.as_ref().map_or(0, pockets_key);
+        self.pockets = pockets;
+        self.hash ^= self.pockets.as_ref().map_or(0, pockets_key);
+    }
 
-        while curr_file != to.file as i8 || curr_rank != to.rank as i8 {
-            let pos = Position::new(curr_file as u8, curr_rank as u8);
-            if let Some(pos) = pos {
-                if !self.is_empty(pos) {
-                    return false;
-                }
-            }
-            curr_file += file_step;
-            curr_rank += rank_step;
-        }
-        true
+    pub fn check_counts(&self) -> Option<&CheckCounts> {
+        self.check_counts.as_ref()
+    }
+
+    /// Starts or stops counting the checks each side gives.
+    pub fn set_check_counts(&mut self, check_counts: Option<CheckCounts>) {
+        self.hash ^= self.check_counts.as_ref().map_or(0, checks_key);
+        self.check_counts = check_counts;
+        self.hash ^= self.check_counts.as_ref().map_or(0, checks_key);
+    }
+
+    pub fn is_promoted(&self, pos: Position) -> bool {
+        pos.is_valid() && self.promoted.contains(pos.index())
+    }
+
+    pub fn find_king(&self, color: Color) -> Option<Position> {
+        self.pieces(PieceType::King, color)
+            .lsb()
+            .and_then(Position::from_index)
+    }
+
+    pub fn is_path_clear(&self, from: Position, to: Position) -> bool {
+        (between(from.index(), to.index()) & self.occupied()).is_empty()
+    }
+
+    pub fn make_move(&mut self, chess_move: &Move) -> Result<MoveUndo, String> {
+        self.play(chess_move, false)
     }
 
-    pub fn make_move(&mut self, chess_move: &Move) -> Result<(), String> {
-        let piece = self
-            .get_piece(chess_move.from)
-            .ok_or("No piece at source position")?;
+    /// Plays `chess_move` as in Atomic chess, where a capture explodes,
+    /// taking the capturer and every piece but pawns around the capture
+    /// square off the board.
+    pub fn make_atomic_move(&mut self, chess_move: &Move) -> Result<MoveUndo, String> {
+        self.play(chess_move, true)
+    }
+
+    fn play(&mut self, chess_move: &Move, explode: bool) -> Result<MoveUndo, String> {
+        let piece = match chess_move.drop {
+            Some(piece_type) => Piece::new(piece_type, self.to_move),
+            None => self
+                .get_piece(chess_move.from)
+                .ok_or("No piece at source position")?,
+        };
 
         if piece.color != self.to_move {
             return Err("Not your turn".to_string());
         }
 
+        let captured_at = match chess_move.is_en_passant {
+            true => Position::new(chess_move.to.file, chess_move.from.rank).unwrap(),
+            false => chess_move.to,
+        };
+        let captured = match chess_move.is_castle || chess_move.is_drop() {
+            true => None,
+            false => self
+                .get_piece(captured_at)
+                .map(|piece| (captured_at, piece)),
+        };
+        let is_capture = captured.is_some();
+
+        let mut undo = MoveUndo {
+            chess_move: *chess_move,
+            moved_piece: piece,
+            captured,
+            exploded: Vec::new(),
+            castling_rights: self.castling_rights,
+            en_passant_target: self.en_passant_target,
+            halfmove_clock: self.halfmove_clock,
+            fullmove_number: self.fullmove_number,
+            hash: self.hash,
+            unmoved: self.unmoved,
+            pockets: self.pockets,
+            promoted: self.promoted,
+            check_counts: self.check_counts,
+        };
+
         let mut moved_piece = piece;
         moved_piece.mark_moved();
 
-        if chess_move.is_castle {
+        if chess_move.is_drop() {
+            self.execute_drop(chess_move.to, moved_piece)?;
+        } else if chess_move.is_castle {
             self.execute_castle(chess_move)?;
         } else if chess_move.is_en_passant {
             self.execute_en_passant(chess_move)?;
@@ -270,55 +572,240 @@ impl Board {
             self.place_piece(chess_move.to, moved_piece);
         }
 
+        self.update_promoted(chess_move, captured);
+
+        if explode && is_capture {
+            undo.exploded = self.explode(chess_move.to);
+        }
+
         self.update_en_passant_target(chess_move, &piece);
-        self.update_castling_rights(chess_move, &piece);
 
-        match piece.piece_type == PieceType::Pawn || !self.is_empty(chess_move.to) {
+        self.hash ^= castling_key(&self.castling_rights);
+        self.update_castling_rights(chess_move, &piece, &undo.exploded);
+        self.hash ^= castling_key(&self.castling_rights);
+
+        match piece.piece_type == PieceType::Pawn || is_capture {
             true => self.halfmove_clock = 0,
             false => self.halfmove_clock += 1,
         };
 
-        if self.to_move == Color::White {
+        if self.to_move == Color::Black {
             self.fullmove_number += 1;
         }
 
+        self.hash ^= side_key(Color::Black);
         self.to_move = self.to_move.opposite();
 
+        if let Some(checks) = self.check_counts
+            && MoveValidator::is_in_check(self, self.to_move)
+        {
+            let mut counted = checks;
+            counted.add(piece.color);
+            self.hash ^= checks_key(&checks) ^ checks_key(&counted);
+            self.check_counts = Some(counted);
+        }
+
+        Ok(undo)
+    }
+
+    /// Takes back the move `undo` was recorded for. It must be the last move
+    /// played on this board.
+    pub fn unmake_move(&mut self, undo: &MoveUndo) {
+        let chess_move = &undo.chess_move;
+
+        if chess_move.is_drop() {
+            self.remove_piece(chess_move.to);
+        } else if chess_move.is_castle {
+            let (king_to, rook_from, rook_to) =
+                Self::castle_squares(&undo.castling_rights, undo.moved_piece.color, chess_move);
+            let rook = self.remove_piece(rook_to);
+            self.remove_piece(king_to);
+            self.place_piece(chess_move.from, undo.moved_piece);
+            if let Some(rook) = rook {
+                self.place_piece(rook_from, rook);
+            }
+        } else {
+            self.remove_piece(chess_move.to);
+            self.place_piece(chess_move.from, undo.moved_piece);
+            if let Some((pos, piece)) = undo.captured {
+                self.place_piece(pos, piece);
+            }
+            for &(pos, piece) in &undo.exploded {
+                self.place_piece(pos, piece);
+            }
+        }
+
+        self.to_move = undo.moved_piece.color;
+        self.castling_rights = undo.castling_rights;
+        self.en_passant_target = undo.en_passant_target;
+        self.halfmove_clock = undo.halfmove_clock;
+        self.fullmove_number = undo.fullmove_number;
+        self.unmoved = undo.unmoved;
+        self.pockets = undo.pockets;
+        self.promoted = undo.promoted;
+        self.check_counts = undo.check_counts;
+        self.hash = undo.hash;
+    }
+
+    /// Zobrist key of the position. The en passant square only counts when a
+    /// legal en passant capture exists, matching when FIDE treats two
+    /// positions as the same.
+    pub fn zobrist_key(&self) -> u64 {
+        self.zobrist_key_with(has_legal_en_passant(self))
+    }
+
+    /// Zobrist key of the position, counting the en passant square only if
+    /// `en_passant_capturable`, as the variant's rules decide.
+    pub fn zobrist_key_with(&self, en_passant_capturable: bool) -> u64 {
+        match self.en_passant_target {
+            Some(target) if en_passant_capturable => self.hash ^ en_passant_key(target.file),
+            _ => self.hash,
+        }
+    }
+
+    fn compute_hash(&self) -> u64 {
+        let mut hash = side_key(self.to_move)
+            ^ castling_key(&self.castling_rights)
+            ^ self.pockets.as_ref().map_or(0, pockets_key)
+            ^ self.check_counts.as_ref().map_or(0, checks_key);
+        for color in [Color::White, Color::Black] {
+            for piece_type in PieceType::ALL {
+                for square in self.pieces(piece_type, color).squares() {
+                    hash ^= piece_key(piece_type, color, square);
+                }
+            }
+        }
+        hash
+    }
+
+    fn execute_drop(&mut self, to: Position, piece: Piece) -> Result<(), String> {
+        if !self.is_empty(to) {
+            return Err("Drop target is occupied".to_string());
+        }
+        if piece.piece_type == PieceType::Pawn && (to.rank == 0 || to.rank == 7) {
+            return Err("Pawns cannot be dropped on the first or last rank".to_string());
+        }
+
+        let color = piece.color;
+        match self.with_pockets(|pockets| pockets.take(color, piece.piece_type)) {
+            Some(true) => {}
+            Some(false) => return Err("Piece is not in the pocket".to_string()),
+            None => return Err("Drops are not allowed in this game".to_string()),
+        }
+
+        self.place_piece(to, piece);
         Ok(())
     }
 
-    fn execute_castle(&mut self, chess_move: &Move) -> Result<(), String> {
-        let king = self
-            .remove_piece(chess_move.from)
-            .ok_or("No king at source position")?;
+    /// Moves the promoted marker along with its piece and sends captured
+    /// pieces to the capturer's pocket, promoted ones as pawns.
+    fn update_promoted(&mut self, chess_move: &Move, captured: Option<(Position, Piece)>) {
+        if chess_move.is_drop() || chess_move.is_castle {
+            return;
+        }
 
-        self.place_piece(chess_move.to, king);
+        let from = Bitboard::from_position(chess_move.from);
+        let to = Bitboard::from_position(chess_move.to);
+        let was_promoted = !(self.promoted & from).is_empty();
+        self.promoted &= !from;
+
+        if let Some((pos, piece)) = captured {
+            let square = Bitboard::from_position(pos);
+            let pocketed = match (self.promoted & square).is_empty() {
+                true => piece.piece_type,
+                false => PieceType::Pawn,
+            };
+            self.promoted &= !square;
+
+            let capturer = piece.color.opposite();
+            self.with_pockets(|pockets| pockets.add(capturer, pocketed));
+        }
 
-        let (rook_from, rook_to) = if chess_move.to.file > chess_move.from.file {
-            // Kingside castle
-            (
-                Position::new(7, chess_move.from.rank).unwrap(),
-                Position::new(5, chess_move.from.rank).unwrap(),
-            )
-        } else {
-            // Queenside castle
-            (
-                Position::new(0, chess_move.from.rank).unwrap(),
-                Position::new(3, chess_move.from.rank).unwrap(),
-            )
-        };
+        if was_promoted || chess_move.promotion.is_some() {
+            self.promoted |= to;
+        }
+    }
+
+    /// Blows up the capturer on `center` along with every piece but pawns on
+    /// the squares around it. Returns the surrounding pieces lost.
+    fn explode(&mut self, center: Position) -> Vec<(Position, Piece)> {
+        self.remove_piece(center);
+
+        let blast = king_attacks(center.index());
+        let mut exploded = Vec::new();
+        for square in (blast & self.occupied() & !self.pieces_of_type(PieceType::Pawn)).squares() {
+            let pos = Position::from_index(square).unwrap();
+            if let Some(piece) = self.remove_piece(pos) {
+                exploded.push((pos, piece));
+            }
+        }
+        self.promoted &= !(blast | Bitboard::from_position(center));
 
+        exploded
+    }
+
+    /// Applies `update` to the pockets, if the board has any, keeping the
+    /// hash in step.
+    fn with_pockets<R>(&mut self, update: impl FnOnce(&mut Pockets) -> R) -> Option<R> {
+        let pockets = self.pockets.as_mut()?;
+        self.hash ^= pockets_key(pockets);
+        let result = update(pockets);
+        self.hash ^= pockets_key(pockets);
+        Some(result)
+    }
+
+    fn execute_castle(&mut self, chess_move: &Move) -> Result<(), String> {
+        let (king_to, rook_from, rook_to) =
+            Self::castle_squares(&self.castling_rights, self.to_move, chess_move);
+
+        if !self
+            .pieces(PieceType::Rook, self.to_move)
+            .contains(rook_from.index())
+        {
+            return Err("No rook for castling".to_string());
+        }
+
+        // Lift both pieces first, in Chess960 either may land on the other's square
+        let mut king = self
+            .remove_piece(chess_move.from)
+            .ok_or("No king at source position")?;
         let mut rook = self.remove_piece(rook_from).ok_or("No rook for castling")?;
+        king.mark_moved();
         rook.mark_moved();
+
+        self.place_piece(king_to, king);
         self.place_piece(rook_to, rook);
 
         Ok(())
     }
 
+    /// King destination, rook origin and rook destination of a castling move.
+    /// Either encoding is accepted: the king's two-square step or the king
+    /// moving onto its own rook.
+    fn castle_squares(
+        rights: &CastlingRights,
+        color: Color,
+        chess_move: &Move,
+    ) -> (Position, Position, Position) {
+        let rank = chess_move.from.rank;
+        let kingside = chess_move.to.file > chess_move.from.file;
+        let (king_file, rook_file) = match kingside {
+            true => (6, 5),
+            false => (2, 3),
+        };
+
+        (
+            Position::new(king_file, rank).unwrap(),
+            Position::new(rights.rook_file(color, kingside), rank).unwrap(),
+            Position::new(rook_file, rank).unwrap(),
+        )
+    }
+
     fn execute_en_passant(&mut self, chess_move: &Move) -> Result<(), String> {
-        let pawn = self
+        let mut pawn = self
             .remove_piece(chess_move.from)
             .ok_or("No pawn for en passant")?;
+        pawn.mark_moved();
 
         self.place_piece(chess_move.to, pawn);
 
@@ -333,41 +820,44 @@ impl Board {
 
         if piece.piece_type == PieceType::Pawn {
             let rank_diff = (chess_move.to.rank as i8 - chess_move.from.rank as i8).abs();
-            if rank_diff == 2 {
+            // A Horde pawn's double step from the first rank cannot be taken
+            // en passant
+            let second_rank = matches!(chess_move.from.rank, 1 | 6);
+            if rank_diff == 2 && second_rank {
                 let target_rank = (chess_move.from.rank + chess_move.to.rank) / 2;
                 self.en_passant_target = Position::new(chess_move.from.file, target_rank);
             }
         }
     }
 
-    fn update_castling_rights(&mut self, chess_move: &Move, piece: &Piece) {
-        match piece.piece_type {
-            PieceType::King => match piece.color {
-                Color::White => {
-                    self.castling_rights.white_kingside = false;
-                    self.castling_rights.white_queenside = false;
-                }
-                Color::Black => {
-                    self.castling_rights.black_kingside = false;
-                    self.castling_rights.black_queenside = false;
-                }
-            },
-            PieceType::Rook => match (piece.color, chess_move.from.file, chess_move.from.rank) {
-                (Color::White, 0, 0) => self.castling_rights.white_queenside = false,
-                (Color::White, 7, 0) => self.castling_rights.white_kingside = false,
-                (Color::Black, 0, 7) => self.castling_rights.black_queenside = false,
-                (Color::Black, 7, 7) => self.castling_rights.black_kingside = false,
-                _ => {}
-            },
-            _ => {}
+    fn update_castling_rights(
+        &mut self,
+        chess_move: &Move,
+        piece: &Piece,
+        exploded: &[(Position, Piece)],
+    ) {
+        let lost = exploded.iter().map(|(_, piece)| piece);
+        for king in std::iter::once(piece).chain(lost) {
+            if king.piece_type == PieceType::King {
+                self.castling_rights.set(king.color, true, false);
+                self.castling_rights.set(king.color, false, false);
+            }
         }
 
-        match (chess_move.to.file, chess_move.to.rank) {
-            (0, 0) => self.castling_rights.white_queenside = false,
-            (7, 0) => self.castling_rights.white_kingside = false,
-            (0, 7) => self.castling_rights.black_queenside = false,
-            (7, 7) => self.castling_rights.black_kingside = false,
-            _ => {}
+        // Moving a castling rook, capturing it or blowing it up gives up
+        // that side
+        for (color, back_rank) in [(Color::White, 0), (Color::Black, 7)] {
+            for kingside in [true, false] {
+                let rook =
+                    Position::new(self.castling_rights.rook_file(color, kingside), back_rank)
+                        .unwrap();
+                if chess_move.from == rook
+                    || chess_move.to == rook
+                    || exploded.iter().any(|&(pos, _)| pos == rook)
+                {
+                    self.castling_rights.set(color, kingside, false);
+                }
+            }
         }
     }
 
@@ -386,6 +876,9 @@ impl Board {
                             empty_count = 0;
                         }
                         fen.push(piece.to_fen_char());
+                        if self.pockets.is_some() && self.is_promoted(pos) {
+                            fen.push('~');
+                        }
                     }
                     None => empty_count += 1,
                 }
@@ -398,6 +891,10 @@ impl Board {
             }
         }
 
+        if let Some(pockets) = &self.pockets {
+            fen.push_str(&format!("[{}]", pockets.to_fen()));
+        }
+
         fen.push(' ');
         fen.push(match self.to_move {
             Color::White => 'w',
@@ -405,23 +902,7 @@ impl Board {
         });
 
         fen.push(' ');
-        let mut castling = String::new();
-        if self.castling_rights.white_kingside {
-            castling.push('K');
-        }
-        if self.castling_rights.white_queenside {
-            castling.push('Q');
-        }
-        if self.castling_rights.black_kingside {
-            castling.push('k');
-        }
-        if self.castling_rights.black_queenside {
-            castling.push('q');
-        }
-        if castling.is_empty() {
-            castling.push('-');
-        }
-        fen.push_str(&castling);
+        fen.push_str(&self.castling_field());
 
         fen.push(' ');
         match self.en_passant_target {
@@ -434,9 +915,368 @@ impl Board {
             self.halfmove_clock, self.fullmove_number
         ));
 
+        if let Some(checks) = &self.check_counts {
+            fen.push(' ');
+            fen.push_str(&checks.to_fen());
+        }
+
         fen
     }
 
+    /// X-FEN castling field: `KQkq` when the castling rook is the outermost
+    /// one on its side of the king, the rook's file letter otherwise.
+    fn castling_field(&self) -> String {
+        let mut castling = String::new();
+
+        for (color, back_rank) in [(Color::White, 0), (Color::Black, 7)] {
+            let rooks = self.pieces(PieceType::Rook, color) & Bitboard::rank(back_rank);
+
+            for kingside in [true, false] {
+                if !self.castling_rights.has(color, kingside) {
+                    continue;
+                }
+
+                let file = self.castling_rights.rook_file(color, kingside);
+                let outermost = match kingside {
+                    true => rooks.msb(),
+                    false => rooks.lsb(),
+                };
+                let flag = match (
+                    outermost == Some(back_rank as usize * 8 + file as usize),
+                    kingside,
+                ) {
+                    (true, true) => 'k',
+                    (true, false) => 'q',
+                    (false, _) => (b'a' + file) as char,
+                };
+
+                castling.push(match color {
+                    Color::White => flag.to_ascii_uppercase(),
+                    Color::Black => flag,
+                });
+            }
+        }
+
+        if castling.is_empty() {
+            castling.push('-');
+        }
+        castling
+    }
+
+    pub fn from_fen(fen: &str) -> Result<Self, FenError> {
+        Self::from_fen_with(fen, &StandardRules, |_| {})
+    }
+
+    /// Reads a FEN like `from_fen`, letting `setup` add variant state before
+    /// the position is validated against `rules`.
+    pub fn from_fen_with(
+        fen: &str,
+        rules: &dyn Ruleset,
+        setup: impl FnOnce(&mut Board),
+    ) -> Result<Self, FenError> {
+        let mut fields: Vec<&str> = fen.split_whitespace().collect();
+
+        // Three-check appends the checks given as a seventh field
+        let check_counts = match fields.len() {
+            7 => {
+                let field = fields.pop().unwrap();
+                Some(
+                    CheckCounts::from_fen(field)
+                        .ok_or_else(|| FenError::InvalidCheckCounts(field.to_string()))?,
+                )
+            }
+            _ => None,
+        };
+        if fields.len() != 6 {
+            return Err(FenError::WrongFieldCount(fields.len()));
+        }
+
+        let mut board = Self::empty();
+        board.check_counts = check_counts;
+
+        // Crazyhouse appends the pockets in brackets
+        let placement = match fields[0].strip_suffix(']') {
+            Some(rest) => {
+                let (placement, pockets) = rest.split_once('[').ok_or_else(|| {
+                    FenError::InvalidPlacement("unbalanced pocket brackets".to_string())
+                })?;
+                let pockets = Pockets::from_fen(pockets).ok_or_else(|| {
+                    FenError::InvalidPlacement(format!("invalid pocket '{}'", pockets))
+                })?;
+                board.pockets = Some(pockets);
+                placement
+            }
+            None => fields[0],
+        };
+        board.parse_placement(placement)?;
+
+        board.to_move = match fields[1] {
+            "w" => Color::White,
+            "b" => Color::Black,
+            other => return Err(FenError::InvalidSideToMove(other.to_string())),
+        };
+
+        board.castling_rights = board.parse_castling_rights(fields[2])?;
+        board.chess960 = board.has_chess960_castling();
+        board.en_passant_target = Self::parse_en_passant(fields[3])?;
+
+        board.halfmove_clock = fields[4]
+            .parse()
+            .map_err(|_| FenError::InvalidHalfmoveClock(fields[4].to_string()))?;
+        board.fullmove_number = match fields[5].parse::<u32>() {
+            Ok(n) if n >= 1 => n,
+            _ => return Err(FenError::InvalidFullmoveNumber(fields[5].to_string())),
+        };
+
+        setup(&mut board);
+        board.validate_castling_rights()?;
+        board.validate_en_passant()?;
+        rules.validate(&board)?;
+        board.sync_moved_flags();
+        board.hash = board.compute_hash();
+
+        Ok(board)
+    }
+
+    fn parse_placement(&mut self, placement: &str) -> Result<(), FenError> {
+        let ranks: Vec<&str> = placement.split('/').collect();
+        if ranks.len() != 8 {
+            return Err(FenError::InvalidPlacement(format!(
+                "expected 8 ranks, found {}",
+                ranks.len()
+            )));
+        }
+
+        for (i, rank_str) in ranks.iter().enumerate() {
+            let rank = 7 - i as u8;
+            let mut file: u8 = 0;
+            let mut last_was_digit = false;
+            // Square of the piece just read, which a '~' marks as promoted
+            let mut last_piece = None;
+
+            for c in rank_str.chars() {
+                if c == '~' {
+                    let pos: Position = last_piece.take().ok_or_else(|| {
+                        FenError::InvalidPlacement(format!("stray '~' in rank {}", rank + 1))
+                    })?;
+                    self.promoted |= Bitboard::from_position(pos);
+                    continue;
+                }
+                last_piece = None;
+
+                if let Some(skip) = c.to_digit(10) {
+                    if !(1..=8).contains(&skip) || last_was_digit {
+                        return Err(FenError::InvalidPlacement(format!(
+                            "bad empty-square count in rank {}",
+                            rank + 1
+                        )));
+                    }
+                    file += skip as u8;
+                    last_was_digit = true;
+                } else {
+                    let piece = Piece::from_fen_char(c).ok_or_else(|| {
+                        FenError::InvalidPlacement(format!("unknown piece '{}'", c))
+                    })?;
+                    if let Some(pos) = Position::new(file, rank) {
+                        self.place_piece(pos, piece);
+                        last_piece = Some(pos);
+                    }
+                    file += 1;
+                    last_was_digit = false;
+                }
+
+                if file > 8 {
+                    break;
+                }
+            }
+
+            if file != 8 {
+                return Err(FenError::InvalidPlacement(format!(
+                    "rank {} does not describe 8 squares",
+                    rank + 1
+                )));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Accepts `KQkq`, Shredder-FEN rook files (`HAha`) and X-FEN, which mixes
+    /// both. `K` and `Q` refer to the outermost rook on that side of the king.
+    fn parse_castling_rights(&self, field: &str) -> Result<CastlingRights, FenError> {
+        let mut rights = CastlingRights::none();
+        if field == "-" {
+            return Ok(rights);
+        }
+
+        let invalid = || FenError::InvalidCastlingRights(field.to_string());
+
+        for c in field.chars() {
+            let (color, back_rank) = match c.is_ascii_uppercase() {
+                true => (Color::White, 0),
+                false => (Color::Black, 7),
+            };
+            let king = self
+                .find_king(color)
+                .filter(|king| king.rank == back_rank)
+                .ok_or_else(invalid)?;
+            let rooks = self.pieces(PieceType::Rook, color) & Bitboard::rank(back_rank);
+
+            let rook_file = match c.to_ascii_lowercase() {
+                'k' => rooks
+                    .msb()
+                    .map(|sq| (sq % 8) as u8)
+                    .filter(|&file| file > king.file),
+                'q' => rooks
+                    .lsb()
+                    .map(|sq| (sq % 8) as u8)
+                    .filter(|&file| file < king.file),
+                file @ 'a'..='h' => Some(file as u8 - b'a'),
+                _ => None,
+            }
+            .filter(|&file| file != king.file)
+            .ok_or_else(invalid)?;
+
+            let kingside = rook_file > king.file;
+            if rights.has(color, kingside) {
+                return Err(invalid());
+            }
+            rights.set(color, kingside, true);
+            rights.set_rook_file(color, kingside, rook_file);
+        }
+
+        Ok(rights)
+    }
+
+    /// Whether any castling right involves a king or rook off its standard file.
+    fn has_chess960_castling(&self) -> bool {
+        [Color::White, Color::Black].into_iter().any(|color| {
+            let king_moved_off = self.find_king(color).is_some_and(|king| king.file != 4);
+            [(true, 7), (false, 0)]
+                .into_iter()
+                .any(|(kingside, standard_file)| {
+                    self.castling_rights.has(color, kingside)
+                        && (king_moved_off
+                            || self.castling_rights.rook_file(color, kingside) != standard_file)
+                })
+        })
+    }
+
+    fn parse_en_passant(field: &str) -> Result<Option<Position>, FenError> {
+        if field == "-" {
+            return Ok(None);
+        }
+
+        Position::from_algebraic(field)
+            .map(Some)
+            .ok_or_else(|| FenError::InvalidEnPassant(field.to_string()))
+    }
+
+    fn validate_castling_rights(&self) -> Result<(), FenError> {
+        let requirements = [
+            (Color::White, true, 'K'),
+            (Color::White, false, 'Q'),
+            (Color::Black, true, 'k'),
+            (Color::Black, false, 'q'),
+        ];
+
+        for (color, kingside, flag) in requirements {
+            if !self.castling_rights.has(color, kingside) {
+                continue;
+            }
+
+            let back_rank = match color {
+                Color::White => 0,
+                Color::Black => 7,
+            };
+            let rook_file = self.castling_rights.rook_file(color, kingside);
+            let rook = Position::new(rook_file, back_rank).unwrap();
+
+            let king_in_place = self
+                .find_king(color)
+                .is_some_and(|king| king.rank == back_rank && (rook_file > king.file) == kingside);
+            let rook_in_place = self.pieces(PieceType::Rook, color).contains(rook.index());
+
+            if !king_in_place || !rook_in_place {
+                return Err(FenError::InvalidCastlingRights(format!(
+                    "'{}' without king and rook on their original squares",
+                    flag
+                )));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn validate_en_passant(&self) -> Result<(), FenError> {
+        let target = match self.en_passant_target {
+            Some(target) => target,
+            None => return Ok(()),
+        };
+
+        // The target sits behind a pawn of the side that just moved
+        let (target_rank, pawn_rank, origin_rank) = match self.to_move {
+            Color::White => (5, 4, 6),
+            Color::Black => (2, 3, 1),
+        };
+        let mover = self.to_move.opposite();
+
+        let pawn = Position::new(target.file, pawn_rank).unwrap();
+        let origin = Position::new(target.file, origin_rank).unwrap();
+
+        if target.rank != target_rank
+            || self.get_piece(pawn) != Some(Piece::new(PieceType::Pawn, mover))
+            || !self.is_empty(target)
+            || !self.is_empty(origin)
+        {
+            return Err(FenError::InvalidEnPassant(target.to_algebraic()));
+        }
+
+        Ok(())
+    }
+
+    /// Derives `has_moved` for pieces whose history a FEN string does not record.
+    fn sync_moved_flags(&mut self) {
+        let rights = self.castling_rights;
+
+        for rank in 0..8 {
+            for file in 0..8 {
+                let pos = Position::new(file, rank).unwrap();
+                let Some(mut piece) = self.get_piece(pos) else {
+                    continue;
+                };
+
+                let back_rank = match piece.color {
+                    Color::White => 0,
+                    Color::Black => 7,
+                };
+                let kingside = rights.has(piece.color, true);
+                let queenside = rights.has(piece.color, false);
+                let is_castling_rook = |kingside| {
+                    rights.has(piece.color, kingside)
+                        && rights.rook_file(piece.color, kingside) == file
+                };
+
+                piece.has_moved = match piece.piece_type {
+                    PieceType::Pawn => {
+                        let pawn_rank = match piece.color {
+                            Color::White => 1,
+                            Color::Black => 6,
+                        };
+                        rank != pawn_rank
+                    }
+                    PieceType::King => !(kingside || queenside),
+                    PieceType::Rook => {
+                        rank != back_rank || !(is_castling_rook(true) || is_castling_rook(false))
+                    }
+                    _ => true,
+                };
+
+                self.place_piece(pos, piece);
+            }
+        }
+    }
+
     pub fn display(&self) -> String {
         let mut display = String::new();
 
@@ -490,6 +1330,236 @@ mod tests {
         assert!(fen.starts_with("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq -"));
     }
 
+    #[test]
+    fn test_fen_round_trip() {
+        let fens = [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "rnbqkbnr/pppp1ppp/8/4p3/4P3/8/PPPP1PPP/RNBQKBNR w KQkq e6 0 2",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+            "4k3/8/8/8/8/8/8/4K2R w K - 12 40",
+        ];
+
+        for fen in fens {
+            let board = Board::from_fen(fen).unwrap();
+            assert_eq!(board.to_fen(), fen);
+        }
+    }
+
+    #[test]
+    fn test_fullmove_number_advances_after_black() {
+        let mut board = Board::new();
+        board
+            .make_move(&Move::from_algebraic("e2e4").unwrap())
+            .unwrap();
+        assert_eq!(board.get_fullmove_number(), 1);
+
+        let undo = board
+            .make_move(&Move::from_algebraic("e7e5").unwrap())
+            .unwrap();
+        assert_eq!(board.get_fullmove_number(), 2);
+        assert_eq!(
+            board.to_fen(),
+            "rnbqkbnr/pppp1ppp/8/4p3/4P3/8/PPPP1PPP/RNBQKBNR w KQkq e6 0 2"
+        );
+
+        board.unmake_move(&undo);
+        assert_eq!(board.get_fullmove_number(), 1);
+    }
+
+    #[test]
+    fn test_fen_restores_state() {
+        let board =
+            Board::from_fen("rnbqkbnr/ppp1pppp/8/8/3pP3/8/PPPP1PPP/RNBQKBNR b Kq e3 0 3").unwrap();
+
+        assert_eq!(board.get_to_move(), Color::Black);
+        assert_eq!(
+            board.get_en_passant_target(),
+            Position::from_algebraic("e3")
+        );
+        assert!(board.get_castling_rights().white_kingside);
+        assert!(!board.get_castling_rights().white_queenside);
+        assert_eq!(board.get_fullmove_number(), 3);
+
+        // Moved pawns and rooks that lost their rights must not double-step or castle
+        let pawn = board
+            .get_piece(Position::from_algebraic("e4").unwrap())
+            .unwrap();
+        assert!(pawn.has_moved);
+        let rook = board
+            .get_piece(Position::from_algebraic("a1").unwrap())
+            .unwrap();
+        assert!(rook.has_moved);
+        let rook = board
+            .get_piece(Position::from_algebraic("h1").unwrap())
+            .unwrap();
+        assert!(!rook.has_moved);
+    }
+
+    #[test]
+    fn test_fen_errors() {
+        let cases = [
+            ("8/8/8/8/8/8/8/8 w - - 0", FenError::WrongFieldCount(5)),
+            (
+                "4k3/8/8/8/8/8/8/4K3 x - - 0 1",
+                FenError::InvalidSideToMove("x".to_string()),
+            ),
+            (
+                "4k3/8/8/8/8/8/8/8 w - - 0 1",
+                FenError::MissingKing(Color::White),
+            ),
+            (
+                "4k3/8/8/8/8/8/8/3KK3 w - - 0 1",
+                FenError::TooManyKings(Color::White),
+            ),
+            (
+                "4k2P/8/8/8/8/8/8/4K3 w - - 0 1",
+                FenError::PawnOnBackRank("h8".to_string()),
+            ),
+            ("4k2R/8/8/8/8/8/8/4K3 w - - 0 1", FenError::OpponentInCheck),
+            (
+                "4k3/8/8/8/8/8/8/4K3 w - - 0 0",
+                FenError::InvalidFullmoveNumber("0".to_string()),
+            ),
+        ];
+
+        for (fen, expected) in cases {
+            assert_eq!(Board::from_fen(fen).unwrap_err(), expected, "{}", fen);
+        }
+
+        let malformed = [
+            "4k3/8/8/8/8/8/8 w - - 0 1",
+            "4k3/8/8/8/8/8/8/4K4 w - - 0 1",
+            "4k3/8/8/8/8/8/8/4K2 w - - 0 1",
+            "4k3/8/8/8/8/8/8/4KX2 w - - 0 1",
+            "4k3/8/8/8/8/8/8/4K3R w - - 0 1",
+            "4k3/8/8/8/8/8/8/4K3 w KK - 0 1",
+            "4k3/8/8/8/8/8/8/4K3 w K - 0 1",
+            "4k3/8/8/8/8/8/8/4K3 w - e3 0 1",
+            "4k3/8/8/8/8/8/8/4K3 w - - -1 1",
+        ];
+
+        for fen in malformed {
+            assert!(Board::from_fen(fen).is_err(), "{}", fen);
+        }
+    }
+
+    #[test]
+    fn test_chess960_start_positions() {
+        let standard = Board::new_chess960(518).unwrap();
+        assert_eq!(standard.to_fen(), Board::new().to_fen());
+        assert!(standard.is_chess960());
+
+        let back_ranks: std::collections::HashSet<String> = (0..960)
+            .map(|index| {
+                let fen = Board::new_chess960(index).unwrap().to_fen();
+                assert_eq!(Board::from_fen(&fen).unwrap().to_fen(), fen);
+                assert!(fen.ends_with(" w KQkq - 0 1"), "{}", fen);
+
+                // Bishops on opposite colors, king between the rooks
+                let back_rank = fen.split('/').next_back().unwrap()[..8].to_string();
+                let bishops: Vec<usize> = back_rank.match_indices('B').map(|(i, _)| i).collect();
+                assert_ne!(bishops[0] % 2, bishops[1] % 2, "{}", fen);
+                let king = back_rank.find('K').unwrap();
+                assert!(
+                    back_rank.find('R').unwrap() < king && back_rank.rfind('R').unwrap() > king
+                );
+                back_rank
+            })
+            .collect();
+        assert_eq!(back_ranks.len(), 960);
+
+        assert_eq!(
+            Board::new_chess960(0).unwrap().to_fen(),
+            "bbqnnrkr/pppppppp/8/8/8/8/PPPPPPPP/BBQNNRKR w KQkq - 0 1"
+        );
+        assert!(Board::new_chess960(960).is_none());
+    }
+
+    #[test]
+    fn test_shredder_and_x_fen_castling() {
+        // Shredder-FEN names every castling rook by file
+        let board =
+            Board::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w HAha - 0 1").unwrap();
+        assert_eq!(board.to_fen(), Board::new().to_fen());
+        assert!(!board.is_chess960());
+
+        // X-FEN uses a file letter only when another rook is further out
+        let fens = [
+            "1r2k1r1/pppppppp/8/8/8/8/PPPPPPPP/1R2K1R1 w KQkq - 0 1",
+            "2k5/8/8/8/8/8/8/RK2R2R w EQ - 0 1",
+            "rk2r2r/8/8/8/8/8/8/2K5 b eq - 0 1",
+        ];
+        for fen in fens {
+            let board = Board::from_fen(fen).unwrap();
+            assert_eq!(board.to_fen(), fen);
+            assert!(board.is_chess960(), "{}", fen);
+        }
+
+        let board = Board::from_fen("2k5/8/8/8/8/8/8/RK2R2R w EQ - 0 1").unwrap();
+        assert_eq!(board.get_castling_rights().rook_file(Color::White, true), 4);
+        assert_eq!(
+            board.get_castling_rights().rook_file(Color::White, false),
+            0
+        );
+
+        for fen in [
+            "2k5/8/8/8/8/8/8/RK2R2R w B - 0 1",
+            "2k5/8/8/8/8/8/8/RK2R2R w C - 0 1",
+            "2k5/8/8/8/8/8/8/RK2R2R w EH - 0 1",
+            "2k5/8/8/8/8/8/8/RK5R w e - 0 1",
+        ] {
+            assert!(Board::from_fen(fen).is_err(), "{}", fen);
+        }
+    }
+
+    #[test]
+    fn test_chess960_castling() {
+        let cases = [
+            // King travels across the back rank, rook hops over it
+            (
+                "2k5/8/8/8/8/8/8/RK2R3 w KQ - 0 1",
+                "b1e1",
+                "2k5/8/8/8/8/8/8/R4RK1 b - - 1 1",
+            ),
+            (
+                "2k5/8/8/8/8/8/8/RK2R3 w KQ - 0 1",
+                "b1a1",
+                "2k5/8/8/8/8/8/8/2KRR3 b - - 1 1",
+            ),
+            // King already on its destination
+            (
+                "4k3/8/8/8/8/8/8/6KR w K - 0 1",
+                "g1h1",
+                "4k3/8/8/8/8/8/8/5RK1 b - - 1 1",
+            ),
+            // Rook already on its destination
+            (
+                "4k3/8/8/8/8/8/8/3R1K2 w Q - 0 1",
+                "f1d1",
+                "4k3/8/8/8/8/8/8/2KR4 b - - 1 1",
+            ),
+        ];
+
+        for (fen, castle, expected) in cases {
+            let mut board = Board::from_fen(fen).unwrap();
+            let chess_move = MoveValidator::generate_legal_moves(&board)
+                .into_iter()
+                .find(|m| m.is_castle && m.to_algebraic() == castle)
+                .unwrap_or_else(|| panic!("{} not generated in {}", castle, fen));
+
+            let undo = board.make_move(&chess_move).unwrap();
+            assert_eq!(board.to_fen(), expected);
+            assert_eq!(
+                board.zobrist_key(),
+                Board::from_fen(expected).unwrap().zobrist_key()
+            );
+
+            board.unmake_move(&undo);
+            assert_eq!(board.to_fen(), fen);
+        }
+    }
+
     #[test]
     fn test_path_clear() {
         let board = Board::new();
@@ -501,4 +1571,137 @@ mod tests {
         let empty_board = Board::empty();
         assert!(empty_board.is_path_clear(rook_pos, target_pos));
     }
+
+    #[test]
+    fn test_make_unmake_restores_position() {
+        let fens = [
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "r2q1rk1/pP1p2pp/Q4n2/bbp1p3/Np6/1B3NBn/pPPP1PPP/R3K2R b KQ - 0 1",
+            "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3",
+            "1r2k1r1/pppppppp/8/8/8/8/PPPPPPPP/1R2K1R1 w KQkq - 0 1",
+            "2k5/8/8/8/8/8/8/RK2R3 w KQ - 0 1",
+            "r1bqkb1r/pppp1ppp/2n2n2/4p3/2B1P3/5N2/PPPP1PPP/RNBQK2R[QPbn] w KQkq - 0 1",
+            "3rk3/8/8/3Q~4/8/8/8/4K3[Rp] b - - 0 1",
+        ];
+
+        for fen in fens {
+            let mut board = Board::from_fen(fen).unwrap();
+            let snapshot: Vec<Option<Piece>> = (0..64)
+                .map(|i| board.get_piece(Position::from_index(i).unwrap()))
+                .collect();
+            let key = board.zobrist_key();
+
+            for chess_move in MoveValidator::generate_legal_moves(&board) {
+                let undo = board.make_move(&chess_move).unwrap();
+                board.unmake_move(&undo);
+
+                let restored: Vec<Option<Piece>> = (0..64)
+                    .map(|i| board.get_piece(Position::from_index(i).unwrap()))
+                    .collect();
+                assert_eq!(board.to_fen(), fen, "{}", chess_move.to_algebraic());
+                assert_eq!(restored, snapshot, "{}", chess_move.to_algebraic());
+                assert_eq!(board.zobrist_key(), key);
+            }
+        }
+    }
+
+    #[test]
+    fn test_crazyhouse_fen() {
+        let fen = "r1bqkb1r/pppp1ppp/2n2n2/4p3/2B1P3/5N2/PPPP1PPP/RNBQK2R[QPbn] w KQkq - 0 1";
+        let board = Board::from_fen(fen).unwrap();
+        let pockets = board.pockets().unwrap();
+        assert_eq!(pockets.count(Color::White, PieceType::Queen), 1);
+        assert_eq!(pockets.count(Color::Black, PieceType::Knight), 1);
+        assert_eq!(board.to_fen(), fen);
+
+        let board = Board::from_fen("4k3/8/8/3Q~4/8/8/8/4K3[] w - - 0 1").unwrap();
+        assert!(board.is_promoted(Position::from_algebraic("d5").unwrap()));
+        assert_eq!(board.to_fen(), "4k3/8/8/3Q~4/8/8/8/4K3[] w - - 0 1");
+
+        // Boards without pockets keep plain FEN
+        assert!(Board::new().pockets().is_none());
+        assert!(!Board::new().to_fen().contains('['));
+        assert!(Board::from_fen("4k3/8/8/8/8/8/8/4K3[K] w - - 0 1").is_err());
+    }
+
+    #[test]
+    fn test_crazyhouse_captures() {
+        let play = |fen: &str, uci: &str| {
+            let mut board = Board::from_fen(fen).unwrap();
+            board
+                .make_move(&Move::from_algebraic(uci).unwrap())
+                .unwrap();
+            board.to_fen()
+        };
+
+        // Captured pieces change sides
+        assert_eq!(
+            play("4k3/8/8/3n4/4P3/8/8/4K3[] w - - 0 1", "e4d5"),
+            "4k3/8/8/3P4/8/8/8/4K3[N] b - - 0 1"
+        );
+
+        // Promoted pieces are marked and go back in hand as pawns
+        assert_eq!(
+            play("4k3/P7/8/8/8/8/8/4K3[] w - - 0 1", "a7a8q"),
+            "Q~3k3/8/8/8/8/8/8/4K3[] b - - 0 1"
+        );
+        assert_eq!(
+            play("3rk3/8/8/3Q~4/8/8/8/4K3[] b - - 0 1", "d8d5"),
+            "4k3/8/8/3r4/8/8/8/4K3[p] w - - 0 2"
+        );
+
+        // Drops come out of the pocket
+        let fen = "4k3/8/8/8/8/8/8/4K3[N] w - - 0 1";
+        let mut board = Board::from_fen(fen).unwrap();
+        let key = board.zobrist_key();
+        let undo = board
+            .make_move(&Move::drop(
+                PieceType::Knight,
+                Position::from_algebraic("f3").unwrap(),
+            ))
+            .unwrap();
+        assert_eq!(board.to_fen(), "4k3/8/8/8/8/5N2/8/4K3[] b - - 1 1");
+        assert_eq!(board.zobrist_key(), board.compute_hash());
+
+        board.unmake_move(&undo);
+        assert_eq!(board.to_fen(), fen);
+        assert_eq!(board.zobrist_key(), key);
+
+        assert!(
+            board
+                .make_move(&Move::drop(
+                    PieceType::Queen,
+                    Position::from_algebraic("d4").unwrap(),
+                ))
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_three_check_fen() {
+        let fen = "rnbqkbnr/pppp1ppp/8/4p3/4P3/8/PPPP1PPP/RNBQKBNR w KQkq - 0 2 +0+0";
+        let mut board = Board::from_fen(fen).unwrap();
+        assert_eq!(board.check_counts(), Some(&CheckCounts::default()));
+        assert_eq!(board.to_fen(), fen);
+        assert!(Board::from_fen("4k3/8/8/8/8/8/8/4K3 w - - 0 1 3+3").is_err());
+
+        // Quiet moves leave the counts alone, checks add to the mover's
+        for uci in ["f1c4", "b8c6"] {
+            board
+                .make_move(&Move::from_algebraic(uci).unwrap())
+                .unwrap();
+        }
+        let key = board.zobrist_key();
+        let undo = board
+            .make_move(&Move::from_algebraic("c4f7").unwrap())
+            .unwrap();
+        assert_eq!(board.check_counts().unwrap().given(Color::White), 1);
+        assert!(board.to_fen().ends_with(" +1+0"));
+        assert_eq!(board.zobrist_key(), board.compute_hash());
+
+        board.unmake_move(&undo);
+        assert_eq!(board.check_counts(), Some(&CheckCounts::default()));
+        assert_eq!(board.zobrist_key(), key);
+        assert_eq!(board.zobrist_key(), board.compute_hash());
+    }
 }
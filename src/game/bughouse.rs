@@ -0,0 +1,403 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::{Color, GameManager, GameResult, Move, Piece, Pockets, Variant};
+
+/// A place at a Bughouse match. Team 0 plays white on board 0 and black on
+/// board 1, team 1 the other two seats.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Seat {
+    pub board: usize,
+    pub color: Color,
+}
+
+impl Seat {
+    pub const ALL: [Seat; 4] = [
+        Seat::new(0, Color::White),
+        Seat::new(0, Color::Black),
+        Seat::new(1, Color::White),
+        Seat::new(1, Color::Black),
+    ];
+
+    pub const fn new(board: usize, color: Color) -> Self {
+        Self { board, color }
+    }
+
+    pub fn team(self) -> usize {
+        match (self.board, self.color) {
+            (0, Color::White) | (1, Color::Black) => 0,
+            _ => 1,
+        }
+    }
+
+    /// The teammate's seat, on the other board with the other color.
+    pub fn partner(self) -> Self {
+        Self::new(1 - self.board, self.color.opposite())
+    }
+}
+
+/// The seats a joining player will take. Unset fields accept any seat.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SeatPreference {
+    pub team: Option<usize>,
+    pub board: Option<usize>,
+    pub color: Option<Color>,
+}
+
+impl SeatPreference {
+    fn accepts(&self, seat: Seat) -> bool {
+        self.team.is_none_or(|team| team == seat.team())
+            && self.board.is_none_or(|board| board == seat.board)
+            && self.color.is_none_or(|color| color == seat.color)
+    }
+}
+
+/// Two linked Bughouse boards, each an ordinary game in the `GameManager`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BughouseMatch {
+    pub id: String,
+    pub boards: [String; 2],
+}
+
+impl BughouseMatch {
+    pub fn board_index(&self, game_id: &str) -> Option<usize> {
+        self.boards.iter().position(|id| id == game_id)
+    }
+
+    /// The board other than `game_id` in this match.
+    pub fn partner_board(&self, game_id: &str) -> Option<&String> {
+        Some(&self.boards[1 - self.board_index(game_id)?])
+    }
+
+    /// Everyone seated on either board.
+    pub fn players(&self, games: &GameManager) -> Vec<String> {
+        self.boards
+            .iter()
+            .filter_map(|id| games.get_game(id))
+            .flat_map(|game| [game.white_player.clone(), game.black_player.clone()])
+            .flatten()
+            .collect()
+    }
+}
+
+/// What a move on one board did to the rest of the match.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BughouseMoveOutcome {
+    /// The captured piece, now in the partner's pocket on the other board.
+    pub transferred: Option<Piece>,
+    /// Whether the match is over, on both boards.
+    pub finished: bool,
+}
+
+/// Links pairs of games into Bughouse matches: seats players, passes
+/// captures across to the partner board and ends both boards together.
+#[derive(Debug, Default)]
+pub struct BughouseManager {
+    matches: HashMap<String, BughouseMatch>,
+    board_matches: HashMap<String, String>, // Game ID -> match ID
+}
+
+impl BughouseManager {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn create_match(&mut self, games: &mut GameManager) -> Result<String, String> {
+        let boards = [
+            games.create_game_with_variant(Variant::Bughouse, None)?,
+            games.create_game_with_variant(Variant::Bughouse, None)?,
+        ];
+        let id = Uuid::new_v4().to_string();
+
+        for board in &boards {
+            self.board_matches.insert(board.clone(), id.clone());
+        }
+        self.matches.insert(
+            id.clone(),
+            BughouseMatch {
+                id: id.clone(),
+                boards,
+            },
+        );
+        Ok(id)
+    }
+
+    pub fn get_match(&self, match_id: &str) -> Option<&BughouseMatch> {
+        self.matches.get(match_id)
+    }
+
+    /// The match `id` names, either directly or through one of its boards.
+    pub fn find_match(&self, id: &str) -> Option<&BughouseMatch> {
+        self.matches
+            .get(id)
+            .or_else(|| self.matches.get(self.board_matches.get(id)?))
+    }
+
+    pub fn match_for_game(&self, game_id: &str) -> Option<&BughouseMatch> {
+        self.matches.get(self.board_matches.get(game_id)?)
+    }
+
+    /// Seats a player at the first free seat they accept, returning the game
+    /// they play in and where they sit.
+    pub fn join_match(
+        &self,
+        games: &mut GameManager,
+        match_id: &str,
+        player_id: String,
+        preference: SeatPreference,
+    ) -> Result<(String, Seat), String> {
+        let bughouse = self.find_match(match_id).ok_or("Match not found")?;
+
+        let seat = Seat::ALL
+            .into_iter()
+            .filter(|&seat| preference.accepts(seat))
+            .find(|seat| {
+                games
+                    .get_game(&bughouse.boards[seat.board])
+                    .is_some_and(|game| match seat.color {
+                        Color::White => game.white_player.is_none(),
+                        Color::Black => game.black_player.is_none(),
+                    })
+            })
+            .ok_or("No free seat matches the request")?;
+
+        let game_id = bughouse.boards[seat.board].clone();
+        games.join_game(&game_id, player_id, Some(seat.color))?;
+        Ok((game_id, seat))
+    }
+
+    /// Plays a move on one board, handing any capture to the partner board.
+    /// The mover is given back up to `lag_ms` of network lag.
+    pub fn make_move(
+        &self,
+        games: &mut GameManager,
+        game_id: &str,
+        player_id: &str,
+        chess_move: Move,
+        lag_ms: u64,
+    ) -> Result<BughouseMoveOutcome, String> {
+        let bughouse = self
+            .match_for_game(game_id)
+            .ok_or("Game is not part of a Bughouse match")?;
+        let board = bughouse.board_index(game_id).ok_or("Game not found")?;
+
+        let game = games.get_game_mut(game_id).ok_or("Game not found")?;
+        let mover = game.board.get_to_move();
+        let before = game.board.pockets().copied().unwrap_or_default();
+        game.make_move_with_lag(player_id, chess_move, lag_ms)?;
+
+        // The board pockets captures for the mover; they belong to the
+        // partner, who plays the captured piece's color
+        let mut pockets = game.board.pockets().copied().unwrap_or_default();
+        let captured = Pockets::PIECES
+            .into_iter()
+            .find(|&piece_type| pockets.count(mover, piece_type) > before.count(mover, piece_type));
+
+        let transferred = match captured {
+            Some(piece_type) => {
+                pockets.take(mover, piece_type);
+                game.set_pockets(pockets);
+
+                let partner = games
+                    .get_game_mut(&bughouse.boards[1 - board])
+                    .ok_or("Partner board not found")?;
+                let mut partner_pockets = partner.board.pockets().copied().unwrap_or_default();
+                partner_pockets.add(mover.opposite(), piece_type);
+                partner.set_pockets(partner_pockets);
+
+                Some(Piece::new(piece_type, mover.opposite()))
+            }
+            None => None,
+        };
+
+        let finished = self.sync_result(games, game_id)?;
+        Ok(BughouseMoveOutcome {
+            transferred,
+            finished,
+        })
+    }
+
+    /// Ends the partner board once `game_id` is over, however it ended.
+    /// Returns whether the match is over.
+    pub fn sync_result(&self, games: &mut GameManager, game_id: &str) -> Result<bool, String> {
+        let bughouse = self
+            .match_for_game(game_id)
+            .ok_or("Game is not part of a Bughouse match")?;
+        let board = bughouse.board_index(game_id).ok_or("Game not found")?;
+
+        // The partner board's winner is the teammate of this board's winner,
+        // who plays the other color there
+        let partner_result = match games.get_game(game_id).ok_or("Game not found")?.result {
+            GameResult::Ongoing => return Ok(false),
+            GameResult::PartnerBoard(_) => return Ok(true),
+            GameResult::Checkmate(winner) | GameResult::VariantWin(winner, _) => {
+                Some(winner.opposite())
+            }
+            GameResult::Resignation(loser) | GameResult::Timeout(loser) => Some(loser),
+            GameResult::Stalemate | GameResult::Draw(_) => None,
+        };
+
+        let partner = games
+            .get_game_mut(&bughouse.boards[1 - board])
+            .ok_or("Partner board not found")?;
+        if partner.result == GameResult::Ongoing {
+            partner.result = GameResult::PartnerBoard(partner_result);
+            partner.stop_clock();
+        }
+        Ok(true)
+    }
+
+    /// Forgets matches whose boards the `GameManager` has cleaned up.
+    pub fn cleanup(&mut self, games: &GameManager) {
+        self.matches.retain(|_, bughouse| {
+            bughouse
+                .boards
+                .iter()
+                .any(|id| games.get_game(id).is_some())
+        });
+        let matches = &self.matches;
+        self.board_matches
+            .retain(|_, match_id| matches.contains_key(match_id));
+    }
+
+    pub fn get_match_count(&self) -> usize {
+        self.matches.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::PieceType;
+
+    fn seated_match(games: &mut GameManager) -> (BughouseManager, BughouseMatch) {
+        let mut manager = BughouseManager::new();
+        let match_id = manager.create_match(games).unwrap();
+        for player in ["a", "b", "c", "d"] {
+            manager
+                .join_match(
+                    games,
+                    &match_id,
+                    player.to_string(),
+                    SeatPreference::default(),
+                )
+                .unwrap();
+        }
+        let bughouse = manager.get_match(&match_id).unwrap().clone();
+        (manager, bughouse)
+    }
+
+    fn play(manager: &BughouseManager, games: &mut GameManager, game_id: &str, uci: &str) {
+        let game = games.get_game(game_id).unwrap();
+        let player = game.get_current_player().unwrap().clone();
+        manager
+            .make_move(
+                games,
+                game_id,
+                &player,
+                Move::from_algebraic(uci).unwrap(),
+                0,
+            )
+            .unwrap();
+    }
+
+    #[test]
+    fn test_seats() {
+        assert_eq!(Seat::new(0, Color::White).team(), 0);
+        assert_eq!(Seat::new(1, Color::Black).team(), 0);
+        assert_eq!(Seat::new(0, Color::Black).team(), 1);
+        assert_eq!(
+            Seat::new(0, Color::White).partner(),
+            Seat::new(1, Color::Black)
+        );
+
+        let mut games = GameManager::new();
+        let mut manager = BughouseManager::new();
+        let match_id = manager.create_match(&mut games).unwrap();
+        let team_one = SeatPreference {
+            team: Some(1),
+            board: Some(1),
+            color: None,
+        };
+
+        let (game_id, seat) = manager
+            .join_match(&mut games, &match_id, "a".to_string(), team_one)
+            .unwrap();
+        assert_eq!(seat, Seat::new(1, Color::White));
+        assert_eq!(manager.match_for_game(&game_id).unwrap().id, match_id);
+        assert!(
+            manager
+                .join_match(&mut games, &game_id, "b".to_string(), team_one)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_captures_feed_partner() {
+        let mut games = GameManager::new();
+        let (manager, bughouse) = seated_match(&mut games);
+        let [first, second] = &bughouse.boards;
+
+        for uci in ["e2e4", "d7d5", "e4d5"] {
+            play(&manager, &mut games, first, uci);
+        }
+
+        // White took a black pawn on the first board, so black gets it on
+        // the second
+        let pockets = |games: &GameManager, id: &str| {
+            games
+                .get_game(id)
+                .unwrap()
+                .board
+                .pockets()
+                .copied()
+                .unwrap()
+        };
+        assert!(pockets(&games, first).is_empty(Color::White));
+        assert_eq!(
+            pockets(&games, second).count(Color::Black, PieceType::Pawn),
+            1
+        );
+
+        play(&manager, &mut games, second, "e2e4");
+        let black = games
+            .get_game(second)
+            .unwrap()
+            .black_player
+            .clone()
+            .unwrap();
+        manager
+            .make_move(
+                &mut games,
+                second,
+                &black,
+                Move::from_algebraic("P@d3").unwrap(),
+                0,
+            )
+            .unwrap();
+        assert!(pockets(&games, second).is_empty(Color::Black));
+    }
+
+    #[test]
+    fn test_match_ends_on_both_boards() {
+        let mut games = GameManager::new();
+        let (manager, bughouse) = seated_match(&mut games);
+        let [first, second] = &bughouse.boards;
+
+        for uci in ["f2f3", "e7e5", "g2g4", "d8h4"] {
+            play(&manager, &mut games, first, uci);
+        }
+
+        assert_eq!(
+            games.get_game(first).unwrap().result,
+            GameResult::Checkmate(Color::Black)
+        );
+        assert_eq!(
+            games.get_game(second).unwrap().result,
+            GameResult::PartnerBoard(Some(Color::White))
+        );
+        assert_eq!(bughouse.players(&games).len(), 4);
+    }
+}
@@ -0,0 +1,351 @@
+use serde::{Deserialize, Serialize};
+
+use super::{Color, LagLimits, TimeControl, TimeControlStage};
+
+/// How a clock gives time back for each move.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ClockMode {
+    /// Fischer: the increment is added after every move.
+    #[default]
+    Increment,
+    /// Bronstein delay: the time a move took is given back, up to the delay.
+    Bronstein,
+    /// Simple (US) delay: the clock only counts down once the delay has
+    /// passed on each move.
+    SimpleDelay,
+}
+
+/// A two-sided chess clock. Times are unix milliseconds passed in by the
+/// caller, so the clock itself never reads the system time.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Clock {
+    pub time_control: TimeControl,
+    stages: Vec<TimeControlStage>,
+    remaining_ms: [u64; 2],
+    /// Moves each side has made, which decide the stage they are in.
+    moves: [u32; 2],
+    /// The side whose clock is running, and since when.
+    running: Option<(Color, u64)>,
+    /// The mover's remaining time after each move, as PGN `[%clk]` records it.
+    pub history: Vec<u64>,
+    /// How long the last move took on the clock.
+    pub last_move_ms: Option<u64>,
+    /// How much network lag is given back, if any is.
+    #[serde(default)]
+    lag_limits: Option<LagLimits>,
+    /// Lag each side may still have given back.
+    #[serde(default)]
+    lag_quota_ms: [u64; 2],
+    /// The lag given back for the last move, with lag compensation on.
+    #[serde(default)]
+    pub last_lag_credit_ms: Option<u64>,
+}
+
+impl Clock {
+    pub fn new(time_control: TimeControl) -> Self {
+        let stages = time_control.stages();
+        let initial_ms = u64::from(stages[0].time_secs) * 1000;
+        Self {
+            time_control,
+            stages,
+            remaining_ms: [initial_ms; 2],
+            moves: [0; 2],
+            running: None,
+            history: Vec::new(),
+            last_move_ms: None,
+            lag_limits: None,
+            lag_quota_ms: [0; 2],
+            last_lag_credit_ms: None,
+        }
+    }
+
+    /// Gives moves back the network lag they had, within `limits`.
+    pub fn with_lag_compensation(mut self, limits: LagLimits) -> Self {
+        self.lag_limits = Some(limits);
+        self.lag_quota_ms = [limits.initial_quota_ms; 2];
+        self
+    }
+
+    /// Starts `color`'s clock, unless one is already running.
+    pub fn start(&mut self, color: Color, now: u64) {
+        if self.running.is_none() {
+            self.running = Some((color, now));
+        }
+    }
+
+    /// The side whose clock is running.
+    pub fn active(&self) -> Option<Color> {
+        self.running.map(|(color, _)| color)
+    }
+
+    /// `color`'s time left at `now`, with the running side's thinking time
+    /// taken off.
+    pub fn remaining_ms(&self, color: Color, now: u64) -> u64 {
+        let remaining = self.remaining_ms[color.index()];
+        match self.running {
+            Some((active, since)) if active == color => {
+                remaining.saturating_sub(self.charge(active, now.saturating_sub(since)))
+            }
+            _ => remaining,
+        }
+    }
+
+    /// How long the running side has been on the move, or `None` when the
+    /// clock is stopped.
+    pub fn running_ms(&self, now: u64) -> Option<u64> {
+        let (_, since) = self.running?;
+        Some(now.saturating_sub(since))
+    }
+
+    /// How long until the running side's flag falls, or `None` when the
+    /// clock is stopped. With lag compensation on, the flag waits for as
+    /// much lag as a move still in flight could be given back.
+    pub fn time_to_flag(&self, now: u64) -> Option<u64> {
+        self.time_to_flag_with_lag(now, u64::MAX)
+    }
+
+    /// The side whose flag has fallen at `now`, if any.
+    pub fn flagged(&self, now: u64) -> Option<Color> {
+        self.flagged_with_lag(now, u64::MAX)
+    }
+
+    /// The side whose flag has fallen at `now`, if any, once a move with
+    /// `lag_ms` of lag has been given back what it may be.
+    pub fn flagged_with_lag(&self, now: u64, lag_ms: u64) -> Option<Color> {
+        match self.time_to_flag_with_lag(now, lag_ms)? {
+            0 => self.active(),
+            _ => None,
+        }
+    }
+
+    fn time_to_flag_with_lag(&self, now: u64, lag_ms: u64) -> Option<u64> {
+        let (active, since) = self.running?;
+        let mut budget = self.remaining_ms[active.index()] + self.lag_credit(active, lag_ms);
+        if self.time_control.mode == ClockMode::SimpleDelay {
+            budget += self.increment_ms(active);
+        }
+        Some(budget.saturating_sub(now.saturating_sub(since)))
+    }
+
+    /// Ends the running side's move: charges it the time taken, gives time
+    /// back as the mode says, adds the next stage's time if the move
+    /// completed a stage, and starts the other side's clock.
+    pub fn press(&mut self, now: u64) {
+        self.press_with_lag(now, 0);
+    }
+
+    /// Like `press`, but first takes off the time the move took as much of
+    /// `lag_ms` as the lag limits let it, drawing on the mover's quota.
+    pub fn press_with_lag(&mut self, now: u64, lag_ms: u64) {
+        let Some((active, since)) = self.running else {
+            return;
+        };
+        let credit = self.lag_credit(active, lag_ms);
+        if let Some(limits) = self.lag_limits {
+            let quota = &mut self.lag_quota_ms[active.index()];
+            *quota = limits.quota_after(*quota, credit);
+            self.last_lag_credit_ms = Some(credit);
+        }
+
+        let elapsed = now.saturating_sub(since).saturating_sub(credit);
+        let charge = self.charge(active, elapsed);
+        let increment_ms = self.increment_ms(active);
+        let refund = match self.time_control.mode {
+            ClockMode::Increment => increment_ms,
+            ClockMode::Bronstein => elapsed.min(increment_ms),
+            ClockMode::SimpleDelay => 0,
+        };
+
+        self.moves[active.index()] += 1;
+        let next_stage_ms = self
+            .stage_starting_after(self.moves[active.index()])
+            .map_or(0, |stage| u64::from(stage.time_secs) * 1000);
+
+        let remaining = &mut self.remaining_ms[active.index()];
+        *remaining = remaining.saturating_sub(charge) + refund + next_stage_ms;
+
+        self.history.push(*remaining);
+        self.last_move_ms = Some(elapsed);
+        self.running = Some((active.opposite(), now));
+    }
+
+    /// Stops the clock and forgets the last move's entry, as when a move is
+    /// taken back. Time already used, or added, stays.
+    pub fn take_back(&mut self, now: u64, mover: Color) {
+        self.stop(now);
+        self.history.pop();
+        let moves = &mut self.moves[mover.index()];
+        *moves = moves.saturating_sub(1);
+    }
+
+    /// Stops the clock, charging the running side for the time it used.
+    pub fn stop(&mut self, now: u64) {
+        if let Some((active, since)) = self.running.take() {
+            let charge = self.charge(active, now.saturating_sub(since));
+            let remaining = &mut self.remaining_ms[active.index()];
+            *remaining = remaining.saturating_sub(charge);
+        }
+    }
+
+    /// The part of `lag_ms` that `color`'s next move may be given back.
+    fn lag_credit(&self, color: Color, lag_ms: u64) -> u64 {
+        self.lag_limits.map_or(0, |limits| {
+            limits.credit(self.lag_quota_ms[color.index()], lag_ms)
+        })
+    }
+
+    /// The part of `elapsed` that comes off `color`'s clock.
+    fn charge(&self, color: Color, elapsed: u64) -> u64 {
+        match self.time_control.mode {
+            ClockMode::SimpleDelay => elapsed.saturating_sub(self.increment_ms(color)),
+            ClockMode::Increment | ClockMode::Bronstein => elapsed,
+        }
+    }
+
+    /// The increment or delay for `color`'s next move, from the stage that
+    /// move falls in.
+    fn increment_ms(&self, color: Color) -> u64 {
+        let mut moves_before = 0;
+        let made = self.moves[color.index()];
+        let stage = self
+            .stages
+            .iter()
+            .find(|stage| match stage.moves {
+                Some(moves) => {
+                    moves_before += moves;
+                    made < moves_before
+                }
+                None => true,
+            })
+            .or(self.stages.last());
+        stage.map_or(0, |stage| u64::from(stage.increment_secs) * 1000)
+    }
+
+    /// The stage that begins once a side has made `made` moves, if one does.
+    /// A last stage with a move count starts over each time it is done.
+    fn stage_starting_after(&self, made: u32) -> Option<&TimeControlStage> {
+        let mut moves_before = 0;
+        for (i, stage) in self.stages.iter().enumerate() {
+            moves_before += stage.moves?;
+            if made == moves_before {
+                return self.stages.get(i + 1).or(Some(stage));
+            }
+            if made < moves_before {
+                return None;
+            }
+        }
+
+        let repeating = self.stages.last()?.moves?;
+        match (made - moves_before) % repeating {
+            0 => self.stages.last(),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn clock_with(mode: ClockMode, initial_secs: u32, increment_secs: u32) -> Clock {
+        TimeControl {
+            mode,
+            ..TimeControl::new(initial_secs, increment_secs)
+        }
+        .clock()
+    }
+
+    #[test]
+    fn test_fischer_increment() {
+        let mut clock = clock_with(ClockMode::Increment, 60, 2);
+        assert_eq!(clock.time_to_flag(0), None);
+
+        clock.start(Color::White, 0);
+        assert_eq!(clock.remaining_ms(Color::White, 5_000), 55_000);
+        clock.press(5_000);
+        assert_eq!(clock.remaining_ms(Color::White, 9_000), 57_000);
+        assert_eq!(clock.remaining_ms(Color::Black, 9_000), 56_000);
+        assert_eq!(clock.active(), Some(Color::Black));
+        assert_eq!(clock.history, [57_000]);
+        assert_eq!(clock.last_move_ms, Some(5_000));
+    }
+
+    #[test]
+    fn test_delays() {
+        // Bronstein gives back the time used, up to the delay
+        let mut clock = clock_with(ClockMode::Bronstein, 60, 3);
+        clock.start(Color::White, 0);
+        clock.press(2_000);
+        clock.press(7_000);
+        assert_eq!(clock.remaining_ms(Color::White, 7_000), 60_000);
+        assert_eq!(clock.remaining_ms(Color::Black, 7_000), 58_000);
+
+        // Simple delay only starts counting after the delay
+        let mut clock = clock_with(ClockMode::SimpleDelay, 60, 3);
+        clock.start(Color::White, 0);
+        assert_eq!(clock.remaining_ms(Color::White, 2_000), 60_000);
+        assert_eq!(clock.time_to_flag(2_000), Some(61_000));
+        clock.press(2_000);
+        clock.press(7_000);
+        assert_eq!(clock.remaining_ms(Color::White, 7_000), 60_000);
+        assert_eq!(clock.remaining_ms(Color::Black, 7_000), 58_000);
+    }
+
+    #[test]
+    fn test_stages() {
+        // Two moves in 10 seconds, then 5 more seconds for the rest, +1s
+        let time_control = TimeControl::from_pgn("", "2/10+1:5+1").unwrap();
+        let mut clock = time_control.clock();
+        clock.start(Color::White, 0);
+        for now in [1_000, 2_000, 3_000, 4_000] {
+            clock.press(now);
+        }
+        // White used 2s over two moves, got 2s back and the second stage
+        assert_eq!(clock.remaining_ms(Color::White, 4_000), 15_000);
+        clock.press(5_000);
+        assert_eq!(clock.remaining_ms(Color::White, 5_000), 15_000);
+
+        // A last stage with a move count repeats
+        let mut clock = TimeControl::from_pgn("", "1/10").unwrap().clock();
+        clock.start(Color::White, 0);
+        clock.press(4_000);
+        clock.press(4_000);
+        clock.press(8_000);
+        assert_eq!(clock.remaining_ms(Color::White, 8_000), 22_000);
+    }
+
+    #[test]
+    fn test_flag_fall() {
+        let mut clock = clock_with(ClockMode::Increment, 10, 0);
+        clock.start(Color::White, 0);
+        assert_eq!(clock.time_to_flag(4_000), Some(6_000));
+        assert_eq!(clock.flagged(9_999), None);
+        assert_eq!(clock.flagged(10_000), Some(Color::White));
+
+        clock.stop(3_000);
+        assert_eq!(clock.flagged(20_000), None);
+        assert_eq!(clock.remaining_ms(Color::White, 20_000), 7_000);
+    }
+
+    #[test]
+    fn test_lag_compensation() {
+        let limits = LagLimits::default();
+        let mut clock = clock_with(ClockMode::Increment, 10, 0).with_lag_compensation(limits);
+        clock.start(Color::White, 0);
+
+        // The flag waits for a move that may still be on its way
+        assert_eq!(clock.time_to_flag(0), Some(11_000));
+        assert_eq!(clock.flagged_with_lag(10_500, 0), Some(Color::White));
+        assert_eq!(clock.flagged_with_lag(10_500, 600), None);
+
+        clock.press_with_lag(4_000, 300);
+        assert_eq!(clock.remaining_ms(Color::White, 4_000), 6_300);
+        assert_eq!(clock.last_move_ms, Some(3_700));
+        assert_eq!(clock.last_lag_credit_ms, Some(300));
+
+        // Black's quota caps what a move with a lot of lag gets back
+        clock.press_with_lag(9_000, 5_000);
+        assert_eq!(clock.remaining_ms(Color::Black, 9_000), 6_000);
+        assert_eq!(clock.time_to_flag(9_000), Some(6_300 + 950));
+    }
+}
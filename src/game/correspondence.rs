@@ -0,0 +1,341 @@
+use std::collections::{BTreeSet, HashMap};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use super::{GameResult, GameState};
+
+const SECS_PER_DAY: u64 = 86_400;
+
+/// Correspondence play: each side has `days_per_move` days for every move,
+/// and players come and go between moves.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Correspondence {
+    pub days_per_move: u32,
+    /// When the side to move has to have moved by, in unix seconds. Unset
+    /// until both players are in, and once the game is over.
+    pub deadline: Option<u64>,
+}
+
+impl Correspondence {
+    pub fn new(days_per_move: u32) -> Self {
+        Self {
+            days_per_move,
+            deadline: None,
+        }
+    }
+
+    /// Gives the side to move a fresh `days_per_move` from `now`.
+    pub fn start(&mut self, now: u64) {
+        self.deadline = Some(now + u64::from(self.days_per_move) * SECS_PER_DAY);
+    }
+
+    pub fn stop(&mut self) {
+        self.deadline = None;
+    }
+
+    pub fn is_overdue(&self, now: u64) -> bool {
+        self.deadline.is_some_and(|deadline| now >= deadline)
+    }
+}
+
+/// Correspondence games ordered by deadline, so the server only has to
+/// wake up for the next one due.
+#[derive(Debug, Default)]
+pub struct DeadlineScheduler {
+    deadlines: HashMap<String, u64>,
+    queue: BTreeSet<(u64, String)>,
+}
+
+impl DeadlineScheduler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Tracks `game`'s current deadline, replacing any earlier one, or
+    /// forgets the game once it has none.
+    pub fn schedule(&mut self, game: &GameState) {
+        if let Some(deadline) = self.deadlines.remove(&game.id) {
+            self.queue.remove(&(deadline, game.id.clone()));
+        }
+
+        let deadline = match game.result {
+            GameResult::Ongoing => game.correspondence.and_then(|c| c.deadline),
+            _ => None,
+        };
+        if let Some(deadline) = deadline {
+            self.deadlines.insert(game.id.clone(), deadline);
+            self.queue.insert((deadline, game.id.clone()));
+        }
+    }
+
+    /// The earliest deadline still pending.
+    pub fn next_deadline(&self) -> Option<u64> {
+        self.queue.first().map(|(deadline, _)| *deadline)
+    }
+
+    /// Removes and returns the games whose deadline has passed at `now`.
+    pub fn take_due(&mut self, now: u64) -> Vec<String> {
+        let mut due = Vec::new();
+        while let Some((deadline, _)) = self.queue.first() {
+            if *deadline > now {
+                break;
+            }
+            let (_, game_id) = self.queue.pop_first().unwrap();
+            self.deadlines.remove(&game_id);
+            due.push(game_id);
+        }
+        due
+    }
+
+    pub fn len(&self) -> usize {
+        self.queue.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.queue.is_empty()
+    }
+}
+
+/// A correspondence game as stored, with its players' names, so their seats
+/// can be given back to them after a restart.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StoredGame {
+    pub game: GameState,
+    pub white_name: Option<String>,
+    pub black_name: Option<String>,
+}
+
+/// Correspondence games on disk, one JSON file per game, along with the
+/// players' queued "your move" notifications.
+#[derive(Debug, Clone)]
+pub struct GameStore {
+    dir: PathBuf,
+}
+
+impl GameStore {
+    pub fn open<P: AsRef<Path>>(dir: P) -> io::Result<Self> {
+        let dir = dir.as_ref().to_path_buf();
+        fs::create_dir_all(dir.join("games"))?;
+        Ok(Self { dir })
+    }
+
+    pub fn save(&self, stored: &StoredGame) -> io::Result<()> {
+        let path = self
+            .dir
+            .join("games")
+            .join(format!("{}.json", stored.game.id));
+        write_json(&path, stored)
+    }
+
+    pub fn load_all(&self) -> io::Result<Vec<StoredGame>> {
+        let mut games = Vec::new();
+        for entry in fs::read_dir(self.dir.join("games"))? {
+            let path = entry?.path();
+            if path.extension().is_some_and(|ext| ext == "json") {
+                games.push(read_json(&path)?);
+            }
+        }
+        Ok(games)
+    }
+
+    pub fn save_inbox(&self, inbox: &HashMap<String, Vec<String>>) -> io::Result<()> {
+        write_json(&self.dir.join("inbox.json"), inbox)
+    }
+
+    pub fn load_inbox(&self) -> io::Result<HashMap<String, Vec<String>>> {
+        let path = self.dir.join("inbox.json");
+        match path.exists() {
+            true => read_json(&path),
+            false => Ok(HashMap::new()),
+        }
+    }
+}
+
+/// Writes through a temporary file, so a crash never leaves half a file.
+fn write_json<T: Serialize>(path: &Path, value: &T) -> io::Result<()> {
+    let json = serde_json::to_vec(value).map_err(io::Error::other)?;
+    let tmp = path.with_extension("json.tmp");
+    fs::write(&tmp, json)?;
+    fs::rename(tmp, path)
+}
+
+fn read_json<T: for<'de> Deserialize<'de>>(path: &Path) -> io::Result<T> {
+    let json = fs::read(path)?;
+    serde_json::from_slice(&json).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+/// Keeps correspondence games going while nobody is connected: stores them,
+/// tracks their deadlines, and holds "your move" notifications for players
+/// who are away. Without a store, games only last until a restart.
+#[derive(Debug, Default)]
+pub struct CorrespondenceManager {
+    store: Option<GameStore>,
+    deadlines: DeadlineScheduler,
+    /// Player ID -> games where it became their move while they were away.
+    inbox: HashMap<String, Vec<String>>,
+}
+
+impl CorrespondenceManager {
+    pub fn new(store: Option<GameStore>) -> Self {
+        Self {
+            store,
+            ..Self::default()
+        }
+    }
+
+    /// Reads back every stored game and queued notification, returning the
+    /// games for the caller to host again.
+    pub fn load(&mut self) -> io::Result<Vec<StoredGame>> {
+        let Some(store) = &self.store else {
+            return Ok(Vec::new());
+        };
+
+        let games = store.load_all()?;
+        self.inbox = store.load_inbox()?;
+        for stored in &games {
+            self.deadlines.schedule(&stored.game);
+        }
+        Ok(games)
+    }
+
+    /// Records `game`'s latest state and deadline.
+    pub fn save(
+        &mut self,
+        game: &GameState,
+        white_name: Option<String>,
+        black_name: Option<String>,
+    ) -> io::Result<()> {
+        self.deadlines.schedule(game);
+        match &self.store {
+            Some(store) => store.save(&StoredGame {
+                game: game.clone(),
+                white_name,
+                black_name,
+            }),
+            None => Ok(()),
+        }
+    }
+
+    pub fn next_deadline(&self) -> Option<u64> {
+        self.deadlines.next_deadline()
+    }
+
+    pub fn take_due(&mut self, now: u64) -> Vec<String> {
+        self.deadlines.take_due(now)
+    }
+
+    /// Holds a "your move" notice for `game_id` until `player_id` is back.
+    pub fn queue_your_move(&mut self, player_id: &str, game_id: &str) -> io::Result<()> {
+        let queued = self.inbox.entry(player_id.to_string()).or_default();
+        if queued.iter().any(|id| id == game_id) {
+            return Ok(());
+        }
+        queued.push(game_id.to_string());
+        self.save_inbox()
+    }
+
+    /// Hands over the games `player_id` was told about while away.
+    pub fn take_inbox(&mut self, player_id: &str) -> io::Result<Vec<String>> {
+        let Some(game_ids) = self.inbox.remove(player_id) else {
+            return Ok(Vec::new());
+        };
+        self.save_inbox()?;
+        Ok(game_ids)
+    }
+
+    fn save_inbox(&self) -> io::Result<()> {
+        match &self.store {
+            Some(store) => store.save_inbox(&self.inbox),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::{Color, Move, Position};
+    use tempfile::TempDir;
+
+    fn correspondence_game(days_per_move: u32) -> GameState {
+        let mut game = GameState::new();
+        game.set_correspondence(days_per_move);
+        game.add_player("white_player".to_string(), None).unwrap();
+        game.add_player("black_player".to_string(), None).unwrap();
+        game
+    }
+
+    #[test]
+    fn test_days_per_move() {
+        let mut game = GameState::new();
+        game.set_correspondence(2);
+        game.add_player("white_player".to_string(), None).unwrap();
+        assert_eq!(game.correspondence.unwrap().deadline, None);
+        game.add_player("black_player".to_string(), None).unwrap();
+        let deadline = game.correspondence.unwrap().deadline.unwrap();
+        assert!(deadline >= game.created_at + 2 * SECS_PER_DAY);
+
+        let e2 = Position::from_algebraic("e2").unwrap();
+        let e4 = Position::from_algebraic("e4").unwrap();
+        game.make_move("white_player", Move::new(e2, e4)).unwrap();
+        assert!(game.correspondence.unwrap().deadline.unwrap() >= deadline);
+
+        // Black lets the deadline pass
+        game.correspondence.as_mut().unwrap().deadline = Some(game.created_at);
+        assert!(game.check_flag());
+        assert_eq!(game.result, GameResult::Timeout(Color::Black));
+        assert_eq!(game.correspondence.unwrap().deadline, None);
+    }
+
+    #[test]
+    fn test_deadline_scheduler() {
+        let mut scheduler = DeadlineScheduler::new();
+        let mut slow = correspondence_game(3);
+        let fast = correspondence_game(1);
+        scheduler.schedule(&slow);
+        scheduler.schedule(&fast);
+        assert_eq!(scheduler.len(), 2);
+        assert_eq!(
+            scheduler.next_deadline(),
+            fast.correspondence.unwrap().deadline
+        );
+
+        let fast_deadline = fast.correspondence.unwrap().deadline.unwrap();
+        assert_eq!(scheduler.take_due(fast_deadline - 1), Vec::<String>::new());
+        assert_eq!(scheduler.take_due(fast_deadline), vec![fast.id.clone()]);
+
+        // Finished games drop out
+        slow.resign("white_player").unwrap();
+        scheduler.schedule(&slow);
+        assert!(scheduler.is_empty());
+    }
+
+    #[test]
+    fn test_store_round_trip() {
+        let dir = TempDir::new().unwrap();
+        let game = correspondence_game(2);
+
+        let mut manager = CorrespondenceManager::new(Some(GameStore::open(dir.path()).unwrap()));
+        manager
+            .save(&game, Some("Alice".to_string()), Some("Bob".to_string()))
+            .unwrap();
+        manager.queue_your_move("white_player", &game.id).unwrap();
+
+        let mut restarted = CorrespondenceManager::new(Some(GameStore::open(dir.path()).unwrap()));
+        let games = restarted.load().unwrap();
+        assert_eq!(games.len(), 1);
+        assert_eq!(games[0].game.id, game.id);
+        assert_eq!(games[0].game.correspondence, game.correspondence);
+        assert_eq!(games[0].white_name.as_deref(), Some("Alice"));
+        assert_eq!(
+            restarted.next_deadline(),
+            game.correspondence.unwrap().deadline
+        );
+
+        assert_eq!(restarted.take_inbox("white_player").unwrap(), [game.id]);
+        assert!(restarted.take_inbox("white_player").unwrap().is_empty());
+    }
+}
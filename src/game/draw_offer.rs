@@ -0,0 +1,161 @@
+use serde::{Deserialize, Serialize};
+
+use super::Color;
+
+/// Moves a player has to make after offering a draw before they may offer
+/// another, so offers cannot be used to pester the opponent.
+pub const DRAW_OFFER_COOLDOWN_MOVES: usize = 5;
+
+/// How long a draw offer stands unanswered before it lapses, in games not
+/// played by correspondence.
+pub const DRAW_OFFER_LIFETIME_MS: u64 = 2 * 60 * 1000;
+
+/// A draw offer waiting on the opponent.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DrawOffer {
+    pub from: Color,
+    /// Plies played when the offer was made.
+    pub ply: usize,
+    pub message: Option<String>,
+    /// When the offer was made, in unix milliseconds.
+    #[serde(default)]
+    pub made_at: u64,
+}
+
+/// A game's draw offers: the one standing, if any, and when each side last
+/// offered. An offer stands until it is answered, its maker moves again or
+/// it goes unanswered for the game's offer lifetime.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DrawOffers {
+    pub pending: Option<DrawOffer>,
+    last_offer_ply: [Option<usize>; 2],
+}
+
+impl DrawOffers {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records `from`'s offer, made at `now` with `ply` plies played.
+    pub fn offer(
+        &mut self,
+        from: Color,
+        ply: usize,
+        message: Option<String>,
+        now: u64,
+    ) -> Result<(), String> {
+        if let Some(pending) = &self.pending {
+            return Err(match pending.from == from {
+                true => "Draw offer already pending".to_string(),
+                false => "Opponent's draw offer is pending".to_string(),
+            });
+        }
+
+        if let Some(last_ply) = self.last_offer_ply[from.index()] {
+            let moves_since = ply.saturating_sub(last_ply) / 2;
+            if moves_since < DRAW_OFFER_COOLDOWN_MOVES {
+                return Err(format!(
+                    "Make {} more moves before offering another draw",
+                    DRAW_OFFER_COOLDOWN_MOVES - moves_since
+                ));
+            }
+        }
+
+        self.last_offer_ply[from.index()] = Some(ply);
+        self.pending = Some(DrawOffer {
+            from,
+            ply,
+            message,
+            made_at: now,
+        });
+        Ok(())
+    }
+
+    /// Takes the offer standing to `to`, for them to answer.
+    pub fn take_offer_to(&mut self, to: Color) -> Result<DrawOffer, String> {
+        match self.pending.take() {
+            Some(offer) if offer.from != to => Ok(offer),
+            pending => {
+                self.pending = pending;
+                Err("No draw offer to respond to".to_string())
+            }
+        }
+    }
+
+    /// Withdraws `mover`'s offer once they move on without an answer.
+    pub fn cancel_on_move(&mut self, mover: Color) -> Option<DrawOffer> {
+        match &self.pending {
+            Some(offer) if offer.from == mover => self.pending.take(),
+            _ => None,
+        }
+    }
+
+    /// Withdraws the offer standing once it has gone `lifetime_ms` without
+    /// an answer.
+    pub fn expire(&mut self, now: u64, lifetime_ms: u64) -> Option<DrawOffer> {
+        match self.time_to_expiry(now, lifetime_ms)? {
+            0 => self.pending.take(),
+            _ => None,
+        }
+    }
+
+    /// How long until the offer standing lapses.
+    pub fn time_to_expiry(&self, now: u64, lifetime_ms: u64) -> Option<u64> {
+        let offer = self.pending.as_ref()?;
+        Some((offer.made_at + lifetime_ms).saturating_sub(now))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_offer_and_answer() {
+        let mut offers = DrawOffers::new();
+        offers.offer(Color::White, 10, None, 0).unwrap();
+        assert!(offers.offer(Color::White, 10, None, 0).is_err());
+        assert!(offers.take_offer_to(Color::White).is_err());
+
+        let offer = offers.take_offer_to(Color::Black).unwrap();
+        assert_eq!(offer.from, Color::White);
+        assert_eq!(offers.pending, None);
+    }
+
+    #[test]
+    fn test_cancelled_when_offerer_moves() {
+        let mut offers = DrawOffers::new();
+        offers.offer(Color::Black, 11, None, 0).unwrap();
+        assert_eq!(offers.cancel_on_move(Color::White), None);
+        assert!(offers.cancel_on_move(Color::Black).is_some());
+        assert!(offers.take_offer_to(Color::White).is_err());
+    }
+
+    #[test]
+    fn test_cooldown() {
+        let mut offers = DrawOffers::new();
+        offers.offer(Color::White, 10, None, 0).unwrap();
+        offers.take_offer_to(Color::Black).unwrap();
+
+        assert_eq!(
+            offers.offer(Color::White, 14, None, 0),
+            Err("Make 3 more moves before offering another draw".to_string())
+        );
+        offers.offer(Color::White, 20, None, 0).unwrap();
+    }
+
+    #[test]
+    fn test_expiry() {
+        let mut offers = DrawOffers::new();
+        assert_eq!(offers.time_to_expiry(0, 1000), None);
+        offers.offer(Color::White, 10, None, 5000).unwrap();
+
+        assert_eq!(offers.time_to_expiry(5400, 1000), Some(600));
+        assert_eq!(offers.expire(5999, 1000), None);
+        assert_eq!(
+            offers.expire(6000, 1000).map(|offer| offer.from),
+            Some(Color::White)
+        );
+        assert!(offers.take_offer_to(Color::Black).is_err());
+    }
+}
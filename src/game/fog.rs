@@ -0,0 +1,223 @@
+use super::bitboard::{
+    Bitboard, bishop_attacks, king_attacks, knight_attacks, pawn_attacks, rook_attacks,
+};
+use super::{Board, Color, GameResult, GameState, Move, PieceType, Variant};
+
+/// How many plies spectators of a fog-of-war game trail behind the players,
+/// so they cannot pass on what either side is hiding.
+pub const SPECTATOR_DELAY_PLIES: usize = 10;
+
+/// Who a game is being shown to. In fog-of-war games each player sees only
+/// what their own pieces reach and spectators see the whole board some moves
+/// late. Every other game looks the same to everyone.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Viewer {
+    Player(Color),
+    Spectator,
+}
+
+/// The squares `color` can see through the fog: those its pieces stand on,
+/// attack or can move to.
+pub fn visible_squares(board: &Board, color: Color) -> Bitboard {
+    let ours = board.color_pieces(color);
+    let occupied = board.occupied();
+
+    let mut visible = ours;
+    for from in ours.squares() {
+        visible |= match board.piece_type_at(from) {
+            Some(PieceType::Pawn) => pawn_attacks(color, from) | pawn_pushes(board, color, from),
+            Some(PieceType::Knight) => knight_attacks(from),
+            Some(PieceType::Bishop) => bishop_attacks(from, occupied),
+            Some(PieceType::Rook) => rook_attacks(from, occupied),
+            Some(PieceType::Queen) => bishop_attacks(from, occupied) | rook_attacks(from, occupied),
+            Some(PieceType::King) => king_attacks(from),
+            None => Bitboard::EMPTY,
+        };
+    }
+    visible
+}
+
+/// The empty squares ahead a pawn can step to. A blocked pawn does not see
+/// what blocks it.
+fn pawn_pushes(board: &Board, color: Color, from: usize) -> Bitboard {
+    let (forward, start_rank): (isize, usize) = match color {
+        Color::White => (8, 1),
+        Color::Black => (-8, 6),
+    };
+    let occupied = board.occupied();
+    let push = |sq: usize| {
+        sq.checked_add_signed(forward)
+            .filter(|&to| to < 64 && !occupied.contains(to))
+    };
+
+    let Some(single) = push(from) else {
+        return Bitboard::EMPTY;
+    };
+    match push(single) {
+        Some(double) if from / 8 == start_rank => {
+            Bitboard::from_square(single) | Bitboard::from_square(double)
+        }
+        _ => Bitboard::from_square(single),
+    }
+}
+
+impl GameState {
+    /// How `player_id` sees this game.
+    pub fn viewer(&self, player_id: &str) -> Viewer {
+        match self.get_player_color(player_id) {
+            Some(color) => Viewer::Player(color),
+            None => Viewer::Spectator,
+        }
+    }
+
+    /// Whether viewers see anything less than the whole game. The fog lifts
+    /// once the game is over.
+    pub fn is_fogged(&self) -> bool {
+        self.variant == Variant::FogOfWar && self.result == GameResult::Ongoing
+    }
+
+    /// The position as `viewer` may see it. PGN export and the archive
+    /// always work from the real board.
+    pub fn board_for(&self, viewer: Viewer) -> Board {
+        if !self.is_fogged() {
+            return self.board.clone();
+        }
+
+        match viewer {
+            Viewer::Player(color) => self
+                .board
+                .fogged(color, visible_squares(&self.board, color)),
+            Viewer::Spectator => {
+                let mut board = self.board.clone();
+                for undo in self.undo_history.iter().rev().take(SPECTATOR_DELAY_PLIES) {
+                    board.unmake_move(undo);
+                }
+                board
+            }
+        }
+    }
+
+    /// The squares `viewer` can see, when the fog hides any from them.
+    pub fn visible_squares_for(&self, viewer: Viewer) -> Option<Bitboard> {
+        match viewer {
+            Viewer::Player(color) if self.is_fogged() => Some(visible_squares(&self.board, color)),
+            _ => None,
+        }
+    }
+
+    /// The moves `viewer` knows were played: their own in fog-of-war, or
+    /// all but the last few for spectators.
+    pub fn moves_for(&self, viewer: Viewer) -> Vec<Move> {
+        if !self.is_fogged() {
+            return self.move_history.clone();
+        }
+
+        match viewer {
+            Viewer::Player(color) => self
+                .undo_history
+                .iter()
+                .filter(|undo| undo.moved_piece.color == color)
+                .map(|undo| undo.chess_move)
+                .collect(),
+            Viewer::Spectator => {
+                let shown = self
+                    .move_history
+                    .len()
+                    .saturating_sub(SPECTATOR_DELAY_PLIES);
+                self.move_history[..shown].to_vec()
+            }
+        }
+    }
+
+    /// The last move, if `viewer` may know about it. Fog-of-war players only
+    /// learn of their own moves.
+    pub fn last_move_for(&self, viewer: Viewer) -> Option<Move> {
+        if !self.is_fogged() {
+            return self.get_last_move().copied();
+        }
+
+        match viewer {
+            Viewer::Player(color) => self
+                .undo_history
+                .last()
+                .filter(|undo| undo.moved_piece.color == color)
+                .map(|undo| undo.chess_move),
+            Viewer::Spectator => self.moves_for(viewer).last().copied(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::{Position, VariantWinReason};
+
+    fn squares(bitboard: Bitboard) -> Vec<String> {
+        bitboard
+            .squares()
+            .map(|sq| Position::from_index(sq).unwrap().to_algebraic())
+            .collect()
+    }
+
+    #[test]
+    fn test_visible_squares() {
+        let board = Variant::FogOfWar
+            .board_from_fen("4k3/8/8/3p4/3P4/8/8/4K2N w - - 0 1")
+            .unwrap();
+
+        // The blocked pawn sees its captures but not the pawn in front of it
+        let visible = visible_squares(&board, Color::White);
+        assert_eq!(
+            squares(visible),
+            [
+                "d1", "e1", "f1", "h1", "d2", "e2", "f2", "g3", "d4", "c5", "e5"
+            ]
+        );
+
+        let fogged = board.fogged(Color::White, visible);
+        assert_eq!(fogged.to_fen(), "8/8/8/8/3P4/8/8/4K2N w - - 0 1");
+    }
+
+    #[test]
+    fn test_views() {
+        let mut game = GameState::for_variant(Variant::FogOfWar, None).unwrap();
+        for uci in ["e2e4", "d7d5"] {
+            game.apply_move(Move::from_algebraic(uci).unwrap()).unwrap();
+        }
+        let white = Viewer::Player(Color::White);
+
+        // White sees the d5 pawn it attacks, but not Black's move
+        assert_eq!(game.last_move_for(white), None);
+        assert_eq!(game.moves_for(white).len(), 1);
+        assert_eq!(
+            game.board_for(white).to_fen(),
+            "8/8/8/3p4/4P3/8/PPPP1PPP/RNBQKBNR w KQ - 0 2"
+        );
+        assert!(game.visible_squares_for(Viewer::Spectator).is_none());
+
+        // Spectators trail behind, and the archive keeps everything
+        assert_eq!(
+            game.board_for(Viewer::Spectator).to_fen(),
+            Board::new().to_fen()
+        );
+        assert!(game.moves_for(Viewer::Spectator).is_empty());
+        assert!(game.to_pgn().contains("1. e4 d5"));
+    }
+
+    #[test]
+    fn test_king_capture_lifts_fog() {
+        let mut game = GameState::for_variant(Variant::FogOfWar, None).unwrap();
+        for uci in ["e2e4", "f7f6", "d1h5", "a7a6", "h5e8"] {
+            game.apply_move(Move::from_algebraic(uci).unwrap()).unwrap();
+        }
+
+        assert_eq!(
+            game.result,
+            GameResult::VariantWin(Color::White, VariantWinReason::KingCaptured)
+        );
+        assert_eq!(
+            game.board_for(Viewer::Player(Color::Black)).to_fen(),
+            game.board.to_fen()
+        );
+    }
+}
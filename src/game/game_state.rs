@@ -1,9 +1,18 @@
+use std::any::Any;
 use std::collections::HashMap;
 
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
-use super::{Board, Color, Move, MoveValidator, PieceType, Position};
+use super::{
+    Board, CheckCounts, Clock, Color, Correspondence, DRAW_OFFER_LIFETIME_MS, DrawOffer,
+    DrawOffers, Move, MoveUndo, PieceType, Pockets, Position, Ruleset, Variant, Viewer,
+};
+use crate::turn_based::{GameRegistry, SeatedGame, Table};
+use crate::utils::current_timestamp_millis;
+
+/// The kind clients pick chess by in the `GameRegistry`.
+pub const CHESS_KIND: &str = "chess";
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum GameResult {
@@ -13,6 +22,32 @@ pub enum GameResult {
     Draw(DrawReason),
     Resignation(Color),
     Timeout(Color),
+    /// Ended by the result on the linked Bughouse board, with the winning
+    /// color on this board, or `None` for a draw.
+    PartnerBoard(Option<Color>),
+    /// Won by a rule of the variant rather than by checkmate.
+    VariantWin(Color, VariantWinReason),
+}
+
+impl GameResult {
+    /// The PGN game termination marker for this result.
+    pub fn pgn_result(&self) -> &'static str {
+        match self {
+            GameResult::Checkmate(Color::White) => "1-0",
+            GameResult::Checkmate(Color::Black) => "0-1",
+            GameResult::Stalemate | GameResult::Draw(_) => "1/2-1/2",
+            GameResult::Resignation(Color::White) => "0-1",
+            GameResult::Resignation(Color::Black) => "1-0",
+            GameResult::Timeout(Color::White) => "0-1",
+            GameResult::Timeout(Color::Black) => "1-0",
+            GameResult::PartnerBoard(Some(Color::White)) => "1-0",
+            GameResult::PartnerBoard(Some(Color::Black)) => "0-1",
+            GameResult::PartnerBoard(None) => "1/2-1/2",
+            GameResult::VariantWin(Color::White, _) => "1-0",
+            GameResult::VariantWin(Color::Black, _) => "0-1",
+            GameResult::Ongoing => "*",
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -21,47 +56,153 @@ pub enum DrawReason {
     ThreefoldRepetition,
     InsufficientMaterial,
     Agreement,
+    /// Racing Kings: Black's king followed White's onto the eighth rank.
+    KingsRaceTied,
+    /// A flag fell, but the opponent had no way left to checkmate.
+    TimeoutVsInsufficientMaterial,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum VariantWinReason {
+    /// Atomic: the loser's king was caught in an explosion.
+    KingExploded,
+    /// Antichess: the winner has given away all of their pieces.
+    AllPiecesLost,
+    /// Antichess: the winner has no move left.
+    Stalemated,
+    /// Three-check: the winner has given a third check.
+    ThreeChecks,
+    /// King of the Hill: the winner's king has reached the center.
+    KingOfTheHill,
+    /// Horde: Black has captured every white piece.
+    HordeDestroyed,
+    /// Racing Kings: the winner's king has reached the eighth rank first.
+    KingReachedGoal,
+    /// Fog of war: the winner has captured the loser's king.
+    KingCaptured,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GameState {
     pub id: String,
+    pub variant: Variant,
     pub board: Board,
     pub white_player: Option<String>,
     pub black_player: Option<String>,
     pub result: GameResult,
     pub move_history: Vec<Move>,
     pub position_history: Vec<String>, // FEN
+    pub position_keys: Vec<u64>,       // Zobrist
+    pub undo_history: Vec<MoveUndo>,
     pub created_at: u64,
     pub last_move_at: u64,
+    /// The players' clocks, in timed games.
+    #[serde(default)]
+    pub clock: Option<Clock>,
+    /// The move deadline, in correspondence games.
+    #[serde(default)]
+    pub correspondence: Option<Correspondence>,
+    #[serde(default)]
+    pub draw_offers: DrawOffers,
 }
 
 impl GameState {
     pub fn new() -> Self {
-        let board = Board::new();
+        Self::with_board(Board::new(), Variant::Standard)
+    }
+
+    pub fn from_fen(fen: &str) -> Result<Self, String> {
+        let board = Board::from_fen(fen).map_err(|e| e.to_string())?;
+        let variant = Variant::detect(&board);
+        Ok(Self::from_board(board, variant))
+    }
+
+    /// A game of `variant` played on from `board`, which may already be over.
+    pub fn from_board(board: Board, variant: Variant) -> Self {
+        let mut game = Self::with_board(board, variant);
+        game.check_game_end();
+        game
+    }
+
+    /// A new game of `variant`. Only Chess960 takes a start position, and
+    /// picks one at random without it.
+    pub fn for_variant(variant: Variant, start_position: Option<u16>) -> Result<Self, String> {
+        match (variant, start_position) {
+            (Variant::Chess960, start_position) => Self::new_chess960(start_position),
+            (_, Some(_)) => Err("Start positions are only supported for Chess960".to_string()),
+            (variant, None) => Ok(Self::with_board(
+                variant.starting_board().unwrap_or_default(),
+                variant,
+            )),
+        }
+    }
+
+    /// A Chess960 game from the given Scharnagl index, or a random one.
+    pub fn new_chess960(start_position: Option<u16>) -> Result<Self, String> {
+        let index = start_position.unwrap_or_else(|| (Uuid::new_v4().as_u128() % 960) as u16);
+        let board = Board::new_chess960(index)
+            .ok_or_else(|| format!("Chess960 start position must be 0-959, got {}", index))?;
+        Ok(Self::with_board(board, Variant::Chess960))
+    }
+
+    fn with_board(board: Board, variant: Variant) -> Self {
         let fen = board.to_fen();
+        let key = variant.ruleset().position_key(&board);
 
         Self {
             id: Uuid::new_v4().to_string(),
+            variant,
             board,
             white_player: None,
             black_player: None,
             result: GameResult::Ongoing,
             move_history: Vec::new(),
             position_history: vec![fen],
+            position_keys: vec![key],
+            undo_history: Vec::new(),
             created_at: Self::current_timestamp(),
             last_move_at: Self::current_timestamp(),
+            clock: None,
+            correspondence: None,
+            draw_offers: DrawOffers::new(),
         }
     }
 
-    pub fn from_fen(fen: &str) -> Result<Self, String> {
-        // FEN Analyzer is required.
-        let mut game = Self::new();
-        game.position_history = vec![fen.to_string()];
-        Ok(game)
+    /// Plays the game on `clock`, which starts once both players are in.
+    pub fn set_clock(&mut self, clock: Clock) {
+        self.clock = Some(clock);
+        self.start_clock_if_ready();
+    }
+
+    /// Plays the game by correspondence, with `days_per_move` days for each
+    /// move, counted once both players are in.
+    pub fn set_correspondence(&mut self, days_per_move: u32) {
+        self.correspondence = Some(Correspondence::new(days_per_move));
+        self.start_clock_if_ready();
+    }
+
+    fn start_clock_if_ready(&mut self) {
+        if !self.is_ready_to_start() || self.result != GameResult::Ongoing {
+            return;
+        }
+        let to_move = self.board.get_to_move();
+        if let Some(clock) = &mut self.clock {
+            clock.start(to_move, current_timestamp_millis());
+        }
+        if let Some(correspondence) = &mut self.correspondence
+            && correspondence.deadline.is_none()
+        {
+            correspondence.start(Self::current_timestamp());
+        }
     }
 
     pub fn add_player(&mut self, player_id: String, color: Option<Color>) -> Result<Color, String> {
+        let color = self.seat_player(player_id, color)?;
+        self.start_clock_if_ready();
+        Ok(color)
+    }
+
+    fn seat_player(&mut self, player_id: String, color: Option<Color>) -> Result<Color, String> {
         match color {
             Some(Color::White) => {
                 if self.white_player.is_some() {
@@ -133,6 +274,17 @@ impl GameState {
     }
 
     pub fn make_move(&mut self, player_id: &str, chess_move: Move) -> Result<(), String> {
+        self.make_move_with_lag(player_id, chess_move, 0)
+    }
+
+    /// Like `make_move`, but gives the mover back up to `lag_ms` of the
+    /// time the move took as network lag, as far as the clock allows.
+    pub fn make_move_with_lag(
+        &mut self,
+        player_id: &str,
+        chess_move: Move,
+        lag_ms: u64,
+    ) -> Result<(), String> {
         if self.result != GameResult::Ongoing {
             return Err("Game is already finished".to_string());
         }
@@ -145,13 +297,45 @@ impl GameState {
             return Err("Not your turn".to_string());
         }
 
-        if !MoveValidator::is_valid_move(&self.board, &chess_move) {
+        if self.check_flag_with_lag(lag_ms) {
+            return Err("Time has run out".to_string());
+        }
+
+        self.apply_move(chess_move)?;
+        self.draw_offers.cancel_on_move(player_color);
+
+        if let Some(clock) = &mut self.clock {
+            match self.result {
+                GameResult::Ongoing => clock.press_with_lag(current_timestamp_millis(), lag_ms),
+                _ => clock.stop(current_timestamp_millis()),
+            }
+        }
+        if let Some(correspondence) = &mut self.correspondence {
+            match self.result {
+                GameResult::Ongoing => correspondence.start(Self::current_timestamp()),
+                _ => correspondence.stop(),
+            }
+        }
+        Ok(())
+    }
+
+    /// Plays a move for the side to move without checking who submitted it.
+    /// Used when replaying recorded games.
+    pub fn apply_move(&mut self, chess_move: Move) -> Result<(), String> {
+        if self.result != GameResult::Ongoing {
+            return Err("Game is already finished".to_string());
+        }
+
+        let rules = self.ruleset();
+        if !rules.is_legal_move(&self.board, &chess_move) {
             return Err("Invalid move".to_string());
         }
 
-        self.board.make_move(&chess_move)?;
+        let undo = rules.make_move(&mut self.board, &chess_move)?;
+        self.undo_history.push(undo);
         self.move_history.push(chess_move);
         self.position_history.push(self.board.to_fen());
+        self.position_keys.push(rules.position_key(&self.board));
         self.last_move_at = Self::current_timestamp();
 
         self.check_game_end();
@@ -159,53 +343,88 @@ impl GameState {
         Ok(())
     }
 
-    fn check_game_end(&mut self) {
-        if MoveValidator::is_checkmate(&self.board) {
-            let winner = self.board.get_to_move().opposite();
-            self.result = GameResult::Checkmate(winner);
-            return;
+    /// Takes back the last move, reopening the game if that move ended it.
+    pub fn take_back(&mut self) -> Result<Move, String> {
+        // Captures have already been handed to the partner board
+        if self.variant == Variant::Bughouse {
+            return Err("Moves cannot be taken back in Bughouse".to_string());
         }
 
-        if MoveValidator::is_stalemate(&self.board) {
-            self.result = GameResult::Stalemate;
-            return;
+        if matches!(
+            self.result,
+            GameResult::Resignation(_)
+                | GameResult::Timeout(_)
+                | GameResult::Draw(DrawReason::Agreement)
+                | GameResult::PartnerBoard(_)
+        ) {
+            return Err("Game is already finished".to_string());
         }
 
-        if MoveValidator::is_draw_by_fifty_move_rule(&self.board) {
-            self.result = GameResult::Draw(DrawReason::FiftyMoveRule);
-            return;
-        }
+        let undo = self.undo_history.pop().ok_or("No move to take back")?;
+        self.board.unmake_move(&undo);
+        self.move_history.pop();
+        self.position_history.pop();
+        self.position_keys.pop();
 
-        if self.is_threefold_repetition() {
-            self.result = GameResult::Draw(DrawReason::ThreefoldRepetition);
-            return;
+        self.result = GameResult::Ongoing;
+        self.last_move_at = Self::current_timestamp();
+        let mover = self.board.get_to_move();
+        if let Some(clock) = &mut self.clock {
+            clock.take_back(current_timestamp_millis(), mover);
         }
-
-        if self.is_insufficient_material() {
-            self.result = GameResult::Draw(DrawReason::InsufficientMaterial);
-            return;
+        if let Some(correspondence) = &mut self.correspondence {
+            correspondence.stop();
         }
+        self.start_clock_if_ready();
+
+        Ok(undo.chess_move)
     }
 
-    fn is_threefold_repetition(&self) -> bool {
-        let curr_pos = self.position_history.last().unwrap();
-        let mut cnt = 0;
+    /// Replaces the pieces in hand, keeping the recorded current position in
+    /// step. Bughouse boards pass captures across this way.
+    pub fn set_pockets(&mut self, pockets: Pockets) {
+        self.board.set_pockets(Some(pockets));
+        if let Some(fen) = self.position_history.last_mut() {
+            *fen = self.board.to_fen();
+        }
+        if let Some(key) = self.position_keys.last_mut() {
+            *key = self.variant.ruleset().position_key(&self.board);
+        }
+    }
 
-        for pos in &self.position_history {
-            let pos_parts: Vec<&str> = pos.split(' ').collect();
-            let curr_parts: Vec<&str> = curr_pos.split(' ').collect();
+    pub fn ruleset(&self) -> &'static dyn Ruleset {
+        self.variant.ruleset()
+    }
 
-            if pos_parts.len() >= 4 && curr_parts.len() >= 4 {
-                if pos_parts[0..4] == curr_parts[0..4] {
-                    cnt += 1;
-                }
-            }
+    fn check_game_end(&mut self) {
+        if let Some(result) = self.ruleset().game_result(self) {
+            self.result = result;
         }
+    }
 
-        cnt >= 3
+    pub(crate) fn is_threefold_repetition(&self) -> bool {
+        let Some(&current) = self.position_keys.last() else {
+            return false;
+        };
+
+        // Nothing before the last capture or pawn move can recur, and only
+        // positions with the same side to move can match. With pockets,
+        // captured pieces come back, so the whole game has to be searched.
+        let reversible_plies = match self.board.pockets() {
+            Some(_) => self.position_keys.len(),
+            None => self.board.get_halfmove_clock() as usize,
+        };
+        self.position_keys
+            .iter()
+            .rev()
+            .take(reversible_plies + 1)
+            .step_by(2)
+            .filter(|&&key| key == current)
+            .count()
+            >= 3
     }
 
-    fn is_insufficient_material(&self) -> bool {
+    pub(crate) fn is_insufficient_material(&self) -> bool {
         let mut white_pieces = Vec::new();
         let mut black_pieces = Vec::new();
 
@@ -226,6 +445,35 @@ impl GameState {
             && Self::is_insufficient_material_for_color(&black_pieces)
     }
 
+    /// Whether `color` could still checkmate by some series of legal moves,
+    /// which decides whether running out of time against them loses. A lone
+    /// minor piece can only mate with help from the other side's pieces.
+    pub(crate) fn has_mating_material(&self, color: Color) -> bool {
+        let mut own_minors = 0;
+        let mut opponent_has_pieces = false;
+
+        for rank in 0..8 {
+            for file in 0..8 {
+                let Some(piece) =
+                    Position::new(file, rank).and_then(|pos| self.board.get_piece(pos))
+                else {
+                    continue;
+                };
+                if piece.color != color {
+                    opponent_has_pieces |= piece.piece_type != PieceType::King;
+                    continue;
+                }
+                match piece.piece_type {
+                    PieceType::King => {}
+                    PieceType::Bishop | PieceType::Knight => own_minors += 1,
+                    PieceType::Pawn | PieceType::Rook | PieceType::Queen => return true,
+                }
+            }
+        }
+
+        own_minors >= 2 || (own_minors == 1 && opponent_has_pieces)
+    }
+
     fn is_insufficient_material_for_color(pieces: &[PieceType]) -> bool {
         let mut bishops = 0;
         let mut knights = 0;
@@ -267,23 +515,96 @@ impl GameState {
             .ok_or("Player not in this game")?;
 
         self.result = GameResult::Resignation(player_color);
+        self.stop_clock();
         self.last_move_at = Self::current_timestamp();
         Ok(())
     }
 
-    pub fn offer_draw(&mut self, player_id: &str) -> Result<(), String> {
+    /// Offers the opponent a draw, which stands until they answer it,
+    /// `player_id` moves again or it lapses unanswered. Offering while the
+    /// opponent's own offer stands accepts it instead, returning `true` as
+    /// the game is drawn.
+    pub fn offer_draw(&mut self, player_id: &str, message: Option<String>) -> Result<bool, String> {
+        if self.result != GameResult::Ongoing {
+            return Err("Game is already finished".to_string());
+        }
+
+        let player_color = self
+            .get_player_color(player_id)
+            .ok_or("Player not in this game")?;
+
+        if self
+            .draw_offers
+            .pending
+            .as_ref()
+            .is_some_and(|offer| offer.from != player_color)
+        {
+            self.respond_to_draw(player_id, true)?;
+            return Ok(true);
+        }
+
+        self.draw_offers.offer(
+            player_color,
+            self.move_history.len(),
+            message,
+            current_timestamp_millis(),
+        )?;
+        Ok(false)
+    }
+
+    /// Accepts or declines the draw offered to `player_id`, returning the
+    /// offer answered.
+    pub fn respond_to_draw(&mut self, player_id: &str, accept: bool) -> Result<DrawOffer, String> {
         if self.result != GameResult::Ongoing {
             return Err("Game is already finished".to_string());
         }
 
-        if !self.is_player_in_game(player_id) {
-            return Err("Player not in this game".to_string());
+        let player_color = self
+            .get_player_color(player_id)
+            .ok_or("Player not in this game")?;
+        let offer = self.draw_offers.take_offer_to(player_color)?;
+
+        if accept {
+            self.result = GameResult::Draw(DrawReason::Agreement);
+            self.stop_clock();
+            self.last_move_at = Self::current_timestamp();
+        }
+        Ok(offer)
+    }
+
+    /// The draw offer standing, while the game goes on.
+    pub fn pending_draw_offer(&self) -> Option<&DrawOffer> {
+        match self.result {
+            GameResult::Ongoing => self.draw_offers.pending.as_ref(),
+            _ => None,
+        }
+    }
+
+    /// How long a draw offer stands unanswered: a move's worth of days in
+    /// correspondence games.
+    fn draw_offer_lifetime_ms(&self) -> u64 {
+        match &self.correspondence {
+            Some(correspondence) => u64::from(correspondence.days_per_move) * 24 * 60 * 60 * 1000,
+            None => DRAW_OFFER_LIFETIME_MS,
         }
+    }
 
-        // TODO: Wait for opponent's agreement of draw
-        self.result = GameResult::Draw(DrawReason::Agreement);
-        self.last_move_at = Self::current_timestamp();
-        Ok(())
+    /// Withdraws the draw offer standing if it has gone unanswered too
+    /// long, returning it.
+    pub fn expire_draw_offer(&mut self) -> Option<DrawOffer> {
+        if self.result != GameResult::Ongoing {
+            return None;
+        }
+        let lifetime_ms = self.draw_offer_lifetime_ms();
+        self.draw_offers
+            .expire(current_timestamp_millis(), lifetime_ms)
+    }
+
+    /// How long until the draw offer standing lapses.
+    pub fn time_to_draw_offer_expiry_ms(&self) -> Option<u64> {
+        self.pending_draw_offer()?;
+        self.draw_offers
+            .time_to_expiry(current_timestamp_millis(), self.draw_offer_lifetime_ms())
     }
 
     pub fn timeout(&mut self, player_id: &str) -> Result<(), String> {
@@ -295,16 +616,74 @@ impl GameState {
             .get_player_color(player_id)
             .ok_or("Player not in this game")?;
 
-        self.result = GameResult::Timeout(player_color);
+        self.result = self.ruleset().timeout_result(self, player_color);
+        self.stop_clock();
         self.last_move_at = Self::current_timestamp();
         Ok(())
     }
 
+    /// Ends the game on time if the side to move has run out on the clock
+    /// or let a correspondence deadline pass, returning whether it did.
+    pub fn check_flag(&mut self) -> bool {
+        self.check_flag_with_lag(u64::MAX)
+    }
+
+    /// `check_flag` for a side whose move has `lag_ms` of lag to be given
+    /// back. Without a move in hand, any lag still allowed is waited out.
+    fn check_flag_with_lag(&mut self, lag_ms: u64) -> bool {
+        if self.result != GameResult::Ongoing {
+            return false;
+        }
+
+        let now = current_timestamp_millis();
+        let overdue = self
+            .correspondence
+            .is_some_and(|correspondence| correspondence.is_overdue(now / 1000));
+        let flagged = match overdue {
+            true => Some(self.board.get_to_move()),
+            false => self
+                .clock
+                .as_ref()
+                .and_then(|clock| clock.flagged_with_lag(now, lag_ms)),
+        };
+        let Some(flagged) = flagged else {
+            return false;
+        };
+        self.result = self.ruleset().timeout_result(self, flagged);
+        self.stop_clock();
+        self.last_move_at = Self::current_timestamp();
+        true
+    }
+
+    /// `color`'s time left, in timed games.
+    pub fn remaining_ms(&self, color: Color) -> Option<u64> {
+        let clock = self.clock.as_ref()?;
+        Some(clock.remaining_ms(color, current_timestamp_millis()))
+    }
+
+    /// How long until the side to move's flag falls, while their clock runs.
+    pub fn time_to_flag_ms(&self) -> Option<u64> {
+        self.clock
+            .as_ref()?
+            .time_to_flag(current_timestamp_millis())
+    }
+
+    /// Stops the clock, or the correspondence deadline, as when the game
+    /// ends.
+    pub fn stop_clock(&mut self) {
+        if let Some(clock) = &mut self.clock {
+            clock.stop(current_timestamp_millis());
+        }
+        if let Some(correspondence) = &mut self.correspondence {
+            correspondence.stop();
+        }
+    }
+
     pub fn get_legal_moves(&self) -> Vec<Move> {
         if self.result != GameResult::Ongoing {
             return Vec::new();
         }
-        MoveValidator::generate_legal_moves(&self.board)
+        self.ruleset().legal_moves(&self.board)
     }
 
     pub fn get_legal_moves_for_player(&self, player_id: &str) -> Vec<Move> {
@@ -317,11 +696,17 @@ impl GameState {
     }
 
     pub fn is_in_check(&self) -> bool {
-        MoveValidator::is_in_check(&self.board, self.board.get_to_move())
+        self.ruleset()
+            .is_in_check(&self.board, self.board.get_to_move())
     }
 
     pub fn get_current_player(&self) -> Option<&String> {
-        match self.board.get_to_move() {
+        self.player_id(self.board.get_to_move())
+    }
+
+    /// The player seated as `color`.
+    pub fn player_id(&self, color: Color) -> Option<&String> {
+        match color {
             Color::White => self.white_player.as_ref(),
             Color::Black => self.black_player.as_ref(),
         }
@@ -349,49 +734,6 @@ impl GameState {
         self.move_history.last()
     }
 
-    pub fn to_pgn(&self) -> String {
-        let mut pgn = String::new();
-
-        // PGN headers
-        pgn.push_str(&format!("[Event \"Chess game\"]\n"));
-        pgn.push_str(&format!("[Site \"Chess Server\"]\n"));
-        pgn.push_str(&format!(
-            "[Date \"{}\"]\n",
-            Self::format_date(self.created_at)
-        ));
-        pgn.push_str(&format!(
-            "[White \"{}\"]\n",
-            self.white_player.as_deref().unwrap_or("Unknown")
-        ));
-        pgn.push_str(&format!(
-            "[Black \"{}\"]\n",
-            self.black_player.as_deref().unwrap_or("Unknown")
-        ));
-
-        let result_str = match &self.result {
-            GameResult::Checkmate(Color::White) => "1-0",
-            GameResult::Checkmate(Color::Black) => "0-1",
-            GameResult::Stalemate | GameResult::Draw(_) => "1/2-1/2",
-            GameResult::Resignation(Color::White) => "0-1",
-            GameResult::Resignation(Color::Black) => "1-0",
-            GameResult::Timeout(Color::White) => "0-1",
-            GameResult::Timeout(Color::Black) => "1-0",
-            GameResult::Ongoing => "*",
-        };
-        pgn.push_str(&format!("[Result \"{}\"]\n", result_str));
-        pgn.push('\n');
-
-        for (i, chess_move) in self.move_history.iter().enumerate() {
-            if i % 2 == 0 {
-                pgn.push_str(&format!("{}.", (i / 2) + 1));
-            }
-            pgn.push_str(&format!(" {} ", chess_move.to_algebraic()));
-        }
-
-        pgn.push_str(&format!(" {}", result_str));
-        pgn
-    }
-
     fn current_timestamp() -> u64 {
         std::time::SystemTime::now()
             .duration_since(std::time::UNIX_EPOCH)
@@ -399,21 +741,18 @@ impl GameState {
             .as_secs()
     }
 
-    fn format_date(timestamp: u64) -> String {
-        // TODO: use chrono
-        format!("{}", timestamp)
-    }
-
     pub fn get_game_info(&self) -> GameInfo {
         GameInfo {
             id: self.id.clone(),
+            variant: self.variant,
             white_player: self.white_player.clone(),
             black_player: self.black_player.clone(),
             to_move: self.board.get_to_move(),
             result: self.result.clone(),
             move_count: self.move_history.len(),
             is_in_check: self.is_in_check(),
-            last_move: self.get_last_move().cloned(),
+            check_counts: self.board.check_counts().copied(),
+            last_move: self.last_move_for(Viewer::Spectator),
             created_at: self.created_at,
             last_move_at: self.last_move_at,
         }
@@ -423,12 +762,17 @@ impl GameState {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GameInfo {
     pub id: String,
+    #[serde(default)]
+    pub variant: Variant,
     pub white_player: Option<String>,
     pub black_player: Option<String>,
     pub to_move: Color,
     pub result: GameResult,
     pub move_count: usize,
     pub is_in_check: bool,
+    /// Checks given by each side, in Three-check.
+    #[serde(default)]
+    pub check_counts: Option<CheckCounts>,
     pub last_move: Option<Move>,
     pub created_at: u64,
     pub last_move_at: u64,
@@ -440,34 +784,89 @@ impl Default for GameState {
     }
 }
 
-#[derive(Debug)]
+/// Hosts every game on the server, of each kind in its `GameRegistry`:
+/// chess games as `GameState`s and the other kinds at `Table`s. All share
+/// one map, and so one set of game IDs.
 pub struct GameManager {
-    games: HashMap<String, GameState>,
+    games: HashMap<String, Box<dyn SeatedGame>>,
     player_games: HashMap<String, Vec<String>>, // Player ID -> its list
+    registry: GameRegistry,
 }
 
 impl GameManager {
     pub fn new() -> Self {
+        Self::with_registry(GameRegistry::with_builtin_games())
+    }
+
+    pub fn with_registry(registry: GameRegistry) -> Self {
         Self {
             games: HashMap::new(),
             player_games: HashMap::new(),
+            registry,
+        }
+    }
+
+    /// The kinds of game that can be created, as named in the registry.
+    pub fn kinds(&self) -> Vec<&'static str> {
+        self.registry.kinds()
+    }
+
+    /// The game `game_id` as a `T`, if it is one.
+    fn get_as<T: SeatedGame>(&self, game_id: &str) -> Option<&T> {
+        let game: &dyn Any = self.games.get(game_id)?.as_ref();
+        game.downcast_ref()
+    }
+
+    fn get_as_mut<T: SeatedGame>(&mut self, game_id: &str) -> Option<&mut T> {
+        let game: &mut dyn Any = self.games.get_mut(game_id)?.as_mut();
+        game.downcast_mut()
+    }
+
+    fn seated_mut(&mut self, game_id: &str) -> Result<&mut dyn SeatedGame, String> {
+        match self.games.get_mut(game_id) {
+            Some(game) => Ok(game.as_mut()),
+            None => Err("Game not found".to_string()),
         }
     }
 
     pub fn create_game(&mut self) -> String {
         let game = GameState::new();
         let game_id = game.id.clone();
-        self.games.insert(game_id.clone(), game);
+        self.games.insert(game_id.clone(), Box::new(game));
         game_id
     }
 
+    pub fn create_game_with_variant(
+        &mut self,
+        variant: Variant,
+        start_position: Option<u16>,
+    ) -> Result<String, String> {
+        let game = GameState::for_variant(variant, start_position)?;
+
+        let game_id = game.id.clone();
+        self.games.insert(game_id.clone(), Box::new(game));
+        Ok(game_id)
+    }
+
+    /// Starts a game of `kind`, as its registry entry sets one up.
+    pub fn create_game_of_kind(&mut self, kind: &str) -> Result<String, String> {
+        let game = self
+            .registry
+            .create(kind)
+            .ok_or_else(|| format!("Unknown game kind '{}'", kind))?;
+
+        let game_id = game.id().to_string();
+        self.games.insert(game_id.clone(), game);
+        Ok(game_id)
+    }
+
     pub fn join_game(
         &mut self,
         game_id: &str,
         player_id: String,
         color: Option<Color>,
     ) -> Result<Color, String> {
-        let game = self.games.get_mut(game_id).ok_or("Game not found")?;
+        let game = self.get_game_mut(game_id).ok_or("Game not found")?;
 
         let assigned_color = game.add_player(player_id.clone(), color)?;
 
@@ -480,7 +879,7 @@ impl GameManager {
     }
 
     pub fn leave_game(&mut self, game_id: &str, player_id: &str) -> Result<(), String> {
-        let game = self.games.get_mut(game_id).ok_or("Game not found")?;
+        let game = self.get_game_mut(game_id).ok_or("Game not found")?;
 
         game.remove_player(player_id);
 
@@ -497,58 +896,230 @@ impl GameManager {
         player_id: &str,
         chess_move: Move,
     ) -> Result<(), String> {
-        let game = self.games.get_mut(game_id).ok_or("Game not found")?;
+        self.make_move_with_lag(game_id, player_id, chess_move, 0)
+    }
 
-        game.make_move(player_id, chess_move)
+    pub fn make_move_with_lag(
+        &mut self,
+        game_id: &str,
+        player_id: &str,
+        chess_move: Move,
+        lag_ms: u64,
+    ) -> Result<(), String> {
+        let game = self.get_game_mut(game_id).ok_or("Game not found")?;
+
+        game.make_move_with_lag(player_id, chess_move, lag_ms)
+    }
+
+    /// Whether `game_id` names a game of any kind.
+    pub fn has_game(&self, game_id: &str) -> bool {
+        self.games.contains_key(game_id)
+    }
+
+    /// Resigns `player_id` from the game `game_id`, of any kind.
+    pub fn resign(&mut self, game_id: &str, player_id: &str) -> Result<(), String> {
+        self.seated_mut(game_id)?.resign(player_id)
+    }
+
+    /// Offers a draw in the game `game_id`, of any kind, returning whether
+    /// it drew the game.
+    pub fn offer_draw(
+        &mut self,
+        game_id: &str,
+        player_id: &str,
+        message: Option<String>,
+    ) -> Result<bool, String> {
+        self.seated_mut(game_id)?.offer_draw(player_id, message)
+    }
+
+    /// Answers the draw offered to `player_id` in the game `game_id`, of any
+    /// kind, returning the offer answered.
+    pub fn respond_to_draw(
+        &mut self,
+        game_id: &str,
+        player_id: &str,
+        accept: bool,
+    ) -> Result<DrawOffer, String> {
+        self.seated_mut(game_id)?.respond_to_draw(player_id, accept)
+    }
+
+    pub fn pending_draw_offer(&self, game_id: &str) -> Option<&DrawOffer> {
+        self.games.get(game_id)?.pending_draw_offer()
+    }
+
+    /// Withdraws the draw offer standing in the game `game_id`, of any kind,
+    /// if it has gone unanswered too long, returning it.
+    pub fn expire_draw_offer(&mut self, game_id: &str) -> Option<DrawOffer> {
+        self.games.get_mut(game_id)?.expire_draw_offer()
+    }
+
+    /// How long until the draw offer standing in the game `game_id`, of any
+    /// kind, lapses.
+    pub fn time_to_draw_offer_expiry_ms(&self, game_id: &str) -> Option<u64> {
+        self.games.get(game_id)?.time_to_draw_offer_expiry_ms()
+    }
+
+    /// The player of `color` in the game `game_id`, of any kind.
+    pub fn player_id(&self, game_id: &str, color: Color) -> Option<&String> {
+        self.games.get(game_id)?.player_id(color)
+    }
+
+    /// Ends the game `game_id`, of any kind, on time if the side to move
+    /// has run out, returning whether it did.
+    pub fn check_flag(&mut self, game_id: &str) -> bool {
+        self.games
+            .get_mut(game_id)
+            .is_some_and(|game| game.check_flag())
+    }
+
+    /// How long until the side to move's flag falls in the game `game_id`,
+    /// of any kind.
+    pub fn time_to_flag_ms(&self, game_id: &str) -> Option<u64> {
+        self.games.get(game_id)?.time_to_flag_ms()
+    }
+
+    /// Hosts a game read back from storage, seats and all.
+    pub fn restore_game(&mut self, game: GameState) {
+        for player_id in [&game.white_player, &game.black_player]
+            .into_iter()
+            .flatten()
+        {
+            self.player_games
+                .entry(player_id.clone())
+                .or_default()
+                .push(game.id.clone());
+        }
+        self.games.insert(game.id.clone(), Box::new(game));
     }
 
     pub fn get_game(&self, game_id: &str) -> Option<&GameState> {
-        self.games.get(game_id)
+        self.get_as(game_id)
     }
 
     pub fn get_game_mut(&mut self, game_id: &str) -> Option<&mut GameState> {
-        self.games.get_mut(game_id)
+        self.get_as_mut(game_id)
     }
 
     pub fn get_player_games(&self, player_id: &str) -> Vec<&GameState> {
         if let Some(game_ids) = self.player_games.get(player_id) {
-            game_ids
-                .iter()
-                .filter_map(|id| self.games.get(id))
-                .collect()
+            game_ids.iter().filter_map(|id| self.get_game(id)).collect()
         } else {
             Vec::new()
         }
     }
 
     pub fn get_active_games(&self) -> Vec<&GameState> {
-        self.games
-            .values()
+        self.games_of::<GameState>()
             .filter(|game| game.result == GameResult::Ongoing)
             .collect()
     }
 
-    pub fn remove_game(&mut self, game_id: &str) -> Option<GameState> {
-        if let Some(game) = self.games.remove(game_id) {
-            for player_games in self.player_games.values_mut() {
-                player_games.retain(|id| id != game_id);
-            }
-            Some(game)
-        } else {
-            None
+    /// Every hosted game that is a `T`.
+    fn games_of<T: SeatedGame>(&self) -> impl Iterator<Item = &T> {
+        self.games.values().filter_map(|game| {
+            let game: &dyn Any = game.as_ref();
+            game.downcast_ref()
+        })
+    }
+
+    /// Removes the game `game_id`, of any kind.
+    pub fn remove_game(&mut self, game_id: &str) -> Option<Box<dyn SeatedGame>> {
+        let game = self.games.remove(game_id)?;
+        for player_games in self.player_games.values_mut() {
+            player_games.retain(|id| id != game_id);
         }
+        Some(game)
     }
 
+    /// Removes games that ended over `max_age_seconds` ago. Correspondence
+    /// games are kept, as their players may not have seen the end yet.
     pub fn cleanup_finished_games(&mut self, max_age_seconds: u64) {
         let curr_time = GameState::current_timestamp();
         let game_ids_to_remove: Vec<String> = self
-            .games
-            .iter()
-            .filter(|(_, game)| {
+            .games_of::<GameState>()
+            .filter(|game| {
                 game.result != GameResult::Ongoing
+                    && game.correspondence.is_none()
                     && (curr_time - game.last_move_at) > max_age_seconds
             })
-            .map(|(id, _)| id.clone())
+            .map(|game| game.id.clone())
+            .collect();
+
+        for game_id in game_ids_to_remove {
+            self.remove_game(&game_id);
+        }
+    }
+
+    /// Seats a player at a table, in `seat` if given or else the first free
+    /// one.
+    pub fn join_table(
+        &mut self,
+        game_id: &str,
+        player_id: String,
+        seat: Option<usize>,
+    ) -> Result<usize, String> {
+        let table = self.get_table_mut(game_id).ok_or("Game not found")?;
+
+        let seat = table.seat_player(player_id.clone(), seat)?;
+
+        let player_games = self.player_games.entry(player_id).or_default();
+        if !player_games.iter().any(|id| id == game_id) {
+            player_games.push(game_id.to_string());
+        }
+        Ok(seat)
+    }
+
+    pub fn spectate_table(&mut self, game_id: &str, player_id: String) -> Result<(), String> {
+        let table = self.get_table_mut(game_id).ok_or("Game not found")?;
+        table.add_spectator(player_id);
+        Ok(())
+    }
+
+    /// Plays a move in the game `game_id`, of any kind, sent as JSON in its
+    /// game's own format.
+    pub fn play_turn(
+        &mut self,
+        game_id: &str,
+        player_id: &str,
+        payload: serde_json::Value,
+    ) -> Result<(), String> {
+        self.play_turn_with_lag(game_id, player_id, payload, 0)
+    }
+
+    pub fn play_turn_with_lag(
+        &mut self,
+        game_id: &str,
+        player_id: &str,
+        payload: serde_json::Value,
+        lag_ms: u64,
+    ) -> Result<(), String> {
+        self.seated_mut(game_id)?.play(player_id, payload, lag_ms)
+    }
+
+    pub fn get_table(&self, game_id: &str) -> Option<&Table> {
+        self.get_as(game_id)
+    }
+
+    pub fn get_table_mut(&mut self, game_id: &str) -> Option<&mut Table> {
+        self.get_as_mut(game_id)
+    }
+
+    pub fn get_active_tables(&self) -> Vec<&Table> {
+        self.games_of::<Table>()
+            .filter(|table| !table.is_over())
+            .collect()
+    }
+
+    /// Removes finished tables and tables idle for longer than
+    /// `timeout_secs`.
+    pub fn cleanup_tables(&mut self, timeout_secs: u64) {
+        let curr_time = GameState::current_timestamp();
+        let game_ids_to_remove: Vec<String> = self
+            .games_of::<Table>()
+            .filter(|table| {
+                table.is_over() || curr_time.saturating_sub(table.last_move_at) >= timeout_secs
+            })
+            .map(|table| table.id.clone())
             .collect();
 
         for game_id in game_ids_to_remove {
@@ -561,10 +1132,7 @@ impl GameManager {
     }
 
     pub fn get_active_game_count(&self) -> usize {
-        self.games
-            .values()
-            .filter(|game| game.result == GameResult::Ongoing)
-            .count()
+        self.get_active_games().len()
     }
 }
 
@@ -577,7 +1145,7 @@ impl Default for GameManager {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::game::Position;
+    use crate::game::{Position, TimeControl};
 
     #[test]
     fn test_game_creation() {
@@ -587,6 +1155,35 @@ mod tests {
         assert!(manager.get_game(&game_id).is_some());
     }
 
+    #[test]
+    fn test_create_chess960_game() {
+        let mut manager = GameManager::new();
+        let game_id = manager
+            .create_game_with_variant(Variant::Chess960, Some(0))
+            .unwrap();
+
+        let game = manager.get_game(&game_id).unwrap();
+        assert_eq!(game.variant, Variant::Chess960);
+        assert_eq!(game.get_game_info().variant, Variant::Chess960);
+        assert!(game.board.to_fen().starts_with("bbqnnrkr/pppppppp/"));
+
+        let random = manager
+            .create_game_with_variant(Variant::Chess960, None)
+            .unwrap();
+        assert!(manager.get_game(&random).unwrap().board.is_chess960());
+
+        assert!(
+            manager
+                .create_game_with_variant(Variant::Chess960, Some(960))
+                .is_err()
+        );
+        assert!(
+            manager
+                .create_game_with_variant(Variant::Standard, Some(518))
+                .is_err()
+        );
+    }
+
     #[test]
     fn test_player_joining() {
         let mut manager = GameManager::new();
@@ -632,6 +1229,138 @@ mod tests {
         assert_eq!(game.board.get_to_move(), Color::Black);
     }
 
+    #[test]
+    fn test_flag_fall() {
+        let flagged = |fen: &str| {
+            let mut game = GameState::from_fen(fen).unwrap();
+            game.set_clock(TimeControl::new(0, 0).clock());
+            game.add_player("white_player".to_string(), Some(Color::White))
+                .unwrap();
+            assert_eq!(game.clock.as_ref().unwrap().active(), None);
+            game.add_player("black_player".to_string(), Some(Color::Black))
+                .unwrap();
+            assert_eq!(game.clock.as_ref().unwrap().active(), Some(Color::White));
+
+            let e1 = Position::from_algebraic("e1").unwrap();
+            let d1 = Position::from_algebraic("d1").unwrap();
+            assert!(game.make_move("white_player", Move::new(e1, d1)).is_err());
+            assert!(!game.check_flag());
+            assert_eq!(game.clock.as_ref().unwrap().active(), None);
+            game.result
+        };
+
+        assert_eq!(
+            flagged("4k3/8/8/8/8/8/7r/4K3 w - - 0 1"),
+            GameResult::Timeout(Color::White)
+        );
+        // A bare king cannot mate, however much White has left
+        assert_eq!(
+            flagged("4k3/8/8/8/8/8/4P3/4K3 w - - 0 1"),
+            GameResult::Draw(DrawReason::TimeoutVsInsufficientMaterial)
+        );
+    }
+
+    #[test]
+    fn test_draw_offers() {
+        let mut game = GameState::new();
+        game.add_player("white_player".to_string(), Some(Color::White))
+            .unwrap();
+        game.add_player("black_player".to_string(), Some(Color::Black))
+            .unwrap();
+        let play = |game: &mut GameState, player: &str, from: &str, to: &str| {
+            let from = Position::from_algebraic(from).unwrap();
+            let to = Position::from_algebraic(to).unwrap();
+            game.make_move(player, Move::new(from, to)).unwrap();
+        };
+
+        // An offer lapses once its maker moves on
+        play(&mut game, "white_player", "e2", "e4");
+        assert_eq!(game.offer_draw("white_player", None), Ok(false));
+        play(&mut game, "black_player", "e7", "e5");
+        assert!(game.pending_draw_offer().is_some());
+        play(&mut game, "white_player", "g1", "f3");
+        assert!(game.pending_draw_offer().is_none());
+        assert!(game.respond_to_draw("black_player", true).is_err());
+
+        // Declined offers leave the game going
+        assert_eq!(game.offer_draw("black_player", None), Ok(false));
+        assert!(game.respond_to_draw("black_player", false).is_err());
+        game.respond_to_draw("white_player", false).unwrap();
+        assert_eq!(game.result, GameResult::Ongoing);
+
+        // Offers too soon after the last are refused
+        assert!(game.offer_draw("white_player", None).is_err());
+
+        // Offering back accepts the opponent's offer
+        play(&mut game, "black_player", "b8", "c6");
+        game.draw_offers = DrawOffers::new(); // past both cooldowns
+        game.offer_draw("black_player", None).unwrap();
+        assert_eq!(game.offer_draw("white_player", None), Ok(true));
+        assert_eq!(game.result, GameResult::Draw(DrawReason::Agreement));
+    }
+
+    #[test]
+    fn test_game_from_fen() {
+        let fen = "r3k2r/8/8/8/4P3/8/8/R3K2R b KQkq e3 4 20";
+        let game = GameState::from_fen(fen).unwrap();
+
+        assert_eq!(game.board.to_fen(), fen);
+        assert_eq!(game.board.get_to_move(), Color::Black);
+        assert_eq!(game.position_history, vec![fen.to_string()]);
+        assert_eq!(game.result, GameResult::Ongoing);
+
+        let mated = GameState::from_fen("7k/6Q1/6K1/8/8/8/8/8 b - - 0 1").unwrap();
+        assert_eq!(mated.result, GameResult::Checkmate(Color::White));
+
+        assert!(GameState::from_fen("not a fen").is_err());
+    }
+
+    #[test]
+    fn test_threefold_repetition() {
+        let mut game = GameState::new();
+
+        for (i, san) in ["Nf3", "Nf6", "Ng1", "Ng8", "Nf3", "Nf6", "Ng1"]
+            .iter()
+            .enumerate()
+        {
+            let chess_move = Move::from_san(san, &game.board).unwrap();
+            game.apply_move(chess_move).unwrap();
+            assert_eq!(game.result, GameResult::Ongoing, "after ply {}", i + 1);
+        }
+
+        let chess_move = Move::from_san("Ng8", &game.board).unwrap();
+        game.apply_move(chess_move).unwrap();
+        assert_eq!(
+            game.result,
+            GameResult::Draw(DrawReason::ThreefoldRepetition)
+        );
+    }
+
+    #[test]
+    fn test_take_back() {
+        let mut game = GameState::new();
+        assert!(game.take_back().is_err());
+
+        for san in ["f3", "e5", "g4", "Qh4#"] {
+            let chess_move = Move::from_san(san, &game.board).unwrap();
+            game.apply_move(chess_move).unwrap();
+        }
+        assert_eq!(game.result, GameResult::Checkmate(Color::Black));
+
+        let taken_back = game.take_back().unwrap();
+        assert_eq!(taken_back.to_algebraic(), "d8h4");
+        assert_eq!(game.result, GameResult::Ongoing);
+        assert_eq!(game.get_move_count(), 3);
+        assert_eq!(&game.board.to_fen(), game.position_history.last().unwrap());
+        assert_eq!(
+            game.board.zobrist_key(),
+            *game.position_keys.last().unwrap()
+        );
+
+        game.result = GameResult::Resignation(Color::White);
+        assert!(game.take_back().is_err());
+    }
+
     #[test]
     fn test_invalid_move() {
         let mut manager = GameManager::new();
@@ -651,4 +1380,124 @@ mod tests {
                 .is_err()
         );
     }
+
+    #[test]
+    fn test_create_crazyhouse_game() {
+        let mut manager = GameManager::new();
+        let game_id = manager
+            .create_game_with_variant(Variant::Crazyhouse, None)
+            .unwrap();
+        let game = manager.get_game_mut(&game_id).unwrap();
+        assert_eq!(game.variant, Variant::Crazyhouse);
+
+        for uci in ["e2e4", "d7d5", "e4d5", "d8d5"] {
+            game.apply_move(Move::from_algebraic(uci).unwrap()).unwrap();
+        }
+        assert_eq!(
+            game.board.to_fen(),
+            "rnb1kbnr/ppp1pppp/8/3q4/8/8/PPPP1PPP/RNBQKBNR[Pp] w KQkq - 0 3"
+        );
+
+        game.apply_move(Move::from_san("P@e4", &game.board).unwrap())
+            .unwrap();
+        assert_eq!(
+            GameState::from_fen(&game.board.to_fen()).unwrap().variant,
+            Variant::Crazyhouse
+        );
+
+        assert!(
+            manager
+                .create_game_with_variant(Variant::Crazyhouse, Some(0))
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_games_of_kind() {
+        let mut manager = GameManager::new();
+        assert_eq!(manager.kinds()[0], CHESS_KIND);
+        assert!(manager.create_game_of_kind("go").is_err());
+
+        // Chess is created through the registry like the other kinds
+        let chess_id = manager.create_game_of_kind(CHESS_KIND).unwrap();
+        manager
+            .join_game(&chess_id, "white".to_string(), Some(Color::White))
+            .unwrap();
+        manager
+            .join_game(&chess_id, "black".to_string(), Some(Color::Black))
+            .unwrap();
+        manager
+            .play_turn(&chess_id, "white", serde_json::json!("e4"))
+            .unwrap();
+        assert_eq!(manager.get_game(&chess_id).unwrap().move_history.len(), 1);
+        assert!(manager.get_table(&chess_id).is_none());
+
+        let game_id = manager.create_game_of_kind("tic-tac-toe").unwrap();
+        assert!(manager.get_game(&game_id).is_none());
+        assert_eq!(manager.join_table(&game_id, "x".to_string(), None), Ok(0));
+        assert_eq!(manager.join_table(&game_id, "o".to_string(), None), Ok(1));
+        manager
+            .play_turn(&game_id, "x", serde_json::json!(4))
+            .unwrap();
+        assert!(
+            manager
+                .play_turn(&game_id, "x", serde_json::json!(0))
+                .is_err()
+        );
+        assert_eq!(manager.get_active_tables().len(), 1);
+
+        // Resignations and draws reach tables and chess games alike
+        assert_eq!(
+            manager.player_id(&game_id, Color::Black),
+            Some(&"o".to_string())
+        );
+        assert_eq!(manager.offer_draw(&game_id, "o", None), Ok(false));
+        assert!(manager.pending_draw_offer(&game_id).is_some());
+        assert!(manager.respond_to_draw(&game_id, "x", false).is_ok());
+        manager.resign(&game_id, "o").unwrap();
+        assert!(manager.get_table(&game_id).unwrap().is_over());
+        assert!(manager.resign("missing", "o").is_err());
+
+        manager.cleanup_tables(0);
+        assert!(manager.get_table(&game_id).is_none());
+        assert!(manager.has_game(&chess_id));
+    }
+
+    #[test]
+    fn test_timed_table() {
+        let mut manager = GameManager::new();
+        let game_id = manager.create_game_of_kind("xiangqi").unwrap();
+        manager
+            .get_table_mut(&game_id)
+            .unwrap()
+            .set_clock(TimeControl::new(60, 0).clock());
+        manager
+            .join_table(&game_id, "red".to_string(), None)
+            .unwrap();
+        assert_eq!(manager.time_to_flag_ms(&game_id), None);
+        manager
+            .join_table(&game_id, "black".to_string(), None)
+            .unwrap();
+        assert!(manager.time_to_flag_ms(&game_id).is_some());
+
+        manager
+            .play_turn(&game_id, "red", serde_json::json!("C2.5"))
+            .unwrap();
+        assert!(!manager.check_flag(&game_id));
+        assert_eq!(manager.offer_draw(&game_id, "black", None), Ok(false));
+        manager
+            .play_turn(&game_id, "black", serde_json::json!("H8+7"))
+            .unwrap();
+        assert_eq!(manager.pending_draw_offer(&game_id), None);
+
+        manager.resign(&game_id, "red").unwrap();
+        let table = manager.get_table(&game_id).unwrap();
+        assert_eq!(table.result, Some(GameResult::Resignation(Color::White)));
+        assert_eq!(manager.time_to_flag_ms(&game_id), None);
+        assert!(
+            manager
+                .play_turn(&game_id, "red", serde_json::json!("h0g2"))
+                .is_err()
+        );
+    }
 }
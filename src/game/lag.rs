@@ -0,0 +1,131 @@
+use serde::{Deserialize, Serialize};
+
+/// How much network lag a clock gives back. A move may be credited up to
+/// `max_per_move_ms`, drawn from a quota that starts at `initial_quota_ms`,
+/// gains `quota_gain_ms` with every move and holds at most `max_quota_ms`,
+/// so lag cannot be claimed in full on every move of a game.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LagLimits {
+    pub max_per_move_ms: u64,
+    pub initial_quota_ms: u64,
+    pub quota_gain_ms: u64,
+    pub max_quota_ms: u64,
+}
+
+impl Default for LagLimits {
+    fn default() -> Self {
+        Self {
+            max_per_move_ms: 1000,
+            initial_quota_ms: 1000,
+            quota_gain_ms: 250,
+            max_quota_ms: 2500,
+        }
+    }
+}
+
+impl LagLimits {
+    /// The part of `lag_ms` that is given back with `quota_ms` left.
+    pub fn credit(&self, quota_ms: u64, lag_ms: u64) -> u64 {
+        lag_ms.min(self.max_per_move_ms).min(quota_ms)
+    }
+
+    /// The quota left after a move credited `credit_ms`.
+    pub fn quota_after(&self, quota_ms: u64, credit_ms: u64) -> u64 {
+        (quota_ms.saturating_sub(credit_ms) + self.quota_gain_ms).min(self.max_quota_ms)
+    }
+}
+
+/// A player's connection as the server has seen it: round trips measured
+/// with pings, and the lag given back to their clock for it.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LagStats {
+    pub pings: u32,
+    /// The smoothed round trip, estimated as TCP does.
+    pub rtt_ms: Option<u64>,
+    /// How far round trips stray from `rtt_ms`, smoothed the same way.
+    pub rtt_deviation_ms: u64,
+    pub max_rtt_ms: u64,
+    pub moves_compensated: u32,
+    pub compensated_ms: u64,
+}
+
+impl LagStats {
+    pub fn record_rtt(&mut self, rtt_ms: u64) {
+        self.pings += 1;
+        self.max_rtt_ms = self.max_rtt_ms.max(rtt_ms);
+        match self.rtt_ms {
+            None => {
+                self.rtt_ms = Some(rtt_ms);
+                self.rtt_deviation_ms = rtt_ms / 2;
+            }
+            Some(smoothed) => {
+                self.rtt_deviation_ms = (3 * self.rtt_deviation_ms + smoothed.abs_diff(rtt_ms)) / 4;
+                self.rtt_ms = Some((7 * smoothed + rtt_ms) / 8);
+            }
+        }
+    }
+
+    /// The most lag a move can believably have had on this connection: a
+    /// round trip, with room for jitter. Nothing before the first ping is
+    /// answered.
+    pub fn lag_allowance_ms(&self) -> u64 {
+        self.rtt_ms.map_or(0, |rtt| rtt + 2 * self.rtt_deviation_ms)
+    }
+
+    /// The lag to claim for a move the server saw take `elapsed_ms`. When
+    /// the client reports the time it saw the move take, the difference is
+    /// lag; otherwise a typical round trip is assumed.
+    pub fn lag_claim_ms(&self, elapsed_ms: u64, reported_move_ms: Option<u64>) -> u64 {
+        let Some(rtt) = self.rtt_ms else {
+            return 0;
+        };
+        let claim = match reported_move_ms {
+            Some(move_ms) => elapsed_ms.saturating_sub(move_ms),
+            None => rtt,
+        };
+        claim.min(self.lag_allowance_ms())
+    }
+
+    pub fn record_credit(&mut self, credit_ms: u64) {
+        if credit_ms > 0 {
+            self.moves_compensated += 1;
+            self.compensated_ms += credit_ms;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lag_claims() {
+        let mut stats = LagStats::default();
+        assert_eq!(stats.lag_claim_ms(5_000, Some(1_000)), 0);
+
+        stats.record_rtt(200);
+        stats.record_rtt(200);
+        assert_eq!(stats.rtt_ms, Some(200));
+        assert_eq!(stats.lag_allowance_ms(), 200 + 2 * 75);
+
+        // The client saw 4.9s of a move the server saw take 5s
+        assert_eq!(stats.lag_claim_ms(5_000, Some(4_900)), 100);
+        // Claims are held to what the connection could have lost
+        assert_eq!(stats.lag_claim_ms(5_000, Some(1_000)), 350);
+        assert_eq!(stats.lag_claim_ms(5_000, None), 200);
+    }
+
+    #[test]
+    fn test_quota() {
+        let limits = LagLimits::default();
+        let mut quota = limits.initial_quota_ms;
+        let mut credited = Vec::new();
+        for _ in 0..4 {
+            let credit = limits.credit(quota, 800);
+            quota = limits.quota_after(quota, credit);
+            credited.push(credit);
+        }
+        assert_eq!(credited, [800, 450, 250, 250]);
+        assert_eq!(limits.credit(limits.max_quota_ms, 5_000), 1000);
+    }
+}
@@ -1,9 +1,42 @@
+pub mod atomic;
+pub mod bitboard;
 pub mod board;
+pub mod bughouse;
+pub mod clock;
+pub mod correspondence;
+pub mod draw_offer;
+pub mod fog;
 pub mod game_state;
+pub mod lag;
+pub mod movegen;
+pub mod perft;
+pub mod pgn;
 pub mod piece;
+pub mod pocket;
 pub mod rules;
+pub mod ruleset;
+pub mod san;
+pub mod time_control;
+pub mod variant;
+pub mod zobrist;
 
+pub use atomic::*;
+pub use bitboard::*;
 pub use board::*;
+pub use bughouse::*;
+pub use clock::*;
+pub use correspondence::*;
+pub use draw_offer::*;
+pub use fog::*;
 pub use game_state::*;
+pub use lag::*;
+pub use movegen::*;
+pub use pgn::*;
 pub use piece::*;
+pub use pocket::*;
 pub use rules::*;
+pub use ruleset::*;
+pub use san::*;
+pub use time_control::*;
+pub use variant::*;
+pub use zobrist::*;
@@ -0,0 +1,439 @@
+use super::bitboard::{
+    Bitboard, between, bishop_attacks, king_attacks, knight_attacks, line, pawn_attacks,
+    rook_attacks,
+};
+use super::{Board, Color, Move, PieceType, Pockets, Position};
+
+const PROMOTION_PIECES: [PieceType; 4] = [
+    PieceType::Queen,
+    PieceType::Rook,
+    PieceType::Bishop,
+    PieceType::Knight,
+];
+
+/// Pieces of `by_color` attacking `square`, with sliders blocked by `occupied`.
+pub fn attackers_to(board: &Board, square: usize, by_color: Color, occupied: Bitboard) -> Bitboard {
+    let queens = board.pieces(PieceType::Queen, by_color);
+    let rooks = board.pieces(PieceType::Rook, by_color) | queens;
+    let bishops = board.pieces(PieceType::Bishop, by_color) | queens;
+
+    (pawn_attacks(by_color.opposite(), square) & board.pieces(PieceType::Pawn, by_color))
+        | (knight_attacks(square) & board.pieces(PieceType::Knight, by_color))
+        | (king_attacks(square) & board.pieces(PieceType::King, by_color))
+        | (rook_attacks(square, occupied) & rooks)
+        | (bishop_attacks(square, occupied) & bishops)
+}
+
+/// Generates every legal move for the side to move.
+///
+/// Checks and pins are resolved up front, so no candidate move has to be played
+/// out to see whether it leaves the king attacked.
+pub fn legal_moves(board: &Board) -> Vec<Move> {
+    let mut moves = Vec::with_capacity(64);
+    MoveGenerator::new(board).generate(&mut moves);
+    moves
+}
+
+/// Every move the pieces can make, ignoring checks and pins. Variants whose
+/// idea of a safe king differs from FIDE filter these themselves.
+pub fn pseudo_legal_moves(board: &Board) -> Vec<Move> {
+    let mut moves = Vec::with_capacity(64);
+    MoveGenerator::pseudo_legal(board).generate(&mut moves);
+    moves
+}
+
+/// Whether the side to move has a legal en passant capture.
+pub fn has_legal_en_passant(board: &Board) -> bool {
+    let Some(target) = board.get_en_passant_target() else {
+        return false;
+    };
+
+    let us = board.get_to_move();
+    let capturers = pawn_attacks(us.opposite(), target.index()) & board.pieces(PieceType::Pawn, us);
+    if capturers.is_empty() {
+        return false;
+    }
+
+    let generator = MoveGenerator::new(board);
+    capturers
+        .squares()
+        .any(|from| generator.is_en_passant_legal(from, target.index()))
+}
+
+struct MoveGenerator<'a> {
+    board: &'a Board,
+    us: Color,
+    them: Color,
+    ours: Bitboard,
+    theirs: Bitboard,
+    occupied: Bitboard,
+    king: Option<usize>,
+    checkers: Bitboard,
+    pinned: Bitboard,
+    // Leave the king's safety to the caller
+    pseudo_legal: bool,
+}
+
+impl<'a> MoveGenerator<'a> {
+    fn new(board: &'a Board) -> Self {
+        let us = board.get_to_move();
+        let them = us.opposite();
+        let occupied = board.occupied();
+        let king = board.pieces(PieceType::King, us).lsb();
+
+        let mut generator = Self {
+            board,
+            us,
+            them,
+            ours: board.color_pieces(us),
+            theirs: board.color_pieces(them),
+            occupied,
+            king,
+            checkers: Bitboard::EMPTY,
+            pinned: Bitboard::EMPTY,
+            pseudo_legal: false,
+        };
+
+        if let Some(king) = king {
+            generator.checkers = attackers_to(board, king, them, occupied);
+            generator.pinned = generator.pinned_pieces(king);
+        }
+
+        generator
+    }
+
+    fn pseudo_legal(board: &'a Board) -> Self {
+        let us = board.get_to_move();
+        let them = us.opposite();
+
+        Self {
+            board,
+            us,
+            them,
+            ours: board.color_pieces(us),
+            theirs: board.color_pieces(them),
+            occupied: board.occupied(),
+            king: board.pieces(PieceType::King, us).lsb(),
+            checkers: Bitboard::EMPTY,
+            pinned: Bitboard::EMPTY,
+            pseudo_legal: true,
+        }
+    }
+
+    fn pinned_pieces(&self, king: usize) -> Bitboard {
+        let queens = self.board.pieces(PieceType::Queen, self.them);
+        let snipers = (rook_attacks(king, self.theirs)
+            & (self.board.pieces(PieceType::Rook, self.them) | queens))
+            | (bishop_attacks(king, self.theirs)
+                & (self.board.pieces(PieceType::Bishop, self.them) | queens));
+
+        let mut pinned = Bitboard::EMPTY;
+        for sniper in snipers.squares() {
+            let blockers = between(king, sniper) & self.occupied;
+            if blockers.count() == 1 && !(blockers & self.ours).is_empty() {
+                pinned |= blockers;
+            }
+        }
+        pinned
+    }
+
+    fn generate(&self, moves: &mut Vec<Move>) {
+        if let Some(king) = self.king {
+            self.generate_king_moves(king, moves);
+
+            // In double check only the king can move
+            if self.checkers.has_more_than_one() {
+                return;
+            }
+        }
+
+        let targets = match (self.king, self.checkers.lsb()) {
+            (Some(king), Some(checker)) => between(king, checker) | Bitboard::from_square(checker),
+            _ => Bitboard::ALL,
+        } & !self.ours;
+
+        for from in self.ours.squares() {
+            let Some(piece_type) = self.board.piece_type_at(from) else {
+                continue;
+            };
+
+            let allowed = match (self.king, self.pinned.contains(from)) {
+                (Some(king), true) => targets & line(king, from),
+                _ => targets,
+            };
+
+            let destinations = match piece_type {
+                PieceType::Pawn => {
+                    self.generate_pawn_moves(from, allowed, moves);
+                    continue;
+                }
+                // Only Antichess boards have kings besides the one above
+                PieceType::King if Some(from) == self.king => continue,
+                PieceType::King => king_attacks(from),
+                PieceType::Knight => knight_attacks(from),
+                PieceType::Bishop => bishop_attacks(from, self.occupied),
+                PieceType::Rook => rook_attacks(from, self.occupied),
+                PieceType::Queen => {
+                    bishop_attacks(from, self.occupied) | rook_attacks(from, self.occupied)
+                }
+            };
+
+            for to in (destinations & allowed).squares() {
+                moves.push(Move::new(square(from), square(to)));
+            }
+        }
+
+        if let Some(pockets) = self.board.pockets() {
+            self.generate_drops(pockets, targets & !self.occupied, moves);
+        }
+    }
+
+    /// Drops from the pocket onto empty `targets`, which in check are the
+    /// squares that block it. Pawns never go to the first or last rank.
+    fn generate_drops(&self, pockets: &Pockets, targets: Bitboard, moves: &mut Vec<Move>) {
+        for piece_type in Pockets::PIECES {
+            if pockets.count(self.us, piece_type) == 0 {
+                continue;
+            }
+
+            let allowed = match piece_type {
+                PieceType::Pawn => targets & !(Bitboard::rank(0) | Bitboard::rank(7)),
+                _ => targets,
+            };
+            for to in allowed.squares() {
+                moves.push(Move::drop(piece_type, square(to)));
+            }
+        }
+    }
+
+    fn generate_king_moves(&self, king: usize, moves: &mut Vec<Move>) {
+        // The king must not hide behind itself along a checking ray
+        let occupied = self.occupied ^ Bitboard::from_square(king);
+
+        for to in (king_attacks(king) & !self.ours).squares() {
+            if self.pseudo_legal || attackers_to(self.board, to, self.them, occupied).is_empty() {
+                moves.push(Move::new(square(king), square(to)));
+            }
+        }
+
+        if self.checkers.is_empty() {
+            self.generate_castles(king, moves);
+        }
+    }
+
+    /// Castling with king and rook on any back rank files. The king always
+    /// ends on the g or c file and the rook next to it, as in Chess960.
+    fn generate_castles(&self, king: usize, moves: &mut Vec<Move>) {
+        let back_rank = match self.us {
+            Color::White => 0,
+            Color::Black => 7,
+        };
+        if king / 8 != back_rank {
+            return;
+        }
+
+        let rights = self.board.get_castling_rights();
+
+        for (kingside, king_file, rook_file) in [(true, 6, 5), (false, 2, 3)] {
+            let rook = back_rank * 8 + rights.rook_file(self.us, kingside) as usize;
+            let king_to = back_rank * 8 + king_file;
+            let rook_to = back_rank * 8 + rook_file;
+
+            if !rights.has(self.us, kingside)
+                || !self.board.pieces(PieceType::Rook, self.us).contains(rook)
+            {
+                continue;
+            }
+
+            // Everything the two pieces cross must be empty apart from themselves
+            let occupied =
+                self.occupied ^ Bitboard::from_square(king) ^ Bitboard::from_square(rook);
+            let king_path = between(king, king_to) | Bitboard::from_square(king_to);
+            let rook_path = between(rook, rook_to) | Bitboard::from_square(rook_to);
+            if !((king_path | rook_path) & occupied).is_empty() {
+                continue;
+            }
+
+            // With the castling rook lifted, a slider behind it on the back
+            // rank is not mistaken as blocked
+            let path_attacked = !self.pseudo_legal
+                && king_path
+                    .squares()
+                    .any(|sq| !attackers_to(self.board, sq, self.them, occupied).is_empty());
+
+            if !path_attacked {
+                let to = match self.board.is_chess960() {
+                    true => rook,
+                    false => king_to,
+                };
+                moves.push(Move::castle(square(king), square(to)));
+            }
+        }
+    }
+
+    fn generate_pawn_moves(&self, from: usize, allowed: Bitboard, moves: &mut Vec<Move>) {
+        let (forward, start_rank, last_rank): (isize, usize, usize) = match self.us {
+            Color::White => (8, 1, 7),
+            Color::Black => (-8, 6, 0),
+        };
+        let push = |sq: usize| sq.checked_add_signed(forward).filter(|&to| to < 64);
+
+        let mut destinations = pawn_attacks(self.us, from) & self.theirs;
+        if let Some(single) = push(from)
+            && !self.occupied.contains(single)
+        {
+            destinations |= Bitboard::from_square(single);
+
+            if from / 8 == start_rank
+                && let Some(double) = push(single)
+                && !self.occupied.contains(double)
+            {
+                destinations |= Bitboard::from_square(double);
+            }
+        }
+
+        for to in (destinations & allowed).squares() {
+            match to / 8 == last_rank {
+                true => {
+                    for promotion in PROMOTION_PIECES {
+                        moves.push(Move::with_promotion(square(from), square(to), promotion));
+                    }
+                }
+                false => moves.push(Move::new(square(from), square(to))),
+            }
+        }
+
+        if let Some(target) = self.board.get_en_passant_target()
+            && pawn_attacks(self.us, from).contains(target.index())
+            && self.is_en_passant_legal(from, target.index())
+        {
+            moves.push(Move::en_passant(square(from), target));
+        }
+    }
+
+    /// En passant removes two pawns from the same rank, which can expose the
+    /// king in ways pin detection does not cover, so the capture is checked on
+    /// the resulting occupancy directly.
+    fn is_en_passant_legal(&self, from: usize, target: usize) -> bool {
+        let captured = (from / 8) * 8 + target % 8;
+        if !self
+            .board
+            .pieces(PieceType::Pawn, self.them)
+            .contains(captured)
+        {
+            return false;
+        }
+
+        let Some(king) = self.king.filter(|_| !self.pseudo_legal) else {
+            return true;
+        };
+
+        let occupied =
+            (self.occupied ^ Bitboard::from_square(from) ^ Bitboard::from_square(captured))
+                | Bitboard::from_square(target);
+        let remaining = !Bitboard::from_square(captured);
+
+        (attackers_to(self.board, king, self.them, occupied) & remaining).is_empty()
+    }
+}
+
+fn square(index: usize) -> Position {
+    Position::from_index(index).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn count(fen: &str) -> usize {
+        legal_moves(&Board::from_fen(fen).unwrap()).len()
+    }
+
+    #[test]
+    fn test_reference_move_counts() {
+        assert_eq!(
+            count("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"),
+            20
+        );
+        // Kiwipete
+        assert_eq!(
+            count("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1"),
+            48
+        );
+        assert_eq!(count("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1"), 14);
+        assert_eq!(
+            count("r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1"),
+            6
+        );
+    }
+
+    #[test]
+    fn test_pins_and_checks() {
+        // The e2 knight is pinned and cannot move
+        let moves = legal_moves(&Board::from_fen("4r1k1/8/8/8/8/8/4N3/4K3 w - - 0 1").unwrap());
+        assert!(moves.iter().all(|m| m.from.to_algebraic() == "e1"));
+
+        // Double check leaves only king moves
+        let fen = "4k3/8/8/8/8/5n2/8/r3K3 w - - 0 1";
+        let moves = legal_moves(&Board::from_fen(fen).unwrap());
+        assert!(moves.iter().all(|m| m.from.to_algebraic() == "e1"));
+        assert!(!moves.is_empty());
+    }
+
+    #[test]
+    fn test_chess960_castling_encoding() {
+        // Standard boards castle with the king's two-square step
+        let castles = |fen: &str| -> Vec<String> {
+            legal_moves(&Board::from_fen(fen).unwrap())
+                .into_iter()
+                .filter(|m| m.is_castle)
+                .map(|m| m.to_algebraic())
+                .collect()
+        };
+        assert_eq!(
+            castles("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1"),
+            ["e1g1", "e1c1"]
+        );
+
+        // Chess960 boards castle by moving the king onto its rook
+        assert_eq!(
+            castles("2k5/8/8/8/8/8/8/RK2R3 w KQ - 0 1"),
+            ["b1e1", "b1a1"]
+        );
+
+        // The a1 rook only looks blocked while the b1 rook is in the way; after
+        // castling it would give check on c1
+        assert!(castles("4k3/8/8/8/8/8/8/rR2K3 w Q - 0 1").is_empty());
+    }
+
+    #[test]
+    fn test_en_passant_discovered_check() {
+        // Capturing en passant would clear the rank between the king and rook
+        let fen = "8/8/8/K2pP2r/8/8/8/7k w - d6 0 1";
+        let moves = legal_moves(&Board::from_fen(fen).unwrap());
+        assert!(moves.iter().all(|m| !m.is_en_passant));
+    }
+
+    #[test]
+    fn test_drops() {
+        // Pawns drop anywhere but the first and last rank
+        let moves = legal_moves(&Board::from_fen("4k3/8/8/8/8/8/8/4K3[P] w - - 0 1").unwrap());
+        let drops: Vec<_> = moves.iter().filter(|m| m.is_drop()).collect();
+        assert_eq!(drops.len(), 48);
+        assert!(drops.iter().all(|m| (1..7).contains(&m.to.rank)));
+        assert_eq!(moves.len(), 53);
+
+        // Dropping between the rook and the king answers the check
+        let moves = legal_moves(&Board::from_fen("4k3/8/8/8/8/8/8/r3K3[N] w - - 0 1").unwrap());
+        let drops: Vec<String> = moves
+            .iter()
+            .filter(|m| m.is_drop())
+            .map(|m| m.to_algebraic())
+            .collect();
+        assert_eq!(drops, ["N@b1", "N@c1", "N@d1"]);
+
+        // Only the side to move drops from its own pocket
+        let moves = legal_moves(&Board::from_fen("4k3/8/8/8/8/8/8/4K3[q] w - - 0 1").unwrap());
+        assert!(moves.iter().all(|m| !m.is_drop()));
+    }
+}
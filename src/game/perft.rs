@@ -0,0 +1,224 @@
+use super::{Board, Move, MoveValidator, Ruleset, StandardRules};
+
+impl MoveValidator {
+    /// Counts the leaf nodes of the legal move tree to `depth` plies.
+    pub fn perft(board: &Board, depth: u32) -> u64 {
+        perft(&StandardRules, board, depth)
+    }
+
+    /// Perft split by root move, in generation order.
+    pub fn divide(board: &Board, depth: u32) -> Vec<(Move, u64)> {
+        divide(&StandardRules, board, depth)
+    }
+}
+
+/// `MoveValidator::perft` for a variant played under `rules`.
+pub fn perft(rules: &dyn Ruleset, board: &Board, depth: u32) -> u64 {
+    let mut board = board.clone();
+    perft_nodes(rules, &mut board, depth)
+}
+
+/// `MoveValidator::divide` for a variant played under `rules`.
+pub fn divide(rules: &dyn Ruleset, board: &Board, depth: u32) -> Vec<(Move, u64)> {
+    if depth == 0 {
+        return Vec::new();
+    }
+
+    let mut board = board.clone();
+    rules
+        .legal_moves(&board)
+        .into_iter()
+        .map(|chess_move| {
+            let undo = rules
+                .make_move(&mut board, &chess_move)
+                .expect("generated move must be playable");
+            let nodes = perft_nodes(rules, &mut board, depth - 1);
+            board.unmake_move(&undo);
+            (chess_move, nodes)
+        })
+        .collect()
+}
+
+fn perft_nodes(rules: &dyn Ruleset, board: &mut Board, depth: u32) -> u64 {
+    if depth == 0 {
+        return 1;
+    }
+
+    let moves = rules.legal_moves(board);
+    if depth == 1 {
+        return moves.len() as u64;
+    }
+
+    let mut nodes = 0;
+    for chess_move in moves {
+        let undo = rules
+            .make_move(board, &chess_move)
+            .expect("generated move must be playable");
+        nodes += perft_nodes(rules, board, depth - 1);
+        board.unmake_move(&undo);
+    }
+    nodes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::Variant;
+
+    fn perft(fen: &str, depth: u32) -> u64 {
+        MoveValidator::perft(&Board::from_fen(fen).unwrap(), depth)
+    }
+
+    #[test]
+    fn test_reference_positions() {
+        let cases = [
+            (
+                "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+                3,
+                8_902,
+            ),
+            // Kiwipete
+            (
+                "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+                3,
+                97_862,
+            ),
+            ("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1", 4, 43_238),
+            (
+                "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+                3,
+                9_467,
+            ),
+            (
+                "r2q1rk1/pP1p2pp/Q4n2/bbp1p3/Np6/1B3NBn/pPPP1PPP/R3K2R b KQ - 0 1",
+                3,
+                9_467,
+            ),
+            (
+                "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+                3,
+                62_379,
+            ),
+            (
+                "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10",
+                3,
+                89_890,
+            ),
+        ];
+
+        for (fen, depth, nodes) in cases {
+            assert_eq!(perft(fen, depth), nodes, "{}", fen);
+        }
+    }
+
+    #[test]
+    fn test_edge_cases() {
+        let cases = [
+            // Self stalemate
+            ("K1k5/8/P7/8/8/8/8/8 w - - 0 1", 6, 2_217),
+            // Under-promotion to give check
+            ("8/P1k5/K7/8/8/8/8/8 w - - 0 1", 6, 92_683),
+            // Promotion to give check
+            ("4k3/1P6/8/8/8/8/K7/8 w - - 0 1", 6, 217_342),
+            ("8/8/2k5/5q2/5n2/8/5K2/8 b - - 0 1", 4, 23_527),
+            // En passant would expose the king
+            ("3k4/3p4/8/K1P4r/8/8/8/8 b - - 0 1", 4, 10_138),
+            // En passant capture gives check
+            ("8/8/1k6/2b5/2pP4/8/5K2/8 b - d3 0 1", 4, 13_931),
+            // Castling gives check
+            ("5k2/8/8/8/8/8/8/4K2R w K - 0 1", 4, 6_399),
+            ("3k4/8/8/8/8/8/8/R3K3 w Q - 0 1", 4, 7_418),
+            // Castling through check
+            ("r3k2r/8/3Q4/8/8/5q2/8/R3K2R b KQkq - 0 1", 3, 50_509),
+        ];
+
+        for (fen, depth, nodes) in cases {
+            assert_eq!(perft(fen, depth), nodes, "{}", fen);
+        }
+    }
+
+    #[test]
+    fn test_chess960_positions() {
+        let cases = [
+            (
+                "bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9",
+                12_189,
+            ),
+            (
+                "2nnrbkr/p1qppppp/8/1ppb4/6PP/3PP3/PPP2P2/BQNNRBKR w HEhe - 1 9",
+                18_002,
+            ),
+            (
+                "b1q1rrkb/pppppppp/3nn3/8/P7/1PPP4/4PPPP/BQNNRKRB w GE - 1 9",
+                10_471,
+            ),
+            (
+                "qbbnnrkr/2pp2pp/p7/1p2pp2/8/P3PP2/1PPP1KPP/QBBNNR1R w hf - 0 9",
+                13_440,
+            ),
+            (
+                "1nbbnrkr/p1p1ppp1/3p4/1p3P1p/3Pq2P/8/PPP1P1P1/QNBBNRKR w HFhf - 0 9",
+                31_058,
+            ),
+        ];
+
+        for (fen, nodes) in cases {
+            assert_eq!(perft(fen, 3), nodes, "{}", fen);
+        }
+    }
+
+    // The edge cases above at their published depths. Slow without
+    // optimizations: `cargo test --release -- --ignored`.
+    #[test]
+    #[ignore]
+    fn test_deep_edge_cases() {
+        let cases = [
+            // En passant would expose the king
+            ("3k4/3p4/8/K1P4r/8/8/8/8 b - - 0 1", 6, 1_134_888),
+            ("8/8/4k3/8/2p5/8/B2P2K1/8 w - - 0 1", 6, 1_015_133),
+            // En passant capture gives check
+            ("8/8/1k6/2b5/2pP4/8/5K2/8 b - d3 0 1", 6, 1_440_467),
+            // Castling gives check
+            ("5k2/8/8/8/8/8/8/4K2R w K - 0 1", 6, 661_072),
+            ("3k4/8/8/8/8/8/8/R3K3 w Q - 0 1", 6, 803_711),
+            // Castling rights lost and castling through check
+            ("r3k2r/1b4bq/8/8/8/8/7B/R3K2R w KQkq - 0 1", 4, 1_274_206),
+            ("r3k2r/8/3Q4/8/8/5q2/8/R3K2R b KQkq - 0 1", 4, 1_720_476),
+            // Promotion out of check
+            ("2K2r2/4P3/8/8/8/8/8/3k4 w - - 0 1", 6, 3_821_001),
+            // Discovered check
+            ("8/8/1P2K3/8/2n5/1q6/8/5k2 b - - 0 1", 5, 1_004_658),
+            // Stalemate and checkmate
+            ("8/k1P5/8/1K6/8/8/8/8 w - - 0 1", 7, 567_584),
+        ];
+
+        for (fen, depth, nodes) in cases {
+            assert_eq!(perft(fen, depth), nodes, "{}", fen);
+        }
+    }
+
+    #[test]
+    fn test_variant_start_positions() {
+        let cases = [(Variant::Horde, 1_274), (Variant::RacingKings, 11_264)];
+
+        for (variant, nodes) in cases {
+            let board = variant.starting_board().unwrap();
+            assert_eq!(
+                super::perft(variant.ruleset(), &board, 3),
+                nodes,
+                "{:?}",
+                variant
+            );
+        }
+    }
+
+    #[test]
+    fn test_divide_sums_to_perft() {
+        let board = Board::new();
+        let divide = MoveValidator::divide(&board, 3);
+
+        assert_eq!(divide.len(), 20);
+        assert_eq!(divide.iter().map(|(_, nodes)| nodes).sum::<u64>(), 8_902);
+        assert!(MoveValidator::divide(&board, 0).is_empty());
+    }
+}
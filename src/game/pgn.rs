@@ -0,0 +1,842 @@
+use thiserror::Error;
+
+use super::{Board, Color, DrawReason, GameResult, GameState, Move, Variant};
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum PgnError {
+    #[error("game {game}: malformed tag pair at line {line}")]
+    MalformedTag { game: usize, line: usize },
+
+    #[error("game {game}: unterminated comment starting at line {line}")]
+    UnterminatedComment { game: usize, line: usize },
+
+    #[error("game {game}: unbalanced variation at line {line}")]
+    UnbalancedVariation { game: usize, line: usize },
+
+    #[error("game {game}: invalid FEN tag: {reason}")]
+    InvalidFen { game: usize, reason: String },
+
+    #[error("game {game}: unsupported variant {variant}")]
+    UnsupportedVariant { game: usize, variant: String },
+
+    #[error("game {game}, ply {ply}: illegal move {san}: {reason}")]
+    IllegalMove {
+        game: usize,
+        ply: usize,
+        san: String,
+        reason: String,
+    },
+
+    #[error("no games found")]
+    NoGames,
+}
+
+/// A single game read from PGN text: its tag pairs, the SAN moves of the
+/// mainline and the game termination marker.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PgnGame {
+    pub number: usize,
+    pub tags: Vec<(String, String)>,
+    pub moves: Vec<String>,
+    pub result: Option<String>,
+}
+
+impl PgnGame {
+    fn new(number: usize) -> Self {
+        Self {
+            number,
+            tags: Vec::new(),
+            moves: Vec::new(),
+            result: None,
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.tags.is_empty() && self.moves.is_empty() && self.result.is_none()
+    }
+
+    fn has_movetext(&self) -> bool {
+        !self.moves.is_empty() || self.result.is_some()
+    }
+
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        self.tags
+            .iter()
+            .find(|(tag, _)| tag == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Replays the mainline under the game's ruleset into a fresh `GameState`.
+    pub fn to_game_state(&self) -> Result<GameState, PgnError> {
+        let variant =
+            match self.tag("Variant") {
+                Some(name) => Some(Variant::from_pgn_name(name).ok_or_else(|| {
+                    PgnError::UnsupportedVariant {
+                        game: self.number,
+                        variant: name.to_string(),
+                    }
+                })?),
+                None => None,
+            };
+
+        let mut board = match self.tag("FEN") {
+            Some(fen) => variant
+                .map_or_else(
+                    || Board::from_fen(fen),
+                    |variant| variant.board_from_fen(fen),
+                )
+                .map_err(|e| PgnError::InvalidFen {
+                    game: self.number,
+                    reason: e.to_string(),
+                })?,
+            None => variant
+                .and_then(Variant::starting_board)
+                .unwrap_or_default(),
+        };
+        let variant = variant.unwrap_or_else(|| Variant::detect(&board));
+        board.set_chess960(variant == Variant::Chess960);
+        variant.configure(&mut board);
+        let mut game = GameState::from_board(board, variant);
+
+        for (i, san) in self.moves.iter().enumerate() {
+            let illegal = |reason: String| PgnError::IllegalMove {
+                game: self.number,
+                ply: i + 1,
+                san: san.clone(),
+                reason,
+            };
+
+            // Repetitions and the fifty-move rule only entitle a player to
+            // claim a draw, so recorded games may legitimately play on.
+            if is_claimable_draw(&game.result) {
+                game.result = GameResult::Ongoing;
+            }
+
+            let chess_move = Move::from_san_with(san, &game.board, game.ruleset())
+                .map_err(|e| illegal(e.to_string()))?;
+            game.apply_move(chess_move).map_err(illegal)?;
+        }
+
+        if (game.result == GameResult::Ongoing || is_claimable_draw(&game.result))
+            && let Some(result) = self.recorded_result()
+        {
+            game.result = result;
+        }
+
+        Ok(game)
+    }
+
+    fn recorded_result(&self) -> Option<GameResult> {
+        let on_time = self
+            .tag("Termination")
+            .is_some_and(|t| t.eq_ignore_ascii_case("time forfeit"));
+
+        let loser = match self.result.as_deref()? {
+            "1-0" => Color::Black,
+            "0-1" => Color::White,
+            "1/2-1/2" if on_time => {
+                return Some(GameResult::Draw(DrawReason::TimeoutVsInsufficientMaterial));
+            }
+            "1/2-1/2" => return Some(GameResult::Draw(DrawReason::Agreement)),
+            _ => return None,
+        };
+
+        Some(match on_time {
+            true => GameResult::Timeout(loser),
+            false => GameResult::Resignation(loser),
+        })
+    }
+}
+
+fn is_claimable_draw(result: &GameResult) -> bool {
+    matches!(
+        result,
+        GameResult::Draw(DrawReason::FiftyMoveRule)
+            | GameResult::Draw(DrawReason::ThreefoldRepetition)
+    )
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Tag(String, String),
+    Move(String),
+    Result(String),
+    VariationStart,
+    VariationEnd,
+}
+
+struct Lexer<'a> {
+    chars: std::iter::Peekable<std::str::Chars<'a>>,
+    line: usize,
+    at_line_start: bool,
+}
+
+impl<'a> Lexer<'a> {
+    fn new(text: &'a str) -> Self {
+        Self {
+            chars: text.chars().peekable(),
+            line: 1,
+            at_line_start: true,
+        }
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.chars.next()?;
+        self.at_line_start = c == '\n';
+        if c == '\n' {
+            self.line += 1;
+        }
+        Some(c)
+    }
+
+    fn skip_line(&mut self) {
+        while let Some(c) = self.bump() {
+            if c == '\n' {
+                break;
+            }
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.chars.peek().is_some_and(|c| c.is_whitespace()) {
+            self.bump();
+        }
+    }
+
+    /// Returns the next token together with the line it started on.
+    /// `game` is only used to label errors.
+    fn next_token(&mut self, game: usize) -> Result<Option<(Token, usize)>, PgnError> {
+        loop {
+            self.skip_whitespace();
+            let line = self.line;
+
+            let Some(&c) = self.chars.peek() else {
+                return Ok(None);
+            };
+
+            match c {
+                '%' if self.at_line_start => self.skip_line(),
+                ';' => self.skip_line(),
+                '{' => {
+                    self.bump();
+                    loop {
+                        match self.bump() {
+                            Some('}') => break,
+                            Some(_) => {}
+                            None => return Err(PgnError::UnterminatedComment { game, line }),
+                        }
+                    }
+                }
+                '$' => {
+                    self.bump();
+                    while self.chars.peek().is_some_and(|c| c.is_ascii_digit()) {
+                        self.bump();
+                    }
+                }
+                '(' => {
+                    self.bump();
+                    return Ok(Some((Token::VariationStart, line)));
+                }
+                ')' => {
+                    self.bump();
+                    return Ok(Some((Token::VariationEnd, line)));
+                }
+                '[' => {
+                    self.bump();
+                    let tag = self
+                        .read_tag()
+                        .ok_or(PgnError::MalformedTag { game, line })?;
+                    return Ok(Some((tag, line)));
+                }
+                _ => {
+                    let symbol = self.read_symbol();
+                    if let Some(token) = classify_symbol(&symbol) {
+                        return Ok(Some((token, line)));
+                    }
+                }
+            }
+        }
+    }
+
+    fn read_tag(&mut self) -> Option<Token> {
+        self.skip_whitespace();
+
+        let mut name = String::new();
+        while let Some(&c) = self.chars.peek() {
+            if !(c.is_alphanumeric() || c == '_') {
+                break;
+            }
+            name.push(c);
+            self.bump();
+        }
+
+        self.skip_whitespace();
+        if name.is_empty() || self.bump()? != '"' {
+            return None;
+        }
+
+        let mut value = String::new();
+        loop {
+            match self.bump()? {
+                '\\' => value.push(self.bump()?),
+                '"' => break,
+                '\n' => return None,
+                c => value.push(c),
+            }
+        }
+
+        self.skip_whitespace();
+        match self.bump()? {
+            ']' => Some(Token::Tag(name, value)),
+            _ => None,
+        }
+    }
+
+    fn read_symbol(&mut self) -> String {
+        let mut symbol = String::new();
+        while let Some(&c) = self.chars.peek() {
+            if c.is_whitespace() || "{}()[];$".contains(c) {
+                break;
+            }
+            symbol.push(c);
+            self.bump();
+        }
+
+        // Guard against looping on a stray character we cannot consume
+        if symbol.is_empty() {
+            self.bump();
+        }
+        symbol
+    }
+}
+
+fn classify_symbol(symbol: &str) -> Option<Token> {
+    if matches!(symbol, "1-0" | "0-1" | "1/2-1/2" | "*") {
+        return Some(Token::Result(symbol.to_string()));
+    }
+
+    // Castling written with zeros starts with a digit but is not a move number
+    if symbol.starts_with("0-0") {
+        return Some(Token::Move(symbol.to_string()));
+    }
+
+    let san = symbol.trim_start_matches(|c: char| c.is_ascii_digit());
+    let san = match san.len() < symbol.len() {
+        true => san.trim_start_matches('.'),
+        false => san.trim_start_matches("..."),
+    };
+
+    match san.is_empty() {
+        true => None,
+        false => Some(Token::Move(san.to_string())),
+    }
+}
+
+/// Parses PGN text holding one or more games.
+pub fn parse_pgn(text: &str) -> Result<Vec<PgnGame>, PgnError> {
+    let mut lexer = Lexer::new(text);
+    let mut games = Vec::new();
+    let mut current = PgnGame::new(1);
+    let mut variation_depth = 0usize;
+    let mut variation_line = 0;
+
+    while let Some((token, line)) = lexer.next_token(current.number)? {
+        match token {
+            Token::Tag(name, value) => {
+                if current.has_movetext() {
+                    if variation_depth > 0 {
+                        return Err(PgnError::UnbalancedVariation {
+                            game: current.number,
+                            line: variation_line,
+                        });
+                    }
+                    let next = PgnGame::new(current.number + 1);
+                    games.push(std::mem::replace(&mut current, next));
+                }
+                current.tags.push((name, value));
+            }
+            Token::VariationStart => {
+                if variation_depth == 0 {
+                    variation_line = line;
+                }
+                variation_depth += 1;
+            }
+            Token::VariationEnd => {
+                if variation_depth == 0 {
+                    return Err(PgnError::UnbalancedVariation {
+                        game: current.number,
+                        line,
+                    });
+                }
+                variation_depth -= 1;
+            }
+            Token::Move(san) if variation_depth == 0 => current.moves.push(san),
+            Token::Result(result) if variation_depth == 0 => {
+                current.result = Some(result);
+                let next = PgnGame::new(current.number + 1);
+                games.push(std::mem::replace(&mut current, next));
+            }
+            Token::Move(_) | Token::Result(_) => {}
+        }
+    }
+
+    if variation_depth > 0 {
+        return Err(PgnError::UnbalancedVariation {
+            game: current.number,
+            line: variation_line,
+        });
+    }
+
+    if !current.is_empty() {
+        games.push(current);
+    }
+
+    Ok(games)
+}
+
+/// Parses and replays every game in a PGN database.
+pub fn import_pgn(text: &str) -> Result<Vec<GameState>, PgnError> {
+    parse_pgn(text)?
+        .iter()
+        .map(PgnGame::to_game_state)
+        .collect()
+}
+
+const PGN_LINE_WIDTH: usize = 80;
+
+/// Tag values and per-move annotations for exporting a game, covering the
+/// parts of a PGN record that `GameState` does not track itself.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PgnTags {
+    pub event: String,
+    pub site: String,
+    pub round: String,
+    pub white: String,
+    pub black: String,
+    pub time_control: Option<String>,
+    /// Remaining clock time in milliseconds after each ply, emitted as
+    /// `[%clk]` comments.
+    pub clocks: Vec<u64>,
+}
+
+impl Default for PgnTags {
+    fn default() -> Self {
+        Self {
+            event: "Casual game".to_string(),
+            site: "Chess Server".to_string(),
+            round: "-".to_string(),
+            white: "?".to_string(),
+            black: "?".to_string(),
+            time_control: None,
+            clocks: Vec::new(),
+        }
+    }
+}
+
+impl PgnTags {
+    pub fn for_game(game: &GameState) -> Self {
+        Self {
+            white: game.white_player.clone().unwrap_or_else(|| "?".to_string()),
+            black: game.black_player.clone().unwrap_or_else(|| "?".to_string()),
+            time_control: game.clock.as_ref().map(|clock| clock.time_control.to_pgn()),
+            clocks: game
+                .clock
+                .as_ref()
+                .map(|clock| clock.history.clone())
+                .unwrap_or_default(),
+            ..Self::default()
+        }
+    }
+}
+
+/// Formats a time control in the PGN `[TimeControl]` syntax, e.g. `300+2`.
+pub fn format_time_control(initial_time_secs: u32, increment_secs: u32) -> String {
+    match increment_secs {
+        0 => initial_time_secs.to_string(),
+        increment => format!("{}+{}", initial_time_secs, increment),
+    }
+}
+
+impl GameState {
+    /// Builds a game from the first game in `pgn`.
+    pub fn from_pgn(pgn: &str) -> Result<Self, PgnError> {
+        parse_pgn(pgn)?
+            .first()
+            .ok_or(PgnError::NoGames)?
+            .to_game_state()
+    }
+
+    pub fn to_pgn(&self) -> String {
+        self.to_pgn_with_tags(&PgnTags::for_game(self))
+    }
+
+    pub fn to_pgn_with_tags(&self, tags: &PgnTags) -> String {
+        let result = self.ruleset().pgn_result(&self.result);
+        let mut pgn = String::new();
+
+        let mut push_tag = |name: &str, value: &str| {
+            pgn.push_str(&format!("[{} \"{}\"]\n", name, escape_tag_value(value)));
+        };
+
+        // Seven tag roster
+        push_tag("Event", &tags.event);
+        push_tag("Site", &tags.site);
+        push_tag("Date", &format_date(self.created_at));
+        push_tag("Round", &tags.round);
+        push_tag("White", &tags.white);
+        push_tag("Black", &tags.black);
+        push_tag("Result", result);
+
+        if let Some(time_control) = &tags.time_control {
+            push_tag("TimeControl", time_control);
+        }
+        push_tag("Termination", termination(&self.result));
+
+        if self.variant != Variant::Standard {
+            push_tag("Variant", self.variant.pgn_name());
+        }
+
+        // Variants without a fixed setup, like Chess960, always record their
+        // start, even when it happens to be the standard one
+        let default_start = self.variant.starting_board().map(|board| board.to_fen());
+        let start_fen = self
+            .position_history
+            .first()
+            .cloned()
+            .unwrap_or_else(|| Board::new().to_fen());
+        if default_start.as_deref() != Some(start_fen.as_str()) {
+            push_tag("SetUp", "1");
+            push_tag("FEN", &start_fen);
+        }
+
+        pgn.push('\n');
+        pgn.push_str(&self.movetext(&start_fen, &tags.clocks, result));
+        pgn.push('\n');
+        pgn
+    }
+
+    fn movetext(&self, start_fen: &str, clocks: &[u64], result: &str) -> String {
+        let mut tokens = Vec::new();
+        let rules = self.ruleset();
+        let mut board = self
+            .variant
+            .board_from_fen(start_fen)
+            .unwrap_or_else(|_| Board::new());
+
+        for (i, chess_move) in self.move_history.iter().enumerate() {
+            let move_number = board.get_fullmove_number();
+            match board.get_to_move() {
+                Color::White => tokens.push(format!("{}.", move_number)),
+                Color::Black if i == 0 => tokens.push(format!("{}...", move_number)),
+                Color::Black => {}
+            }
+
+            tokens.push(chess_move.to_san_with(&board, rules));
+            if let Some(&clock_ms) = clocks.get(i) {
+                tokens.push(format!("{{[%clk {}]}}", format_clock(clock_ms)));
+            }
+
+            if rules.make_move(&mut board, chess_move).is_err() {
+                break;
+            }
+        }
+        tokens.push(result.to_string());
+
+        let mut text = String::new();
+        let mut line_len = 0;
+        for token in tokens {
+            if line_len > 0 && line_len + 1 + token.len() > PGN_LINE_WIDTH {
+                text.push('\n');
+                line_len = 0;
+            } else if line_len > 0 {
+                text.push(' ');
+                line_len += 1;
+            }
+            text.push_str(&token);
+            line_len += token.len();
+        }
+        text
+    }
+}
+
+fn escape_tag_value(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+fn termination(result: &GameResult) -> &'static str {
+    match result {
+        GameResult::Ongoing => "unterminated",
+        GameResult::Timeout(_) | GameResult::Draw(DrawReason::TimeoutVsInsufficientMaterial) => {
+            "time forfeit"
+        }
+        _ => "normal",
+    }
+}
+
+fn format_clock(ms: u64) -> String {
+    let secs = ms / 1000;
+    format!("{}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+}
+
+/// Formats a unix timestamp as a PGN `YYYY.MM.DD` date (UTC).
+fn format_date(timestamp: u64) -> String {
+    let days = (timestamp / 86_400) as i64;
+
+    // Civil-from-days conversion on the proleptic Gregorian calendar
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    format!("{:04}.{:02}.{:02}", year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::VariantWinReason;
+
+    const OPERA_GAME: &str = r#"[Event "Paris"]
+[Site "Paris FRA"]
+[Date "1858.??.??"]
+[Round "?"]
+[White "Paul Morphy"]
+[Black "Duke Karl / Count Isouard"]
+[Result "1-0"]
+
+1. e4 e5 2. Nf3 d6 3. d4 Bg4 {This is a weak move already.} 4. dxe5 Bxf3
+5. Qxf3 dxe5 6. Bc4 Nf6 7. Qb3 $1 Qe7 8. Nc3 c6 9. Bg5 b5 $6 (9... Qb4+ 10. Qxb4
+Bxb4 11. O-O (11. a3 Ba5) 11... O-O) 10. Nxb5 cxb5 11. Bxb5+ Nbd7 12. O-O-O Rd8
+13. Rxd7 Rxd7 14. Rd1 Qe6 15. Bxd7+ Nxd7 16. Qb8+ ; a queen sacrifice
+Nxb8 17. Rd8# 1-0
+"#;
+
+    #[test]
+    fn test_parse_single_game() {
+        let games = parse_pgn(OPERA_GAME).unwrap();
+        assert_eq!(games.len(), 1);
+
+        let game = &games[0];
+        assert_eq!(game.tag("White"), Some("Paul Morphy"));
+        assert_eq!(game.tag("Black"), Some("Duke Karl / Count Isouard"));
+        assert_eq!(game.moves.len(), 33);
+        assert_eq!(game.moves[5], "Bg4");
+        assert_eq!(game.moves.last().unwrap(), "Rd8#");
+        assert_eq!(game.result.as_deref(), Some("1-0"));
+
+        let state = game.to_game_state().unwrap();
+        assert_eq!(state.get_move_count(), 33);
+        assert_eq!(state.result, GameResult::Checkmate(Color::White));
+    }
+
+    #[test]
+    fn test_multi_game_database() {
+        let text = format!(
+            "{}\n[Event \"Second\"]\n[FEN \"4k3/8/8/8/8/8/4P3/4K3 w - - 0 1\"]\n[SetUp \"1\"]\n\n1. e4 Kd7 2. e5 1/2-1/2\n\n1. d4 d5 *",
+            OPERA_GAME
+        );
+
+        let games = import_pgn(&text).unwrap();
+        assert_eq!(games.len(), 3);
+
+        assert_eq!(games[1].get_move_count(), 3);
+        assert_eq!(games[1].result, GameResult::Draw(DrawReason::Agreement));
+        assert_eq!(games[2].get_move_count(), 2);
+        assert_eq!(games[2].result, GameResult::Ongoing);
+    }
+
+    #[test]
+    fn test_recorded_result() {
+        let pgn = "[Termination \"time forfeit\"]\n\n1. f3 e5 2. g4 0-1";
+        let game = GameState::from_pgn(pgn).unwrap();
+        assert_eq!(game.result, GameResult::Timeout(Color::White));
+
+        let pgn = "1. e4 e5 2. Nf3 0-1";
+        let game = GameState::from_pgn(pgn).unwrap();
+        assert_eq!(game.result, GameResult::Resignation(Color::White));
+    }
+
+    #[test]
+    fn test_illegal_move_reports_ply() {
+        let text = format!("{}\n\n1. e4 e5 2. Nf3 Nc6 3. Bb5 Nf3 1-0", OPERA_GAME);
+
+        match import_pgn(&text) {
+            Err(PgnError::IllegalMove { game, ply, san, .. }) => {
+                assert_eq!(game, 2);
+                assert_eq!(ply, 6);
+                assert_eq!(san, "Nf3");
+            }
+            other => panic!("Expected IllegalMove, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_syntax_errors() {
+        assert!(matches!(
+            parse_pgn("[Event \"Unclosed]\n1. e4 *"),
+            Err(PgnError::MalformedTag { line: 1, .. })
+        ));
+        assert!(matches!(
+            parse_pgn("1. e4 {never closed"),
+            Err(PgnError::UnterminatedComment { .. })
+        ));
+        assert!(matches!(
+            parse_pgn("1. e4 (1. d4 d5 *"),
+            Err(PgnError::UnbalancedVariation { .. })
+        ));
+        assert!(matches!(
+            parse_pgn("1. e4 e5) *"),
+            Err(PgnError::UnbalancedVariation { .. })
+        ));
+        assert_eq!(GameState::from_pgn("").unwrap_err(), PgnError::NoGames);
+    }
+
+    #[test]
+    fn test_escape_lines_and_compact_move_numbers() {
+        let pgn = "% exported by a tool\n1.e4 e5 2.Nf3 3...Nc6?! $2 *";
+        let games = parse_pgn(pgn).unwrap();
+        assert_eq!(games[0].moves, vec!["e4", "e5", "Nf3", "Nc6?!"]);
+    }
+
+    #[test]
+    fn test_format_date() {
+        assert_eq!(format_date(0), "1970.01.01");
+        assert_eq!(format_date(951_782_400), "2000.02.29");
+        assert_eq!(format_date(1_735_689_599), "2024.12.31");
+    }
+
+    #[test]
+    fn test_export_round_trip() {
+        let original = GameState::from_pgn(OPERA_GAME).unwrap();
+        let tags = PgnTags {
+            white: "Paul \"Morphy\"".to_string(),
+            time_control: Some(format_time_control(300, 2)),
+            clocks: vec![299_000, 3_600_000],
+            ..PgnTags::default()
+        };
+        let pgn = original.to_pgn_with_tags(&tags);
+
+        assert!(pgn.contains("[White \"Paul \\\"Morphy\\\"\"]\n"));
+        assert!(pgn.contains("[Round \"-\"]\n[White"));
+        assert!(pgn.contains("[TimeControl \"300+2\"]"));
+        assert!(pgn.contains("[Termination \"normal\"]"));
+        assert!(!pgn.contains("[FEN"));
+        assert!(pgn.contains("1. e4 {[%clk 0:04:59]} e5 {[%clk 1:00:00]} 2. Nf3 d6"));
+        assert!(pgn.contains("17. Rd8#"));
+        assert!(pgn.ends_with(" 1-0\n") || pgn.ends_with("\n1-0\n"));
+        assert!(pgn.lines().all(|line| line.len() <= PGN_LINE_WIDTH));
+
+        let reimported = parse_pgn(&pgn).unwrap();
+        assert_eq!(reimported[0].tag("White"), Some("Paul \"Morphy\""));
+        assert_eq!(
+            reimported[0].to_game_state().unwrap().move_history,
+            original.move_history
+        );
+    }
+
+    #[test]
+    fn test_export_custom_start() {
+        let pgn = "[FEN \"4k3/8/8/8/8/8/4P3/4K3 b - - 0 12\"]\n\n12... Kd7 13. e4 *";
+        let game = GameState::from_pgn(pgn).unwrap();
+        let exported = game.to_pgn();
+
+        assert!(exported.contains("[SetUp \"1\"]\n[FEN \"4k3/8/8/8/8/8/4P3/4K3 b - - 0 12\"]"));
+        assert!(exported.contains("[Termination \"unterminated\"]"));
+        assert!(exported.contains("\n12... Kd7 13. e4 *\n"));
+    }
+
+    #[test]
+    fn test_chess960_round_trip() {
+        // The standard setup still plays under Chess960 castling rules
+        let mut game = GameState::new_chess960(Some(518)).unwrap();
+        for san in ["e4", "e5", "Nf3", "Nc6", "Bc4", "Bc5", "O-O"] {
+            let chess_move = Move::from_san(san, &game.board).unwrap();
+            game.apply_move(chess_move).unwrap();
+        }
+        assert_eq!(game.move_history[6].to_algebraic(), "e1h1");
+
+        let pgn = game.to_pgn();
+        assert!(pgn.contains("[Variant \"Chess960\"]\n[SetUp \"1\"]\n[FEN \"rnbqkbnr/"));
+        assert!(pgn.contains("4. O-O *"));
+
+        let reimported = GameState::from_pgn(&pgn).unwrap();
+        assert_eq!(reimported.variant, Variant::Chess960);
+        assert_eq!(reimported.move_history, game.move_history);
+        assert_eq!(reimported.board.to_fen(), game.board.to_fen());
+    }
+
+    #[test]
+    fn test_variant_tag() {
+        let game = GameState::from_pgn("[Variant \"Standard\"]\n\n1. e4 *").unwrap();
+        assert_eq!(game.variant, Variant::Standard);
+        assert!(!game.to_pgn().contains("[Variant"));
+
+        assert_eq!(
+            GameState::from_pgn("[Variant \"Bughouse\"]\n\n1. e4 *").unwrap_err(),
+            PgnError::UnsupportedVariant {
+                game: 1,
+                variant: "Bughouse".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn test_crazyhouse_round_trip() {
+        let pgn = "[Variant \"Crazyhouse\"]\n\n1. e4 d5 2. exd5 Qxd5 3. P@e4 Qa5 4. Nc3 P@d4 *";
+        let game = GameState::from_pgn(pgn).unwrap();
+        assert_eq!(game.variant, Variant::Crazyhouse);
+        assert!(game.move_history[4].is_drop());
+
+        let exported = game.to_pgn();
+        assert!(exported.contains("[Variant \"Crazyhouse\"]"));
+        assert!(!exported.contains("[FEN"));
+        assert!(exported.contains("3. P@e4 Qa5 4. Nc3 P@d4 *"));
+
+        let reimported = GameState::from_pgn(&exported).unwrap();
+        assert_eq!(reimported.move_history, game.move_history);
+        assert_eq!(reimported.board.to_fen(), game.board.to_fen());
+    }
+
+    #[test]
+    fn test_antichess_forced_captures() {
+        let pgn = "[Variant \"Antichess\"]\n\n1. e3 b5 2. Bxb5 c6 3. Bxc6 Nxc6 *";
+        let game = GameState::from_pgn(pgn).unwrap();
+        assert_eq!(game.variant, Variant::Antichess);
+
+        let reimported = GameState::from_pgn(&game.to_pgn()).unwrap();
+        assert_eq!(reimported.board.to_fen(), game.board.to_fen());
+
+        // Passing up the bishop capture is not allowed
+        let pgn = "[Variant \"Antichess\"]\n\n1. e3 b5 2. Nc3 *";
+        assert!(matches!(
+            GameState::from_pgn(pgn).unwrap_err(),
+            PgnError::IllegalMove { ply: 3, .. }
+        ));
+    }
+
+    #[test]
+    fn test_three_check_round_trip() {
+        let pgn =
+            "[Variant \"Three-check\"]\n\n1. e4 e5 2. Bc4 Nc6 3. Bxf7+ Kxf7 4. Qh5+ g6 5. Qf3+ 1-0";
+        let game = GameState::from_pgn(pgn).unwrap();
+        assert_eq!(game.variant, Variant::ThreeCheck);
+        assert_eq!(
+            game.result,
+            GameResult::VariantWin(Color::White, VariantWinReason::ThreeChecks)
+        );
+
+        let exported = game.to_pgn();
+        assert!(exported.contains("[Result \"1-0\"]"));
+        assert!(!exported.contains("[FEN"));
+
+        let reimported = GameState::from_pgn(&exported).unwrap();
+        assert_eq!(reimported.result, game.result);
+        assert_eq!(reimported.board.to_fen(), game.board.to_fen());
+    }
+}
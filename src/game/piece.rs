@@ -13,6 +13,10 @@ impl Color {
             Color::Black => Color::White,
         }
     }
+
+    pub fn index(self) -> usize {
+        self as usize
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
@@ -25,6 +29,21 @@ pub enum PieceType {
     King,
 }
 
+impl PieceType {
+    pub const ALL: [PieceType; 6] = [
+        PieceType::Pawn,
+        PieceType::Rook,
+        PieceType::Knight,
+        PieceType::Bishop,
+        PieceType::Queen,
+        PieceType::King,
+    ];
+
+    pub fn index(self) -> usize {
+        self as usize
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Piece {
     pub piece_type: PieceType,
@@ -106,6 +125,21 @@ impl Position {
         }
     }
 
+    /// Square index from 0 (a1) to 63 (h8).
+    pub fn index(&self) -> usize {
+        self.rank as usize * 8 + self.file as usize
+    }
+
+    pub fn from_index(index: usize) -> Option<Self> {
+        match index < 64 {
+            true => Some(Self {
+                file: (index % 8) as u8,
+                rank: (index / 8) as u8,
+            }),
+            false => None,
+        }
+    }
+
     pub fn from_algebraic(notation: &str) -> Option<Self> {
         let chars: Vec<char> = notation.chars().collect();
         if chars.len() != 2 {
@@ -151,6 +185,10 @@ pub struct Move {
     pub promotion: Option<PieceType>,
     pub is_castle: bool,
     pub is_en_passant: bool,
+    /// Piece type placed from the pocket; `from` and `to` are both the
+    /// target square.
+    #[serde(default)]
+    pub drop: Option<PieceType>,
 }
 
 impl Move {
@@ -161,6 +199,7 @@ impl Move {
             promotion: None,
             is_castle: false,
             is_en_passant: false,
+            drop: None,
         }
     }
 
@@ -171,6 +210,7 @@ impl Move {
             promotion: Some(promotion),
             is_castle: false,
             is_en_passant: false,
+            drop: None,
         }
     }
 
@@ -181,6 +221,7 @@ impl Move {
             promotion: None,
             is_castle: true,
             is_en_passant: false,
+            drop: None,
         }
     }
 
@@ -191,10 +232,31 @@ impl Move {
             promotion: None,
             is_castle: false,
             is_en_passant: true,
+            drop: None,
         }
     }
 
+    pub fn drop(piece_type: PieceType, to: Position) -> Self {
+        Self {
+            from: to,
+            to,
+            promotion: None,
+            is_castle: false,
+            is_en_passant: false,
+            drop: Some(piece_type),
+        }
+    }
+
+    pub fn is_drop(&self) -> bool {
+        self.drop.is_some()
+    }
+
     pub fn to_algebraic(&self) -> String {
+        if let Some(piece_type) = self.drop {
+            let piece = Piece::new(piece_type, Color::White).to_fen_char();
+            return format!("{}@{}", piece, self.to.to_algebraic());
+        }
+
         let mut result = format!("{}{}", self.from.to_algebraic(), self.to.to_algebraic());
 
         if let Some(promotion) = self.promotion {
@@ -203,6 +265,7 @@ impl Move {
                 PieceType::Rook => 'r',
                 PieceType::Bishop => 'b',
                 PieceType::Knight => 'n',
+                PieceType::King => 'k',
                 _ => 'q',
             };
             result.push(promotion_char);
@@ -212,6 +275,14 @@ impl Move {
     }
 
     pub fn from_algebraic(notation: &str) -> Option<Self> {
+        if let Some((piece, square)) = notation.split_once('@') {
+            let piece = Piece::from_fen_char(piece.chars().next()?).filter(|_| piece.len() == 1)?;
+            return Some(Self::drop(
+                piece.piece_type,
+                Position::from_algebraic(square)?,
+            ));
+        }
+
         if notation.len() < 4 {
             return None;
         }
@@ -228,6 +299,7 @@ impl Move {
                 'r' => Some(PieceType::Rook),
                 'b' => Some(PieceType::Bishop),
                 'n' => Some(PieceType::Knight),
+                'k' => Some(PieceType::King),
                 _ => None,
             };
         }
@@ -270,5 +342,13 @@ mod tests {
 
         let parsed_move = Move::from_algebraic("e2e4q").unwrap();
         assert_eq!(parsed_move.promotion, Some(PieceType::Queen));
+
+        let drop = Move::from_algebraic("N@f3").unwrap();
+        assert_eq!(
+            drop,
+            Move::drop(PieceType::Knight, Position::from_algebraic("f3").unwrap())
+        );
+        assert_eq!(drop.to_algebraic(), "N@f3");
+        assert!(Move::from_algebraic("NN@f3").is_none());
     }
 }
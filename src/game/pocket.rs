@@ -0,0 +1,112 @@
+use serde::{Deserialize, Serialize};
+
+use super::{Color, Piece, PieceType};
+
+/// Pieces each side holds in hand and may drop back onto the board, as in
+/// Crazyhouse.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Pockets {
+    counts: [[u8; 5]; 2],
+}
+
+impl Pockets {
+    /// Piece types a pocket can hold, in FEN order.
+    pub const PIECES: [PieceType; 5] = [
+        PieceType::Queen,
+        PieceType::Rook,
+        PieceType::Bishop,
+        PieceType::Knight,
+        PieceType::Pawn,
+    ];
+
+    pub fn count(&self, color: Color, piece_type: PieceType) -> u8 {
+        match piece_type {
+            PieceType::King => 0,
+            _ => self.counts[color.index()][piece_type.index()],
+        }
+    }
+
+    /// Adds a piece to `color`'s pocket. Kings are never held in hand.
+    pub fn add(&mut self, color: Color, piece_type: PieceType) {
+        if piece_type != PieceType::King {
+            self.counts[color.index()][piece_type.index()] += 1;
+        }
+    }
+
+    /// Takes a piece out of `color`'s pocket, if there is one.
+    pub fn take(&mut self, color: Color, piece_type: PieceType) -> bool {
+        match self.count(color, piece_type) {
+            0 => false,
+            _ => {
+                self.counts[color.index()][piece_type.index()] -= 1;
+                true
+            }
+        }
+    }
+
+    pub fn is_empty(&self, color: Color) -> bool {
+        self.counts[color.index()].iter().all(|&count| count == 0)
+    }
+
+    /// Every piece in `color`'s pocket, one entry per piece.
+    pub fn pieces(&self, color: Color) -> Vec<PieceType> {
+        Self::PIECES
+            .into_iter()
+            .flat_map(|piece_type| {
+                std::iter::repeat_n(piece_type, self.count(color, piece_type) as usize)
+            })
+            .collect()
+    }
+
+    /// The contents of a FEN pocket, e.g. `QNpp`, without the brackets.
+    pub fn to_fen(&self) -> String {
+        [Color::White, Color::Black]
+            .into_iter()
+            .flat_map(|color| {
+                self.pieces(color)
+                    .into_iter()
+                    .map(move |piece_type| Piece::new(piece_type, color).to_fen_char())
+            })
+            .collect()
+    }
+
+    pub fn from_fen(field: &str) -> Option<Self> {
+        let mut pockets = Self::default();
+        for c in field.chars() {
+            let piece = Piece::from_fen_char(c)?;
+            if piece.piece_type == PieceType::King {
+                return None;
+            }
+            pockets.add(piece.color, piece.piece_type);
+        }
+        Some(pockets)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pocket_fen() {
+        let pockets = Pockets::from_fen("pNqQp").unwrap();
+        assert_eq!(pockets.count(Color::Black, PieceType::Pawn), 2);
+        assert_eq!(pockets.count(Color::White, PieceType::Queen), 1);
+        assert_eq!(pockets.to_fen(), "QNqpp");
+        assert_eq!(Pockets::from_fen("").unwrap().to_fen(), "");
+
+        assert!(Pockets::from_fen("K").is_none());
+        assert!(Pockets::from_fen("x").is_none());
+    }
+
+    #[test]
+    fn test_take() {
+        let mut pockets = Pockets::default();
+        assert!(!pockets.take(Color::White, PieceType::Knight));
+
+        pockets.add(Color::White, PieceType::Knight);
+        assert!(!pockets.is_empty(Color::White));
+        assert!(pockets.take(Color::White, PieceType::Knight));
+        assert!(pockets.is_empty(Color::White));
+    }
+}
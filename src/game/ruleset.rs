@@ -0,0 +1,866 @@
+use std::ops::RangeInclusive;
+
+use super::bitboard::Bitboard;
+use super::movegen::{has_legal_en_passant, pseudo_legal_moves};
+use super::{
+    AtomicValidator, Board, Color, DrawReason, FenError, GameResult, GameState, Move, MoveUndo,
+    MoveValidator, PieceType, Position, VariantWinReason,
+};
+
+/// The rules a variant plays by: which moves are legal, when the game is over
+/// and how its results read.
+///
+/// `GameState` looks its ruleset up from its `Variant`, so a new variant only
+/// needs an implementation here and an arm in `Variant::ruleset`.
+pub trait Ruleset: Send + Sync {
+    fn legal_moves(&self, board: &Board) -> Vec<Move>;
+
+    fn is_legal_move(&self, board: &Board, chess_move: &Move) -> bool {
+        self.legal_moves(board).contains(chess_move)
+    }
+
+    fn is_in_check(&self, board: &Board, color: Color) -> bool;
+
+    fn is_checkmate(&self, board: &Board) -> bool {
+        self.is_in_check(board, board.get_to_move()) && self.legal_moves(board).is_empty()
+    }
+
+    fn is_stalemate(&self, board: &Board) -> bool {
+        !self.is_in_check(board, board.get_to_move()) && self.legal_moves(board).is_empty()
+    }
+
+    /// Plays a legal `chess_move` on `board`.
+    fn make_move(&self, board: &mut Board, chess_move: &Move) -> Result<MoveUndo, String> {
+        board.make_move(chess_move)
+    }
+
+    /// Whether the side to move can capture en passant, which makes the
+    /// target square part of the position.
+    fn has_legal_en_passant(&self, board: &Board) -> bool {
+        has_legal_en_passant(board)
+    }
+
+    /// Key of the position on `board`, equal for positions that count as
+    /// repeated.
+    fn position_key(&self, board: &Board) -> u64 {
+        board.zobrist_key_with(self.has_legal_en_passant(board))
+    }
+
+    /// Checks a position read from FEN could occur in a game under these
+    /// rules. `Board` has already checked its castling rights and en passant
+    /// target.
+    fn validate(&self, board: &Board) -> Result<(), FenError> {
+        validate_kings(board, Color::White, 1..=1)?;
+        validate_kings(board, Color::Black, 1..=1)?;
+        validate_back_rank_pawns(board, Bitboard::EMPTY)?;
+        validate_opponent_not_in_check(self, board)
+    }
+
+    /// How the game ended after the last move, or `None` while it goes on.
+    fn game_result(&self, game: &GameState) -> Option<GameResult>;
+
+    /// How the game ends when `flagged` runs out of time.
+    fn timeout_result(&self, _game: &GameState, flagged: Color) -> GameResult {
+        GameResult::Timeout(flagged)
+    }
+
+    /// The PGN game termination marker for `result`.
+    fn pgn_result(&self, result: &GameResult) -> &'static str {
+        result.pgn_result()
+    }
+}
+
+/// Checks `color` has as many kings as `allowed`.
+fn validate_kings(
+    board: &Board,
+    color: Color,
+    allowed: RangeInclusive<u32>,
+) -> Result<(), FenError> {
+    let kings = board.pieces(PieceType::King, color).count();
+    if kings < *allowed.start() {
+        return Err(FenError::MissingKing(color));
+    }
+    if kings > *allowed.end() {
+        return Err(FenError::TooManyKings(color));
+    }
+    Ok(())
+}
+
+/// Checks no pawn stands on the first or last rank outside `allowed`.
+fn validate_back_rank_pawns(board: &Board, allowed: Bitboard) -> Result<(), FenError> {
+    let back_ranks = Bitboard::rank(0) | Bitboard::rank(7);
+    let misplaced = (board.pieces_of_type(PieceType::Pawn) & back_ranks) & !allowed;
+    match misplaced.lsb() {
+        Some(square) => Err(FenError::PawnOnBackRank(
+            Position::from_index(square).unwrap().to_algebraic(),
+        )),
+        None => Ok(()),
+    }
+}
+
+/// The side that just moved cannot have left its king in check.
+fn validate_opponent_not_in_check<R: Ruleset + ?Sized>(
+    rules: &R,
+    board: &Board,
+) -> Result<(), FenError> {
+    match rules.is_in_check(board, board.get_to_move().opposite()) {
+        true => Err(FenError::OpponentInCheck),
+        false => Ok(()),
+    }
+}
+
+/// FIDE rules. Chess960 plays by them too, its castling lives in `Board`.
+pub struct StandardRules;
+
+impl Ruleset for StandardRules {
+    fn legal_moves(&self, board: &Board) -> Vec<Move> {
+        MoveValidator::generate_legal_moves(board)
+    }
+
+    fn is_legal_move(&self, board: &Board, chess_move: &Move) -> bool {
+        MoveValidator::is_valid_move(board, chess_move)
+    }
+
+    fn is_in_check(&self, board: &Board, color: Color) -> bool {
+        MoveValidator::is_in_check(board, color)
+    }
+
+    fn game_result(&self, game: &GameState) -> Option<GameResult> {
+        let board = &game.board;
+
+        if self.is_checkmate(board) {
+            return Some(GameResult::Checkmate(board.get_to_move().opposite()));
+        }
+
+        if self.is_stalemate(board) {
+            return Some(GameResult::Stalemate);
+        }
+
+        if MoveValidator::is_draw_by_fifty_move_rule(board) {
+            return Some(GameResult::Draw(DrawReason::FiftyMoveRule));
+        }
+
+        if game.is_threefold_repetition() {
+            return Some(GameResult::Draw(DrawReason::ThreefoldRepetition));
+        }
+
+        if game.is_insufficient_material() {
+            return Some(GameResult::Draw(DrawReason::InsufficientMaterial));
+        }
+
+        None
+    }
+
+    /// Running out of time only loses if the opponent could still mate.
+    fn timeout_result(&self, game: &GameState, flagged: Color) -> GameResult {
+        match game.has_mating_material(flagged.opposite()) {
+            true => GameResult::Timeout(flagged),
+            false => GameResult::Draw(DrawReason::TimeoutVsInsufficientMaterial),
+        }
+    }
+}
+
+/// FIDE moves plus drops, which the move generator adds whenever the board
+/// has pockets. Pieces keep coming back, so material never runs out and the
+/// fifty-move rule does not apply.
+pub struct CrazyhouseRules;
+
+impl Ruleset for CrazyhouseRules {
+    fn legal_moves(&self, board: &Board) -> Vec<Move> {
+        MoveValidator::generate_legal_moves(board)
+    }
+
+    fn is_legal_move(&self, board: &Board, chess_move: &Move) -> bool {
+        MoveValidator::is_valid_move(board, chess_move)
+    }
+
+    fn is_in_check(&self, board: &Board, color: Color) -> bool {
+        MoveValidator::is_in_check(board, color)
+    }
+
+    fn game_result(&self, game: &GameState) -> Option<GameResult> {
+        let board = &game.board;
+
+        if self.is_checkmate(board) {
+            return Some(GameResult::Checkmate(board.get_to_move().opposite()));
+        }
+
+        if self.is_stalemate(board) {
+            return Some(GameResult::Stalemate);
+        }
+
+        if game.is_threefold_repetition() {
+            return Some(GameResult::Draw(DrawReason::ThreefoldRepetition));
+        }
+
+        None
+    }
+}
+
+/// Crazyhouse moves on one board of a Bughouse match. Pockets are filled from
+/// the partner board, so positions never really repeat and only checkmate
+/// and stalemate end the game.
+pub struct BughouseRules;
+
+impl Ruleset for BughouseRules {
+    fn legal_moves(&self, board: &Board) -> Vec<Move> {
+        MoveValidator::generate_legal_moves(board)
+    }
+
+    fn is_legal_move(&self, board: &Board, chess_move: &Move) -> bool {
+        MoveValidator::is_valid_move(board, chess_move)
+    }
+
+    fn is_in_check(&self, board: &Board, color: Color) -> bool {
+        MoveValidator::is_in_check(board, color)
+    }
+
+    fn game_result(&self, game: &GameState) -> Option<GameResult> {
+        let board = &game.board;
+
+        if self.is_checkmate(board) {
+            return Some(GameResult::Checkmate(board.get_to_move().opposite()));
+        }
+
+        if self.is_stalemate(board) {
+            return Some(GameResult::Stalemate);
+        }
+
+        None
+    }
+}
+
+/// Atomic chess, where captures explode and moves are legal by
+/// `AtomicValidator`.
+pub struct AtomicRules;
+
+impl Ruleset for AtomicRules {
+    fn legal_moves(&self, board: &Board) -> Vec<Move> {
+        AtomicValidator::generate_legal_moves(board)
+    }
+
+    fn is_in_check(&self, board: &Board, color: Color) -> bool {
+        AtomicValidator::is_in_check(board, color)
+    }
+
+    fn make_move(&self, board: &mut Board, chess_move: &Move) -> Result<MoveUndo, String> {
+        board.make_atomic_move(chess_move)
+    }
+
+    fn has_legal_en_passant(&self, board: &Board) -> bool {
+        self.legal_moves(board).iter().any(|m| m.is_en_passant)
+    }
+
+    fn game_result(&self, game: &GameState) -> Option<GameResult> {
+        let board = &game.board;
+
+        if let Some(loser) = AtomicValidator::exploded_king(board) {
+            return Some(GameResult::VariantWin(
+                loser.opposite(),
+                VariantWinReason::KingExploded,
+            ));
+        }
+
+        if self.is_checkmate(board) {
+            return Some(GameResult::Checkmate(board.get_to_move().opposite()));
+        }
+
+        if self.is_stalemate(board) {
+            return Some(GameResult::Stalemate);
+        }
+
+        if MoveValidator::is_draw_by_fifty_move_rule(board) {
+            return Some(GameResult::Draw(DrawReason::FiftyMoveRule));
+        }
+
+        if game.is_threefold_repetition() {
+            return Some(GameResult::Draw(DrawReason::ThreefoldRepetition));
+        }
+
+        // Kings cannot capture, so two bare kings can never end the game
+        if board.occupied() == board.pieces_of_type(PieceType::King) {
+            return Some(GameResult::Draw(DrawReason::InsufficientMaterial));
+        }
+
+        None
+    }
+}
+
+/// Antichess. Captures are compulsory, kings are ordinary pieces, and
+/// running out of pieces or moves wins. Any material can still be given
+/// away, so it is never insufficient.
+pub struct AntichessRules;
+
+impl Ruleset for AntichessRules {
+    /// There is no check, so every move the pieces can make is playable,
+    /// except castling, and pawns may also promote to a king. Whenever a
+    /// capture is available, one must be made.
+    fn legal_moves(&self, board: &Board) -> Vec<Move> {
+        let theirs = board.color_pieces(board.get_to_move().opposite());
+        let is_capture = |m: &Move| m.is_en_passant || theirs.contains(m.to.index());
+
+        let mut moves = pseudo_legal_moves(board);
+        moves.retain(|m| !m.is_castle);
+        let king_promotions: Vec<Move> = moves
+            .iter()
+            .filter(|m| m.promotion == Some(PieceType::Queen))
+            .map(|m| Move::with_promotion(m.from, m.to, PieceType::King))
+            .collect();
+        moves.extend(king_promotions);
+
+        if moves.iter().any(is_capture) {
+            moves.retain(is_capture);
+        }
+        moves
+    }
+
+    fn has_legal_en_passant(&self, board: &Board) -> bool {
+        self.legal_moves(board).iter().any(|m| m.is_en_passant)
+    }
+
+    fn is_in_check(&self, _board: &Board, _color: Color) -> bool {
+        false
+    }
+
+    /// Kings are ordinary pieces, so there may be any number of them.
+    fn validate(&self, board: &Board) -> Result<(), FenError> {
+        validate_back_rank_pawns(board, Bitboard::EMPTY)
+    }
+
+    fn game_result(&self, game: &GameState) -> Option<GameResult> {
+        let board = &game.board;
+        let to_move = board.get_to_move();
+
+        if board.color_pieces(to_move).is_empty() {
+            return Some(GameResult::VariantWin(
+                to_move,
+                VariantWinReason::AllPiecesLost,
+            ));
+        }
+
+        if self.legal_moves(board).is_empty() {
+            return Some(GameResult::VariantWin(
+                to_move,
+                VariantWinReason::Stalemated,
+            ));
+        }
+
+        if MoveValidator::is_draw_by_fifty_move_rule(board) {
+            return Some(GameResult::Draw(DrawReason::FiftyMoveRule));
+        }
+
+        if game.is_threefold_repetition() {
+            return Some(GameResult::Draw(DrawReason::ThreefoldRepetition));
+        }
+
+        None
+    }
+}
+
+/// FIDE rules where a third check also wins. The board counts the checks.
+/// A lone minor piece can still give them, so only bare kings are
+/// insufficient material.
+pub struct ThreeCheckRules;
+
+impl Ruleset for ThreeCheckRules {
+    fn legal_moves(&self, board: &Board) -> Vec<Move> {
+        MoveValidator::generate_legal_moves(board)
+    }
+
+    fn is_legal_move(&self, board: &Board, chess_move: &Move) -> bool {
+        MoveValidator::is_valid_move(board, chess_move)
+    }
+
+    fn is_in_check(&self, board: &Board, color: Color) -> bool {
+        MoveValidator::is_in_check(board, color)
+    }
+
+    fn game_result(&self, game: &GameState) -> Option<GameResult> {
+        let board = &game.board;
+        let mover = board.get_to_move().opposite();
+
+        if board
+            .check_counts()
+            .is_some_and(|checks| checks.given(mover) >= 3)
+        {
+            return Some(GameResult::VariantWin(mover, VariantWinReason::ThreeChecks));
+        }
+
+        if self.is_checkmate(board) {
+            return Some(GameResult::Checkmate(mover));
+        }
+
+        if self.is_stalemate(board) {
+            return Some(GameResult::Stalemate);
+        }
+
+        if MoveValidator::is_draw_by_fifty_move_rule(board) {
+            return Some(GameResult::Draw(DrawReason::FiftyMoveRule));
+        }
+
+        if game.is_threefold_repetition() {
+            return Some(GameResult::Draw(DrawReason::ThreefoldRepetition));
+        }
+
+        if board.occupied() == board.pieces_of_type(PieceType::King) {
+            return Some(GameResult::Draw(DrawReason::InsufficientMaterial));
+        }
+
+        None
+    }
+}
+
+/// FIDE rules where bringing the king to d4, e4, d5 or e5 also wins. Kings
+/// alone can still race there, so material is never insufficient.
+pub struct KingOfTheHillRules;
+
+impl KingOfTheHillRules {
+    const HILL: Bitboard = Bitboard(1 << 27 | 1 << 28 | 1 << 35 | 1 << 36);
+}
+
+impl Ruleset for KingOfTheHillRules {
+    fn legal_moves(&self, board: &Board) -> Vec<Move> {
+        MoveValidator::generate_legal_moves(board)
+    }
+
+    fn is_legal_move(&self, board: &Board, chess_move: &Move) -> bool {
+        MoveValidator::is_valid_move(board, chess_move)
+    }
+
+    fn is_in_check(&self, board: &Board, color: Color) -> bool {
+        MoveValidator::is_in_check(board, color)
+    }
+
+    fn game_result(&self, game: &GameState) -> Option<GameResult> {
+        let board = &game.board;
+        let mover = board.get_to_move().opposite();
+
+        if !(board.pieces(PieceType::King, mover) & Self::HILL).is_empty() {
+            return Some(GameResult::VariantWin(
+                mover,
+                VariantWinReason::KingOfTheHill,
+            ));
+        }
+
+        if self.is_checkmate(board) {
+            return Some(GameResult::Checkmate(mover));
+        }
+
+        if self.is_stalemate(board) {
+            return Some(GameResult::Stalemate);
+        }
+
+        if MoveValidator::is_draw_by_fifty_move_rule(board) {
+            return Some(GameResult::Draw(DrawReason::FiftyMoveRule));
+        }
+
+        if game.is_threefold_repetition() {
+            return Some(GameResult::Draw(DrawReason::ThreefoldRepetition));
+        }
+
+        None
+    }
+}
+
+/// Horde. White's pawns may double-step from the first rank, and White
+/// wins by checkmate, Black by taking every white piece. White has no king,
+/// so material counts do not apply.
+pub struct HordeRules;
+
+impl Ruleset for HordeRules {
+    fn legal_moves(&self, board: &Board) -> Vec<Move> {
+        let mut moves = MoveValidator::generate_legal_moves(board);
+        if board.get_to_move() != Color::White {
+            return moves;
+        }
+
+        let occupied = board.occupied();
+        let first_rank_pawns = board.pieces(PieceType::Pawn, Color::White) & Bitboard::rank(0);
+        for from in first_rank_pawns.squares() {
+            if occupied.contains(from + 8) || occupied.contains(from + 16) {
+                continue;
+            }
+
+            let double_step = Move::new(
+                Position::from_index(from).unwrap(),
+                Position::from_index(from + 16).unwrap(),
+            );
+            let mut after = board.clone();
+            if after.make_move(&double_step).is_ok()
+                && !MoveValidator::is_in_check(&after, Color::White)
+            {
+                moves.push(double_step);
+            }
+        }
+        moves
+    }
+
+    fn is_in_check(&self, board: &Board, color: Color) -> bool {
+        MoveValidator::is_in_check(board, color)
+    }
+
+    /// White has no king, and pawns on its first rank.
+    fn validate(&self, board: &Board) -> Result<(), FenError> {
+        validate_kings(board, Color::White, 0..=1)?;
+        validate_kings(board, Color::Black, 1..=1)?;
+        let first_rank_pawns = Bitboard::rank(0) & board.pieces(PieceType::Pawn, Color::White);
+        validate_back_rank_pawns(board, first_rank_pawns)?;
+        validate_opponent_not_in_check(self, board)
+    }
+
+    fn game_result(&self, game: &GameState) -> Option<GameResult> {
+        let board = &game.board;
+
+        if board.color_pieces(Color::White).is_empty() {
+            return Some(GameResult::VariantWin(
+                Color::Black,
+                VariantWinReason::HordeDestroyed,
+            ));
+        }
+
+        if self.is_checkmate(board) {
+            return Some(GameResult::Checkmate(board.get_to_move().opposite()));
+        }
+
+        if self.is_stalemate(board) {
+            return Some(GameResult::Stalemate);
+        }
+
+        if MoveValidator::is_draw_by_fifty_move_rule(board) {
+            return Some(GameResult::Draw(DrawReason::FiftyMoveRule));
+        }
+
+        if game.is_threefold_repetition() {
+            return Some(GameResult::Draw(DrawReason::ThreefoldRepetition));
+        }
+
+        None
+    }
+}
+
+/// Racing Kings. No move may give check, and the first king on the eighth
+/// rank wins. When White gets there first, Black may still draw by
+/// following straight away.
+pub struct RacingKingsRules;
+
+impl RacingKingsRules {
+    fn gives_check(board: &Board, chess_move: &Move) -> bool {
+        let mut after = board.clone();
+        after.make_move(chess_move).is_ok()
+            && MoveValidator::is_in_check(&after, after.get_to_move())
+    }
+
+    fn reached_goal(board: &Board, color: Color) -> bool {
+        !(board.pieces(PieceType::King, color) & Bitboard::rank(7)).is_empty()
+    }
+
+    /// Whether Black's king can step onto the eighth rank this move.
+    fn can_equalize(&self, board: &Board) -> bool {
+        board.get_to_move() == Color::Black
+            && self
+                .legal_moves(board)
+                .iter()
+                .any(|m| board.find_king(Color::Black) == Some(m.from) && m.to.rank == 7)
+    }
+}
+
+impl Ruleset for RacingKingsRules {
+    fn legal_moves(&self, board: &Board) -> Vec<Move> {
+        let mut moves = MoveValidator::generate_legal_moves(board);
+        moves.retain(|m| !Self::gives_check(board, m));
+        moves
+    }
+
+    fn is_in_check(&self, _board: &Board, _color: Color) -> bool {
+        false
+    }
+
+    /// No king may stand in check, whoever is to move.
+    fn validate(&self, board: &Board) -> Result<(), FenError> {
+        validate_kings(board, Color::White, 1..=1)?;
+        validate_kings(board, Color::Black, 1..=1)?;
+        validate_back_rank_pawns(board, Bitboard::EMPTY)?;
+        validate_opponent_not_in_check(&StandardRules, board)?;
+        match MoveValidator::is_in_check(board, board.get_to_move()) {
+            true => Err(FenError::RacingKingsCheck),
+            false => Ok(()),
+        }
+    }
+
+    fn game_result(&self, game: &GameState) -> Option<GameResult> {
+        let board = &game.board;
+
+        match (
+            Self::reached_goal(board, Color::White),
+            Self::reached_goal(board, Color::Black),
+        ) {
+            (true, true) => return Some(GameResult::Draw(DrawReason::KingsRaceTied)),
+            (false, true) => {
+                return Some(GameResult::VariantWin(
+                    Color::Black,
+                    VariantWinReason::KingReachedGoal,
+                ));
+            }
+            (true, false) if !self.can_equalize(board) => {
+                return Some(GameResult::VariantWin(
+                    Color::White,
+                    VariantWinReason::KingReachedGoal,
+                ));
+            }
+            _ => {}
+        }
+
+        if self.is_stalemate(board) {
+            return Some(GameResult::Stalemate);
+        }
+
+        if MoveValidator::is_draw_by_fifty_move_rule(board) {
+            return Some(GameResult::Draw(DrawReason::FiftyMoveRule));
+        }
+
+        if game.is_threefold_repetition() {
+            return Some(GameResult::Draw(DrawReason::ThreefoldRepetition));
+        }
+
+        None
+    }
+}
+
+/// Fog-of-war chess, where each player only sees what their pieces reach.
+/// There is no check: kings may walk into attack, and capturing one wins.
+/// What each side sees is worked out in `fog`. Blunders stay possible to the end,
+/// so material is never insufficient.
+pub struct FogOfWarRules;
+
+impl Ruleset for FogOfWarRules {
+    fn legal_moves(&self, board: &Board) -> Vec<Move> {
+        pseudo_legal_moves(board)
+    }
+
+    fn has_legal_en_passant(&self, board: &Board) -> bool {
+        self.legal_moves(board).iter().any(|m| m.is_en_passant)
+    }
+
+    fn is_in_check(&self, _board: &Board, _color: Color) -> bool {
+        false
+    }
+
+    /// A king may stand attacked, or already be captured.
+    fn validate(&self, board: &Board) -> Result<(), FenError> {
+        validate_kings(board, Color::White, 0..=1)?;
+        validate_kings(board, Color::Black, 0..=1)?;
+        validate_back_rank_pawns(board, Bitboard::EMPTY)
+    }
+
+    fn game_result(&self, game: &GameState) -> Option<GameResult> {
+        let board = &game.board;
+        let to_move = board.get_to_move();
+
+        if board.pieces(PieceType::King, to_move).is_empty() {
+            return Some(GameResult::VariantWin(
+                to_move.opposite(),
+                VariantWinReason::KingCaptured,
+            ));
+        }
+
+        if self.is_stalemate(board) {
+            return Some(GameResult::Stalemate);
+        }
+
+        if MoveValidator::is_draw_by_fifty_move_rule(board) {
+            return Some(GameResult::Draw(DrawReason::FiftyMoveRule));
+        }
+
+        if game.is_threefold_repetition() {
+            return Some(GameResult::Draw(DrawReason::ThreefoldRepetition));
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::Variant;
+
+    #[test]
+    fn test_standard_game_result() {
+        let rules = StandardRules;
+
+        let ongoing = GameState::new();
+        assert_eq!(rules.game_result(&ongoing), None);
+
+        let mated = GameState::from_fen("7k/6Q1/6K1/8/8/8/8/8 b - - 0 1").unwrap();
+        assert_eq!(
+            rules.game_result(&mated),
+            Some(GameResult::Checkmate(Color::White))
+        );
+        assert_eq!(rules.pgn_result(&mated.result), "1-0");
+
+        let bare_kings = GameState::from_fen("8/8/4k3/8/8/4K3/8/8 w - - 0 1").unwrap();
+        assert_eq!(
+            rules.game_result(&bare_kings),
+            Some(GameResult::Draw(DrawReason::InsufficientMaterial))
+        );
+    }
+
+    #[test]
+    fn test_setup_validation() {
+        let horde = "4k3/8/8/8/8/8/8/PPPPPPPP w - - 0 1";
+        assert!(Variant::Horde.board_from_fen(horde).is_ok());
+        assert_eq!(
+            Board::from_fen(horde).unwrap_err(),
+            FenError::MissingKing(Color::White)
+        );
+
+        let kings = "k7/8/8/8/8/8/8/KK6 w - - 0 1";
+        assert!(Variant::Antichess.board_from_fen(kings).is_ok());
+        assert_eq!(
+            Variant::FogOfWar.board_from_fen(kings).unwrap_err(),
+            FenError::TooManyKings(Color::White)
+        );
+
+        let checked = "8/8/8/8/8/8/K7/r6k w - - 0 1";
+        assert!(Variant::Standard.board_from_fen(checked).is_ok());
+        assert_eq!(
+            Variant::RacingKings.board_from_fen(checked).unwrap_err(),
+            FenError::RacingKingsCheck
+        );
+
+        // Adjacent kings never check each other in Atomic
+        let touching = "8/8/8/8/8/8/3k4/r2K4 b - - 0 1";
+        assert_eq!(
+            Board::from_fen(touching).unwrap_err(),
+            FenError::OpponentInCheck
+        );
+        assert!(Variant::Atomic.board_from_fen(touching).is_ok());
+    }
+
+    #[test]
+    fn test_antichess_moves() {
+        let moves = |fen: &str| -> Vec<String> {
+            AntichessRules
+                .legal_moves(&Variant::Antichess.board_from_fen(fen).unwrap())
+                .iter()
+                .map(Move::to_algebraic)
+                .collect()
+        };
+
+        // Taking is compulsory, and kings take and are taken like any piece
+        assert_eq!(
+            moves("rnbqkbnr/ppp1pppp/8/3p4/4P3/8/PPPP1PPP/RNBQKBNR w - - 0 2"),
+            ["e4d5"]
+        );
+        assert_eq!(moves("8/8/8/8/8/8/6k1/7K w - - 0 1"), ["h1g2"]);
+
+        // Pawns promote to kings too, and every king moves
+        let promotions = moves("8/4P3/8/8/8/8/8/7K w - - 0 1");
+        assert!(promotions.contains(&"e7e8k".to_string()));
+        assert_eq!(promotions.len(), 8);
+        assert_eq!(moves("K7/8/8/8/8/8/8/K7 w - - 0 1").len(), 6);
+    }
+
+    #[test]
+    fn test_antichess_game_result() {
+        let game = |fen: &str| {
+            let board = Variant::Antichess.board_from_fen(fen).unwrap();
+            GameState::from_board(board, Variant::Antichess)
+        };
+
+        let given_away = game("8/8/8/8/8/8/8/7k w - - 0 1");
+        assert_eq!(
+            given_away.result,
+            GameResult::VariantWin(Color::White, VariantWinReason::AllPiecesLost)
+        );
+        assert_eq!(given_away.to_pgn().lines().nth(6), Some("[Result \"1-0\"]"));
+
+        let stalemated = game("8/8/8/8/8/p7/P7/8 w - - 0 1");
+        assert_eq!(
+            stalemated.result,
+            GameResult::VariantWin(Color::White, VariantWinReason::Stalemated)
+        );
+        assert!(!AntichessRules.is_in_check(&stalemated.board, Color::White));
+    }
+
+    #[test]
+    fn test_three_check_and_king_of_the_hill() {
+        let mut three_check = GameState::from_fen("4k3/8/8/8/8/8/8/R3K3 w - - 0 1 +2+0").unwrap();
+        assert_eq!(three_check.variant, Variant::ThreeCheck);
+        three_check
+            .apply_move(Move::from_algebraic("a1a8").unwrap())
+            .unwrap();
+        assert_eq!(
+            three_check.result,
+            GameResult::VariantWin(Color::White, VariantWinReason::ThreeChecks)
+        );
+        assert_eq!(three_check.get_game_info().check_counts.unwrap().white, 3);
+
+        let board = Board::from_fen("4k3/8/8/8/8/3K4/8/8 w - - 0 1").unwrap();
+        let mut hill = GameState::from_board(board, Variant::KingOfTheHill);
+        assert_eq!(hill.result, GameResult::Ongoing);
+        hill.apply_move(Move::from_algebraic("d3e4").unwrap())
+            .unwrap();
+        assert_eq!(
+            hill.result,
+            GameResult::VariantWin(Color::White, VariantWinReason::KingOfTheHill)
+        );
+        assert_eq!(hill.ruleset().pgn_result(&hill.result), "1-0");
+    }
+
+    #[test]
+    fn test_horde_game_result() {
+        let game = |fen: &str| {
+            let board = Variant::Horde.board_from_fen(fen).unwrap();
+            GameState::from_board(board, Variant::Horde)
+        };
+
+        let horde = GameState::for_variant(Variant::Horde, None).unwrap();
+        assert_eq!(horde.result, GameResult::Ongoing);
+        assert_eq!(
+            horde.board.pieces(PieceType::Pawn, Color::White).count(),
+            36
+        );
+
+        let destroyed = game("4k3/8/8/8/8/8/8/8 w - - 0 1");
+        assert_eq!(
+            destroyed.result,
+            GameResult::VariantWin(Color::Black, VariantWinReason::HordeDestroyed)
+        );
+
+        let mated = game("k7/PP6/1PP5/8/8/8/8/8 b - - 0 1");
+        assert_eq!(mated.result, GameResult::Checkmate(Color::White));
+
+        let stalemated = game("k7/8/8/8/8/7p/7P/8 w - - 0 1");
+        assert_eq!(stalemated.result, GameResult::Stalemate);
+    }
+
+    #[test]
+    fn test_racing_kings_game_result() {
+        let game = |fen: &str| {
+            let board = Variant::RacingKings.board_from_fen(fen).unwrap();
+            GameState::from_board(board, Variant::RacingKings)
+        };
+
+        // Black's king can still follow White's onto the eighth rank
+        let mut race = game("4K3/1k6/8/8/8/8/8/8 b - - 0 1");
+        assert_eq!(race.result, GameResult::Ongoing);
+        race.apply_move(Move::from_algebraic("b7b6").unwrap())
+            .unwrap();
+        assert_eq!(
+            race.result,
+            GameResult::VariantWin(Color::White, VariantWinReason::KingReachedGoal)
+        );
+
+        let mut tied = game("4K3/1k6/8/8/8/8/8/8 b - - 0 1");
+        tied.apply_move(Move::from_algebraic("b7b8").unwrap())
+            .unwrap();
+        assert_eq!(tied.result, GameResult::Draw(DrawReason::KingsRaceTied));
+
+        let too_far = game("4K3/8/1k6/8/8/8/8/8 b - - 0 1");
+        assert_eq!(
+            too_far.result,
+            GameResult::VariantWin(Color::White, VariantWinReason::KingReachedGoal)
+        );
+    }
+}
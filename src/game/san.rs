@@ -0,0 +1,446 @@
+use thiserror::Error;
+
+use super::{Board, Move, PieceType, Position, Ruleset, StandardRules};
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum SanError {
+    #[error("empty move")]
+    Empty,
+
+    #[error("malformed SAN: {0}")]
+    Malformed(String),
+
+    #[error("no legal move matches {0}")]
+    IllegalMove(String),
+
+    #[error("ambiguous move {0}")]
+    AmbiguousMove(String),
+}
+
+impl Move {
+    /// Encodes the move in Standard Algebraic Notation for the given position.
+    ///
+    /// The move is expected to be legal on `board`; check and mate suffixes are
+    /// derived by playing it on a copy of the board.
+    pub fn to_san(&self, board: &Board) -> String {
+        self.to_san_with(board, &StandardRules)
+    }
+
+    /// `to_san` in a game played under `rules`, which decide the rival moves
+    /// to tell apart and what counts as check.
+    pub fn to_san_with(&self, board: &Board, rules: &dyn Ruleset) -> String {
+        let mut san = if let Some(piece_type) = self.drop {
+            format!("{}@{}", piece_letter(piece_type), self.to.to_algebraic())
+        } else if self.is_castle {
+            match self.to.file > self.from.file {
+                true => "O-O".to_string(),
+                false => "O-O-O".to_string(),
+            }
+        } else {
+            self.san_body(board, rules)
+        };
+
+        let mut after = board.clone();
+        if rules.make_move(&mut after, self).is_ok() {
+            if rules.is_checkmate(&after) {
+                san.push('#');
+            } else if rules.is_in_check(&after, after.get_to_move()) {
+                san.push('+');
+            }
+        }
+
+        san
+    }
+
+    fn san_body(&self, board: &Board, rules: &dyn Ruleset) -> String {
+        let mut san = String::new();
+        let piece_type = board
+            .get_piece(self.from)
+            .map(|piece| piece.piece_type)
+            .unwrap_or(PieceType::Pawn);
+        let is_capture = self.is_en_passant || !board.is_empty(self.to);
+
+        if piece_type == PieceType::Pawn {
+            if is_capture {
+                san.push(file_char(self.from.file));
+            }
+        } else {
+            san.push(piece_letter(piece_type));
+            san.push_str(&self.disambiguation(board, rules, piece_type));
+        }
+
+        if is_capture {
+            san.push('x');
+        }
+        san.push_str(&self.to.to_algebraic());
+
+        if let Some(promotion) = self.promotion {
+            san.push('=');
+            san.push(piece_letter(promotion));
+        }
+
+        san
+    }
+
+    fn disambiguation(&self, board: &Board, rules: &dyn Ruleset, piece_type: PieceType) -> String {
+        let rivals: Vec<Position> = rules
+            .legal_moves(board)
+            .into_iter()
+            .filter(|other| {
+                other.to == self.to
+                    && other.from != self.from
+                    && !other.is_castle
+                    && board.get_piece(other.from).map(|p| p.piece_type) == Some(piece_type)
+            })
+            .map(|other| other.from)
+            .collect();
+
+        if rivals.is_empty() {
+            String::new()
+        } else if rivals.iter().all(|pos| pos.file != self.from.file) {
+            file_char(self.from.file).to_string()
+        } else if rivals.iter().all(|pos| pos.rank != self.from.rank) {
+            rank_char(self.from.rank).to_string()
+        } else {
+            self.from.to_algebraic()
+        }
+    }
+
+    /// Decodes a SAN string such as `Nbd7`, `exd6 e.p.`, `O-O-O`, `e8=Q+` or
+    /// the drop `N@f3` into the matching legal move on `board`.
+    pub fn from_san(san: &str, board: &Board) -> Result<Move, SanError> {
+        Self::from_san_with(san, board, &StandardRules)
+    }
+
+    /// `from_san` in a game played under `rules`.
+    pub fn from_san_with(san: &str, board: &Board, rules: &dyn Ruleset) -> Result<Move, SanError> {
+        let cleaned = strip_san_annotations(san);
+        if cleaned.is_empty() {
+            return Err(SanError::Empty);
+        }
+
+        let legal_moves = rules.legal_moves(board);
+
+        if let Some(kingside) = parse_castle(cleaned) {
+            return legal_moves
+                .into_iter()
+                .find(|m| m.is_castle && (m.to.file > m.from.file) == kingside)
+                .ok_or_else(|| SanError::IllegalMove(san.to_string()));
+        }
+
+        if let Some((piece, square)) = cleaned.split_once('@') {
+            let mut letters = piece.chars();
+            let piece_type = match (letters.next(), letters.next()) {
+                (None, _) => Some(PieceType::Pawn),
+                (Some(letter), None) => piece_from_letter(letter),
+                _ => None,
+            };
+            let drop = piece_type
+                .zip(Position::from_algebraic(square))
+                .map(|(piece_type, to)| Move::drop(piece_type, to))
+                .ok_or_else(|| SanError::Malformed(san.to_string()))?;
+
+            return match legal_moves.contains(&drop) {
+                true => Ok(drop),
+                false => Err(SanError::IllegalMove(san.to_string())),
+            };
+        }
+
+        let parsed =
+            ParsedSan::parse(cleaned).ok_or_else(|| SanError::Malformed(san.to_string()))?;
+
+        let candidates: Vec<Move> = legal_moves
+            .into_iter()
+            .filter(|m| !m.is_castle && !m.is_drop() && parsed.matches(m, board))
+            .collect();
+
+        match candidates.len() {
+            0 => Err(SanError::IllegalMove(san.to_string())),
+            1 => Ok(candidates[0]),
+            _ => Err(SanError::AmbiguousMove(san.to_string())),
+        }
+    }
+}
+
+struct ParsedSan {
+    piece_type: PieceType,
+    from_file: Option<u8>,
+    from_rank: Option<u8>,
+    to: Position,
+    promotion: Option<PieceType>,
+}
+
+impl ParsedSan {
+    fn parse(san: &str) -> Option<Self> {
+        let mut chars: Vec<char> = san.chars().collect();
+
+        let piece_type = match chars.first() {
+            Some(&c) if c.is_ascii_uppercase() => {
+                chars.remove(0);
+                piece_from_letter(c)?
+            }
+            _ => PieceType::Pawn,
+        };
+
+        // Promotion suffix, with or without '='
+        let mut promotion = None;
+        if let Some(&last) = chars.last()
+            && last.is_ascii_uppercase()
+        {
+            promotion = Some(piece_from_letter(last)?);
+            chars.pop();
+            if chars.last() == Some(&'=') {
+                chars.pop();
+            }
+        }
+
+        if chars.len() < 2 {
+            return None;
+        }
+        let to_str: String = chars[chars.len() - 2..].iter().collect();
+        let to = Position::from_algebraic(&to_str)?;
+        chars.truncate(chars.len() - 2);
+
+        if chars.last() == Some(&'x') {
+            chars.pop();
+        }
+
+        let mut from_file = None;
+        let mut from_rank = None;
+        for c in chars {
+            match c {
+                'a'..='h' if from_file.is_none() && from_rank.is_none() => {
+                    from_file = Some(c as u8 - b'a')
+                }
+                '1'..='8' if from_rank.is_none() => from_rank = Some(c as u8 - b'1'),
+                _ => return None,
+            }
+        }
+
+        if piece_type == PieceType::Pawn && from_rank.is_some() {
+            return None;
+        }
+
+        Some(Self {
+            piece_type,
+            from_file,
+            from_rank,
+            to,
+            promotion,
+        })
+    }
+
+    fn matches(&self, chess_move: &Move, board: &Board) -> bool {
+        let Some(piece) = board.get_piece(chess_move.from) else {
+            return false;
+        };
+
+        piece.piece_type == self.piece_type
+            && chess_move.to == self.to
+            && chess_move.promotion == self.promotion
+            && self
+                .from_file
+                .is_none_or(|file| file == chess_move.from.file)
+            && self
+                .from_rank
+                .is_none_or(|rank| rank == chess_move.from.rank)
+    }
+}
+
+fn strip_san_annotations(san: &str) -> &str {
+    let mut s = san.trim();
+    for suffix in ["e.p.", "ep"] {
+        if let Some(stripped) = s.strip_suffix(suffix) {
+            s = stripped.trim_end();
+        }
+    }
+    s.trim_end_matches(['+', '#', '!', '?'])
+}
+
+fn parse_castle(san: &str) -> Option<bool> {
+    match san {
+        "O-O" | "0-0" => Some(true),
+        "O-O-O" | "0-0-0" => Some(false),
+        _ => None,
+    }
+}
+
+fn piece_letter(piece_type: PieceType) -> char {
+    match piece_type {
+        PieceType::Pawn => 'P',
+        PieceType::Rook => 'R',
+        PieceType::Knight => 'N',
+        PieceType::Bishop => 'B',
+        PieceType::Queen => 'Q',
+        PieceType::King => 'K',
+    }
+}
+
+fn piece_from_letter(c: char) -> Option<PieceType> {
+    match c {
+        'P' => Some(PieceType::Pawn),
+        'R' => Some(PieceType::Rook),
+        'N' => Some(PieceType::Knight),
+        'B' => Some(PieceType::Bishop),
+        'Q' => Some(PieceType::Queen),
+        'K' => Some(PieceType::King),
+        _ => None,
+    }
+}
+
+fn file_char(file: u8) -> char {
+    (b'a' + file) as char
+}
+
+fn rank_char(rank: u8) -> char {
+    (b'1' + rank) as char
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::MoveValidator;
+
+    fn san_after(fen: &str, uci: &str) -> String {
+        let board = Board::from_fen(fen).unwrap();
+        let chess_move = MoveValidator::generate_legal_moves(&board)
+            .into_iter()
+            .find(|m| m.to_algebraic() == uci)
+            .unwrap();
+        chess_move.to_san(&board)
+    }
+
+    #[test]
+    fn test_to_san() {
+        let start = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+        assert_eq!(san_after(start, "e2e4"), "e4");
+        assert_eq!(san_after(start, "g1f3"), "Nf3");
+
+        // File disambiguation
+        let fen = "rnbqkb1r/ppp1pppp/5n2/8/8/8/PPPPPPPP/RNBQKBNR b KQkq - 0 1";
+        assert_eq!(san_after(fen, "b8d7"), "Nbd7");
+        assert_eq!(san_after(fen, "f6d7"), "Nfd7");
+
+        // Rank disambiguation
+        let fen = "4k3/8/8/R7/8/8/8/R3K3 w - - 0 1";
+        assert_eq!(san_after(fen, "a1a3"), "R1a3");
+
+        // Full-square disambiguation
+        let fen = "4k3/8/8/8/8/Q7/8/Q1Q1K3 w - - 0 1";
+        assert_eq!(san_after(fen, "a1b2"), "Qa1b2");
+
+        // Captures, en passant, promotion with check, mate and castling
+        let fen = "4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1";
+        assert_eq!(san_after(fen, "e5d6"), "exd6");
+
+        let fen = "8/4P3/8/8/8/8/k7/4K3 w - - 0 1";
+        assert_eq!(san_after(fen, "e7e8q"), "e8=Q");
+
+        let fen = "k7/4P3/8/8/8/8/8/4K3 w - - 0 1";
+        assert_eq!(san_after(fen, "e7e8q"), "e8=Q+");
+
+        let fen = "r1bqkb1r/pppp1ppp/2n2n2/4p2Q/2B1P3/8/PPPP1PPP/RNB1K1NR w KQkq - 4 4";
+        assert_eq!(san_after(fen, "h5f7"), "Qxf7#");
+
+        let fen = "r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1";
+        assert_eq!(san_after(fen, "e1g1"), "O-O");
+        assert_eq!(san_after(fen, "e1c1"), "O-O-O");
+    }
+
+    #[test]
+    fn test_from_san() {
+        let board = Board::new();
+        let chess_move = Move::from_san("Nf3", &board).unwrap();
+        assert_eq!(chess_move.to_algebraic(), "g1f3");
+
+        let fen = "r3k2r/pppq1ppp/2np1n2/4p3/4P3/2NP1N2/PPPQ1PPP/R3K2R w KQkq - 0 1";
+        let board = Board::from_fen(fen).unwrap();
+        let castle = Move::from_san("O-O-O", &board).unwrap();
+        assert!(castle.is_castle);
+        assert_eq!(castle.to_algebraic(), "e1c1");
+
+        let fen = "4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1";
+        let board = Board::from_fen(fen).unwrap();
+        let en_passant = Move::from_san("exd6 e.p.", &board).unwrap();
+        assert!(en_passant.is_en_passant);
+
+        let fen = "rnbqkb1r/ppp1pppp/5n2/8/8/8/PPPPPPPP/RNBQKBNR b KQkq - 0 1";
+        let board = Board::from_fen(fen).unwrap();
+        assert_eq!(
+            Move::from_san("Nbd7", &board).unwrap().to_algebraic(),
+            "b8d7"
+        );
+        assert_eq!(
+            Move::from_san("N6d7", &board).unwrap().to_algebraic(),
+            "f6d7"
+        );
+
+        let fen = "k7/4P3/8/8/8/8/8/4K3 w - - 0 1";
+        let board = Board::from_fen(fen).unwrap();
+        let promotion = Move::from_san("e8=Q+", &board).unwrap();
+        assert_eq!(promotion.promotion, Some(PieceType::Queen));
+        let underpromotion = Move::from_san("e8N", &board).unwrap();
+        assert_eq!(underpromotion.promotion, Some(PieceType::Knight));
+    }
+
+    #[test]
+    fn test_from_san_errors() {
+        let fen = "4k3/8/8/8/8/Q7/8/Q1Q1K3 w - - 0 1";
+        let board = Board::from_fen(fen).unwrap();
+
+        assert_eq!(Move::from_san("", &board), Err(SanError::Empty));
+        assert_eq!(
+            Move::from_san("Qb2", &board),
+            Err(SanError::AmbiguousMove("Qb2".to_string()))
+        );
+        assert_eq!(
+            Move::from_san("Qa1b2", &board).unwrap().to_algebraic(),
+            "a1b2"
+        );
+        assert_eq!(
+            Move::from_san("Nf3", &board),
+            Err(SanError::IllegalMove("Nf3".to_string()))
+        );
+        assert!(matches!(
+            Move::from_san("Zz9", &board),
+            Err(SanError::Malformed(_))
+        ));
+    }
+
+    #[test]
+    fn test_san_round_trip() {
+        let fen = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+        let board = Board::from_fen(fen).unwrap();
+
+        for chess_move in MoveValidator::generate_legal_moves(&board) {
+            let san = chess_move.to_san(&board);
+            assert_eq!(Move::from_san(&san, &board).unwrap(), chess_move, "{}", san);
+        }
+    }
+
+    #[test]
+    fn test_drop_san() {
+        let fen = "4k3/8/8/8/8/8/8/4K3[NP] w - - 0 1";
+        let board = Board::from_fen(fen).unwrap();
+
+        let knight = Move::from_san("N@f3", &board).unwrap();
+        assert_eq!(
+            knight,
+            Move::drop(PieceType::Knight, Position::from_algebraic("f3").unwrap())
+        );
+        assert_eq!(knight.to_san(&board), "N@f3");
+
+        let pawn = Move::from_san("@d7", &board).unwrap();
+        assert_eq!(pawn.drop, Some(PieceType::Pawn));
+        assert_eq!(pawn.to_san(&board), "P@d7+");
+
+        assert_eq!(
+            Move::from_san("P@d8", &board),
+            Err(SanError::IllegalMove("P@d8".to_string()))
+        );
+        assert_eq!(
+            Move::from_san("Q@d4", &board),
+            Err(SanError::IllegalMove("Q@d4".to_string()))
+        );
+    }
+}
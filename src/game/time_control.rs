@@ -0,0 +1,206 @@
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use super::{Clock, ClockMode};
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum TimeControlError {
+    #[error("invalid time control field: {0}")]
+    InvalidField(String),
+
+    #[error("unsupported time control field: {0}")]
+    UnsupportedField(String),
+
+    #[error("only the last stage may run to the end of the game")]
+    OpenStageNotLast,
+}
+
+/// One stage of a time control: `time_secs` for the next `moves` moves, or
+/// for the rest of the game when `moves` is `None`. Every move made in the
+/// stage gets `increment_secs`, as an increment or a delay.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TimeControlStage {
+    pub moves: Option<u32>,
+    pub time_secs: u32,
+    #[serde(default)]
+    pub increment_secs: u32,
+}
+
+impl TimeControlStage {
+    pub fn new(moves: Option<u32>, time_secs: u32, increment_secs: u32) -> Self {
+        Self {
+            moves,
+            time_secs,
+            increment_secs,
+        }
+    }
+
+    /// Reads one field of a PGN `[TimeControl]` tag, like `40/5400+30`.
+    fn from_pgn(field: &str) -> Result<Self, TimeControlError> {
+        let invalid = || TimeControlError::InvalidField(field.to_string());
+        if field == "?" || field == "-" || field.starts_with('*') {
+            return Err(TimeControlError::UnsupportedField(field.to_string()));
+        }
+
+        let (moves, clock) = match field.split_once('/') {
+            Some((moves, clock)) => {
+                let moves = moves.parse::<u32>().map_err(|_| invalid())?;
+                if moves == 0 {
+                    return Err(invalid());
+                }
+                (Some(moves), clock)
+            }
+            None => (None, field),
+        };
+        let (time, increment) = clock.split_once('+').unwrap_or((clock, "0"));
+
+        Ok(Self {
+            moves,
+            time_secs: time.parse().map_err(|_| invalid())?,
+            increment_secs: increment.parse().map_err(|_| invalid())?,
+        })
+    }
+
+    fn to_pgn(self, with_increment: bool) -> String {
+        let mut field = match self.moves {
+            Some(moves) => format!("{}/{}", moves, self.time_secs),
+            None => self.time_secs.to_string(),
+        };
+        if with_increment && self.increment_secs > 0 {
+            field.push_str(&format!("+{}", self.increment_secs));
+        }
+        field
+    }
+}
+
+/// How much time each player gets. Simple controls are one stage of
+/// `initial_time_secs` and `increment_secs`; tournament controls list their
+/// stages, e.g. 40 moves in 90 minutes, then 30 minutes for the rest.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TimeControl {
+    pub initial_time_secs: u32,
+    /// The Fischer increment, or the delay in the delay modes.
+    pub increment_secs: u32,
+    pub name: String,
+    #[serde(default)]
+    pub mode: ClockMode,
+    /// Every stage, the first included, for controls with more than one.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub stages: Vec<TimeControlStage>,
+}
+
+impl TimeControl {
+    /// A single-stage Fischer control, named in PGN syntax.
+    pub fn new(initial_time_secs: u32, increment_secs: u32) -> Self {
+        let stage = TimeControlStage::new(None, initial_time_secs, increment_secs);
+        Self {
+            initial_time_secs,
+            increment_secs,
+            name: stage.to_pgn(true),
+            mode: ClockMode::Increment,
+            stages: Vec::new(),
+        }
+    }
+
+    /// Reads a PGN `[TimeControl]` tag value, like `40/5400+30:1800+30`.
+    /// A last stage with a move count repeats for the rest of the game.
+    pub fn from_pgn(name: &str, pgn: &str) -> Result<Self, TimeControlError> {
+        let stages = pgn
+            .split(':')
+            .map(TimeControlStage::from_pgn)
+            .collect::<Result<Vec<_>, _>>()?;
+        let first = stages[0];
+        if stages[..stages.len() - 1]
+            .iter()
+            .any(|stage| stage.moves.is_none())
+        {
+            return Err(TimeControlError::OpenStageNotLast);
+        }
+
+        Ok(Self {
+            initial_time_secs: first.time_secs,
+            increment_secs: first.increment_secs,
+            name: name.to_string(),
+            mode: ClockMode::Increment,
+            stages: match stages.len() {
+                1 if first.moves.is_none() => Vec::new(),
+                _ => stages,
+            },
+        })
+    }
+
+    /// The stages to play, in order.
+    pub fn stages(&self) -> Vec<TimeControlStage> {
+        match self.stages.is_empty() {
+            true => vec![TimeControlStage::new(
+                None,
+                self.initial_time_secs,
+                self.increment_secs,
+            )],
+            false => self.stages.clone(),
+        }
+    }
+
+    /// The PGN `[TimeControl]` tag value. The tag has no syntax for delays,
+    /// so those leave the per-move time out.
+    pub fn to_pgn(&self) -> String {
+        let with_increment = self.mode == ClockMode::Increment;
+        self.stages()
+            .into_iter()
+            .map(|stage| stage.to_pgn(with_increment))
+            .collect::<Vec<_>>()
+            .join(":")
+    }
+
+    /// A fresh clock for this time control.
+    pub fn clock(&self) -> Clock {
+        Clock::new(self.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pgn_round_trip() {
+        let classical = TimeControl::from_pgn("Classical", "40/5400+30:1800+30").unwrap();
+        assert_eq!(
+            classical.stages(),
+            [
+                TimeControlStage::new(Some(40), 5400, 30),
+                TimeControlStage::new(None, 1800, 30),
+            ]
+        );
+        assert_eq!(classical.initial_time_secs, 5400);
+        assert_eq!(classical.to_pgn(), "40/5400+30:1800+30");
+
+        let blitz = TimeControl::from_pgn("Blitz", "300+2").unwrap();
+        assert_eq!(blitz.stages, []);
+        assert_eq!(blitz.to_pgn(), "300+2");
+        assert_eq!(blitz.to_pgn(), TimeControl::new(300, 2).name);
+
+        let delayed = TimeControl {
+            mode: ClockMode::SimpleDelay,
+            ..TimeControl::new(5400, 5)
+        };
+        assert_eq!(delayed.to_pgn(), "5400");
+    }
+
+    #[test]
+    fn test_invalid_pgn() {
+        assert_eq!(
+            TimeControl::from_pgn("", "?"),
+            Err(TimeControlError::UnsupportedField("?".to_string()))
+        );
+        assert_eq!(
+            TimeControl::from_pgn("", "0/300"),
+            Err(TimeControlError::InvalidField("0/300".to_string()))
+        );
+        assert_eq!(
+            TimeControl::from_pgn("", "300:40/1800"),
+            Err(TimeControlError::OpenStageNotLast)
+        );
+        assert!(TimeControl::from_pgn("", "40/90m").is_err());
+    }
+}
@@ -0,0 +1,137 @@
+use serde::{Deserialize, Serialize};
+
+use super::{
+    AntichessRules, AtomicRules, Board, BughouseRules, CheckCounts, CrazyhouseRules, FenError,
+    FogOfWarRules, HordeRules, KingOfTheHillRules, Pockets, RacingKingsRules, Ruleset,
+    StandardRules, ThreeCheckRules,
+};
+
+/// Antichess starts from the standard setup without castling.
+const ANTICHESS_START: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w - - 0 1";
+
+/// Black's standard army against 36 white pawns.
+const HORDE_START: &str =
+    "rnbqkbnr/pppppppp/8/1PP2PP1/PPPPPPPP/PPPPPPPP/PPPPPPPP/PPPPPPPP w kq - 0 1";
+
+/// Both armies side by side on the first two ranks, kings on the inside.
+const RACING_KINGS_START: &str = "8/8/8/8/8/8/krbnNBRK/qrbnNBRQ w - - 0 1";
+
+/// The rule set a game is played under.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum Variant {
+    #[default]
+    Standard,
+    Chess960,
+    Crazyhouse,
+    /// One board of a four-player Bughouse match, see `BughouseManager`.
+    Bughouse,
+    Atomic,
+    Antichess,
+    ThreeCheck,
+    KingOfTheHill,
+    Horde,
+    RacingKings,
+    /// Dark chess: each player sees only the squares their pieces reach.
+    FogOfWar,
+}
+
+impl Variant {
+    pub fn ruleset(self) -> &'static dyn Ruleset {
+        match self {
+            Variant::Standard | Variant::Chess960 => &StandardRules,
+            Variant::Crazyhouse => &CrazyhouseRules,
+            Variant::Bughouse => &BughouseRules,
+            Variant::Atomic => &AtomicRules,
+            Variant::Antichess => &AntichessRules,
+            Variant::ThreeCheck => &ThreeCheckRules,
+            Variant::KingOfTheHill => &KingOfTheHillRules,
+            Variant::Horde => &HordeRules,
+            Variant::RacingKings => &RacingKingsRules,
+            Variant::FogOfWar => &FogOfWarRules,
+        }
+    }
+
+    /// The board every game of this variant starts from, or `None` when the
+    /// setup varies from game to game.
+    pub fn starting_board(self) -> Option<Board> {
+        match self {
+            Variant::Standard | Variant::KingOfTheHill | Variant::Atomic | Variant::FogOfWar => {
+                Some(Board::new())
+            }
+            Variant::Chess960 => None,
+            Variant::Antichess => self.board_from_fen(ANTICHESS_START).ok(),
+            Variant::Horde => self.board_from_fen(HORDE_START).ok(),
+            Variant::RacingKings => self.board_from_fen(RACING_KINGS_START).ok(),
+            Variant::Crazyhouse | Variant::Bughouse | Variant::ThreeCheck => {
+                let mut board = Board::new();
+                self.configure(&mut board);
+                Some(board)
+            }
+        }
+    }
+
+    /// Gives the board the state this variant keeps beyond the pieces:
+    /// pockets or check counts.
+    pub fn configure(self, board: &mut Board) {
+        if matches!(self, Variant::Crazyhouse | Variant::Bughouse) && board.pockets().is_none() {
+            board.set_pockets(Some(Pockets::default()));
+        }
+        if self == Variant::ThreeCheck && board.check_counts().is_none() {
+            board.set_check_counts(Some(CheckCounts::default()));
+        }
+    }
+
+    /// Reads a FEN position of this variant, validated by its rules.
+    pub fn board_from_fen(self, fen: &str) -> Result<Board, FenError> {
+        Board::from_fen_with(fen, self.ruleset(), |board| self.configure(board))
+    }
+
+    /// The variant a position read from FEN implies on its own.
+    pub fn detect(board: &Board) -> Self {
+        if board.pockets().is_some() {
+            Variant::Crazyhouse
+        } else if board.check_counts().is_some() {
+            Variant::ThreeCheck
+        } else if board.is_chess960() {
+            Variant::Chess960
+        } else {
+            Variant::Standard
+        }
+    }
+
+    /// Value of the PGN `Variant` tag.
+    pub fn pgn_name(self) -> &'static str {
+        match self {
+            Variant::Standard => "Standard",
+            Variant::Chess960 => "Chess960",
+            Variant::Crazyhouse => "Crazyhouse",
+            Variant::Bughouse => "Bughouse",
+            Variant::Atomic => "Atomic",
+            Variant::Antichess => "Antichess",
+            Variant::ThreeCheck => "Three-check",
+            Variant::KingOfTheHill => "King of the Hill",
+            Variant::Horde => "Horde",
+            Variant::RacingKings => "Racing Kings",
+            Variant::FogOfWar => "Fog of War",
+        }
+    }
+
+    /// Reads a PGN `Variant` tag, accepting the spellings other tools export.
+    /// Bughouse boards are exported but cannot be replayed on their own, as
+    /// their drops depend on the partner board.
+    pub fn from_pgn_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().replace([' ', '-'], "").as_str() {
+            "standard" | "chess" => Some(Variant::Standard),
+            "chess960" | "fischerandom" | "fischerrandom" => Some(Variant::Chess960),
+            "crazyhouse" => Some(Variant::Crazyhouse),
+            "atomic" => Some(Variant::Atomic),
+            "antichess" | "losingchess" | "giveaway" => Some(Variant::Antichess),
+            "threecheck" | "3check" => Some(Variant::ThreeCheck),
+            "kingofthehill" | "koth" => Some(Variant::KingOfTheHill),
+            "horde" => Some(Variant::Horde),
+            "racingkings" => Some(Variant::RacingKings),
+            "fogofwar" | "darkchess" => Some(Variant::FogOfWar),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,171 @@
+use std::sync::OnceLock;
+
+use super::{CastlingRights, CheckCounts, Color, PieceType, Pockets};
+
+struct ZobristKeys {
+    pieces: [[[u64; 64]; 6]; 2],
+    black_to_move: u64,
+    castling: [u64; 4],
+    en_passant_file: [u64; 8],
+    // By color, piece and count held; counts past the table share its last key
+    pocket: [[[u64; POCKET_DEPTH]; 5]; 2],
+    // By color and checks given, capped at three
+    checks: [[u64; 4]; 2],
+}
+
+const POCKET_DEPTH: usize = 17;
+
+// Keys come from a fixed seed so hashes stay stable across runs and can be
+// stored alongside opening books or persisted games.
+const SEED: u64 = 0x9e37_79b9_7f4a_7c15;
+
+fn keys() -> &'static ZobristKeys {
+    static KEYS: OnceLock<ZobristKeys> = OnceLock::new();
+    KEYS.get_or_init(|| {
+        let mut state = SEED;
+        let mut next = || {
+            // splitmix64
+            state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+            let mut z = state;
+            z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+            z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+            z ^ (z >> 31)
+        };
+
+        let mut keys = ZobristKeys {
+            pieces: [[[0; 64]; 6]; 2],
+            black_to_move: 0,
+            castling: [0; 4],
+            en_passant_file: [0; 8],
+            pocket: [[[0; POCKET_DEPTH]; 5]; 2],
+            checks: [[0; 4]; 2],
+        };
+
+        for color in &mut keys.pieces {
+            for piece in color.iter_mut() {
+                for key in piece.iter_mut() {
+                    *key = next();
+                }
+            }
+        }
+        keys.black_to_move = next();
+        for key in &mut keys.castling {
+            *key = next();
+        }
+        for key in &mut keys.en_passant_file {
+            *key = next();
+        }
+        for color in &mut keys.pocket {
+            for piece in color.iter_mut() {
+                // Nothing in hand leaves the key unchanged
+                for key in piece.iter_mut().skip(1) {
+                    *key = next();
+                }
+            }
+        }
+        for color in &mut keys.checks {
+            for key in color.iter_mut().skip(1) {
+                *key = next();
+            }
+        }
+
+        keys
+    })
+}
+
+pub fn piece_key(piece_type: PieceType, color: Color, square: usize) -> u64 {
+    keys().pieces[color.index()][piece_type.index()][square]
+}
+
+pub fn side_key(color: Color) -> u64 {
+    match color {
+        Color::White => 0,
+        Color::Black => keys().black_to_move,
+    }
+}
+
+pub fn castling_key(rights: &CastlingRights) -> u64 {
+    let flags = [
+        rights.white_kingside,
+        rights.white_queenside,
+        rights.black_kingside,
+        rights.black_queenside,
+    ];
+
+    flags
+        .iter()
+        .zip(keys().castling)
+        .filter(|(set, _)| **set)
+        .fold(0, |hash, (_, key)| hash ^ key)
+}
+
+pub fn en_passant_key(file: u8) -> u64 {
+    keys().en_passant_file[file as usize]
+}
+
+pub fn pockets_key(pockets: &Pockets) -> u64 {
+    let mut hash = 0;
+    for color in [Color::White, Color::Black] {
+        for piece_type in Pockets::PIECES {
+            let count = (pockets.count(color, piece_type) as usize).min(POCKET_DEPTH - 1);
+            hash ^= keys().pocket[color.index()][piece_type.index()][count];
+        }
+    }
+    hash
+}
+
+pub fn checks_key(checks: &CheckCounts) -> u64 {
+    [Color::White, Color::Black]
+        .into_iter()
+        .fold(0, |hash, color| {
+            let count = (checks.given(color) as usize).min(3);
+            hash ^ keys().checks[color.index()][count]
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::game::{Board, Move};
+
+    fn key(fen: &str) -> u64 {
+        Board::from_fen(fen).unwrap().zobrist_key()
+    }
+
+    #[test]
+    fn test_incremental_key_matches_fresh_board() {
+        let mut board = Board::new();
+        for san in [
+            "e4", "d5", "exd5", "Qxd5", "Nc3", "Qa5", "d4", "c6", "Bd2", "Bf5",
+        ] {
+            let chess_move = Move::from_san(san, &board).unwrap();
+            board.make_move(&chess_move).unwrap();
+            assert_eq!(board.zobrist_key(), key(&board.to_fen()), "after {}", san);
+        }
+
+        // Castling rights and side to move are part of the key
+        let fen = "r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1";
+        assert_ne!(key(fen), key("r3k2r/8/8/8/8/8/8/R3K2R w Kkq - 0 1"));
+        assert_ne!(key(fen), key("r3k2r/8/8/8/8/8/8/R3K2R b KQkq - 0 1"));
+    }
+
+    #[test]
+    fn test_en_passant_only_counts_when_capturable() {
+        // No black pawn can take on e3
+        assert_eq!(
+            key("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1"),
+            key("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1")
+        );
+
+        // The d4 pawn can
+        assert_ne!(
+            key("rnbqkbnr/ppp1pppp/8/8/3pP3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1"),
+            key("rnbqkbnr/ppp1pppp/8/8/3pP3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1")
+        );
+
+        // A capture that would expose the king is not legal either
+        assert_eq!(
+            key("8/8/8/8/k2pP2R/8/8/4K3 b - e3 0 1"),
+            key("8/8/8/8/k2pP2R/8/8/4K3 b - - 0 1")
+        );
+    }
+}
@@ -1,4 +1,5 @@
 pub mod game;
 pub mod network;
 pub mod player;
+pub mod turn_based;
 pub mod utils;
@@ -1,12 +1,17 @@
 use chess_server::{
-    game::{Board, GameManager, MoveValidator, Position, Move},
+    game::{Board, GameManager, MoveValidator},
     network::ChessServer,
-    utils::{load_config, ServerConfig},
+    utils::{ServerConfig, load_config},
 };
 use tokio::signal;
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let args: Vec<String> = std::env::args().collect();
+    if args.get(1).map(String::as_str) == Some("perft") {
+        return run_perft(&args[2..]);
+    }
+
     println!("Chess Server Starting...");
 
     let config = match load_config() {
@@ -35,7 +40,9 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let server_for_run = server_for_shutdown.clone();
 
     let shutdown_task = tokio::spawn(async move {
-        signal::ctrl_c().await.expect("Failed to install Ctrl+C handler");
+        signal::ctrl_c()
+            .await
+            .expect("Failed to install Ctrl+C handler");
         println!("\nRecived Ctrl+C, shutting down...");
         server_for_shutdown.stop().await;
     });
@@ -59,6 +66,44 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     Ok(())
 }
 
+fn run_perft(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let usage = "usage: chess-server perft <fen|startpos> <depth>";
+
+    let (depth, fen_parts) = args.split_last().ok_or(usage)?;
+    let depth: u32 = depth.parse().map_err(|_| usage)?;
+
+    // Accept the FEN either quoted or as separate arguments
+    let fen = fen_parts.join(" ");
+    let board = match fen.as_str() {
+        "" => return Err(usage.into()),
+        "startpos" => Board::new(),
+        _ => Board::from_fen(&fen)?,
+    };
+
+    let start = std::time::Instant::now();
+    let divide = MoveValidator::divide(&board, depth);
+
+    for (chess_move, nodes) in &divide {
+        println!("{}: {}", chess_move.to_algebraic(), nodes);
+    }
+
+    let total: u64 = match depth {
+        0 => 1,
+        _ => divide.iter().map(|(_, nodes)| nodes).sum(),
+    };
+    let elapsed = start.elapsed().as_secs_f64();
+
+    println!();
+    println!("Nodes searched: {}", total);
+    println!(
+        "Time: {:.3}s ({:.0} nodes/s)",
+        elapsed,
+        total as f64 / elapsed.max(1e-9)
+    );
+
+    Ok(())
+}
+
 fn test_chess_logic() {
     println!("Testing chess logic...");
 
@@ -94,13 +139,13 @@ fn test_chess_logic() {
             Err(e) => println!("Failed to make move: {}", e),
         }
     }
-    
+
     if let Some(game) = game_manager.get_game(&game_id) {
         println!("Game after first move:");
         println!("{}", game.board.display());
         println!("Turn: {:?}", game.board.get_to_move());
         println!("Move count: {}", game.get_move_count());
     }
-    
+
     println!("Chess logic test completed!");
-}
\ No newline at end of file
+}
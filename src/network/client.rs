@@ -531,6 +531,12 @@ impl ClientManager {
         cnt
     }
 
+    /// The players with a client connected.
+    pub async fn get_player_ids(&self) -> Vec<String> {
+        let player_clients_guard = self.player_clients.read().await;
+        player_clients_guard.keys().cloned().collect()
+    }
+
     pub async fn get_client_count(&self) -> usize {
         let clients_guard = self.clients.read().await;
         clients_guard.len()
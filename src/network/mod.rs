@@ -2,6 +2,6 @@ pub mod client;
 pub mod protocol;
 pub mod server;
 
-pub use protocol::*;
 pub use client::*;
-pub use server::*;
\ No newline at end of file
+pub use protocol::*;
+pub use server::*;
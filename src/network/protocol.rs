@@ -1,7 +1,14 @@
 use serde::{Deserialize, Serialize};
-
-use crate::game::{Color, GameInfo, GameResult, Move};
+use serde_json::Value;
+
+pub use crate::game::TimeControl;
+use crate::game::{
+    Board, CHESS_KIND, CheckCounts, Color, Correspondence, DrawOffer, Explosion, GameInfo,
+    GameResult, GameState, LagStats, Move, PieceType, Pockets, Position, Ruleset, Seat,
+    SeatPreference, Variant,
+};
 use crate::player::{PlayerDisplayInfo, PlayerPreferences, PlayerStats};
+use crate::turn_based::Table;
 use crate::utils::{ChessResult, ChessServerError, ErrorResponse};
 
 pub const PROTOCOL_VERSION: &str = "1.0";
@@ -32,15 +39,21 @@ pub enum MessageType {
     JoinGameResponse(JoinGameResponse),
     LeaveGame(LeaveGameRequest),
     SpectateGame(SpectateGameRequest),
+    SpectateGameResponse(SpectateGameResponse),
 
     // Game Play
     MakeMove(MakeMoveRequest),
     GameUpdate(GameUpdateNotification),
     MoveUpdate(MoveUpdateNotification),
+    /// A correspondence game awaits the recipient's move. Held until they
+    /// connect if they are away.
+    YourMove(YourMoveNotification),
 
     // Game Control
     OfferDraw(OfferDrawRequest),
     RespondToDraw(RespondToDrawRequest),
+    /// A draw offer made to the recipient, or the answer to theirs.
+    DrawOfferUpdate(DrawOfferNotification),
     Resign(ResignRequest),
     RequestUndo(RequestUndoRequest),
     RespondToUndo(RespondToUndoRequest),
@@ -59,6 +72,16 @@ pub enum MessageType {
     GetGameInfoResponse(GameInfo),
     GetLegalMoves(GetLegalMovesRequest),
     GetLegalMovesResponse(GetLegalMovesResponse),
+    GetPgn(GetPgnRequest),
+    GetPgnResponse(GetPgnResponse),
+
+    // Other turn-based games
+    /// Answers `JoinGame` for a game of another kind.
+    JoinTurnBasedGameResponse(JoinTurnBasedGameResponse),
+    /// Answers `SpectateGame` for a game of another kind.
+    SpectateTurnBasedGameResponse(TurnBasedUpdateNotification),
+    PlayTurn(PlayTurnRequest),
+    TurnBasedUpdate(TurnBasedUpdateNotification),
 
     // Chat
     SendMessage(ChatMessageRequest),
@@ -108,22 +131,78 @@ pub struct DisconnectRequest {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CreateGameRequest {
     pub time_control: Option<TimeControl>,
+    /// A time control preset from the server's configuration, used instead
+    /// of `time_control`.
+    #[serde(default)]
+    pub time_control_preset: Option<String>,
+    /// Plays by correspondence, with this many days for each move, instead
+    /// of on a clock.
+    #[serde(default)]
+    pub days_per_move: Option<u32>,
     pub color_preference: Option<Color>,
     pub is_private: bool,
     pub password: Option<String>,
+    #[serde(default)]
+    pub variant: Variant,
+    /// Chess960 start position index (0-959); random when omitted.
+    #[serde(default)]
+    pub start_position: Option<u16>,
+    /// Bughouse team (0 or 1); `color_preference` picks the color.
+    #[serde(default)]
+    pub team: Option<usize>,
+    /// Bughouse board (0 or 1).
+    #[serde(default)]
+    pub board: Option<usize>,
+    /// One of `ServerInfo::game_kinds`; chess when omitted. Other kinds
+    /// seat the creator by `color_preference`, White being seat 0.
+    #[serde(default)]
+    pub kind: Option<String>,
+}
+
+impl CreateGameRequest {
+    pub fn seat_preference(&self) -> SeatPreference {
+        SeatPreference {
+            team: self.team,
+            board: self.board,
+            color: self.color_preference,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CreateGameResponse {
     pub game_id: String,
     pub player_color: Color,
+    #[serde(default)]
+    pub bughouse: Option<BughouseSeating>,
 }
 
+/// `game_id` may also name a Bughouse match, or either of its boards.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct JoinGameRequest {
     pub game_id: String,
     pub password: Option<String>,
     pub color_preference: Option<Color>,
+    /// Bughouse team (0 or 1).
+    #[serde(default)]
+    pub team: Option<usize>,
+    /// Bughouse board (0 or 1).
+    #[serde(default)]
+    pub board: Option<usize>,
+    /// The seat to take at a game of another kind, or the one
+    /// `color_preference` picks, or else the first free one.
+    #[serde(default)]
+    pub seat: Option<usize>,
+}
+
+impl JoinGameRequest {
+    pub fn seat_preference(&self) -> SeatPreference {
+        SeatPreference {
+            team: self.team,
+            board: self.board,
+            color: self.color_preference,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -132,6 +211,29 @@ pub struct JoinGameResponse {
     pub player_color: Color,
     pub opponent_info: Option<PlayerDisplayInfo>,
     pub game_state: GameStateSnapshot,
+    #[serde(default)]
+    pub bughouse: Option<BughouseSeating>,
+}
+
+/// Where a player sits in a Bughouse match. `game_id` in the response is the
+/// board they play on.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BughouseSeating {
+    pub match_id: String,
+    pub boards: [String; 2],
+    pub seat: Seat,
+    pub team: usize,
+}
+
+impl BughouseSeating {
+    pub fn new(match_id: String, boards: [String; 2], seat: Seat) -> Self {
+        Self {
+            match_id,
+            boards,
+            seat,
+            team: seat.team(),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -145,31 +247,100 @@ pub struct SpectateGameRequest {
     pub game_id: String,
 }
 
+/// The game as spectators see it. Fog-of-war games are shown in full, but
+/// some moves behind the players.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SpectateGameResponse {
+    pub game_id: String,
+    pub game_state: GameStateSnapshot,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MakeMoveRequest {
     pub game_id: String,
-    pub chess_move: Move,
+    #[serde(default)]
+    pub chess_move: Option<Move>,
+    #[serde(default)]
+    pub san: Option<String>,
+    /// How long the move took on the client's side. What the server saw it
+    /// take beyond this is network lag, some of which is given back.
     pub move_time_ms: Option<u64>,
 }
 
+impl MakeMoveRequest {
+    /// Resolves the submitted move against the legal moves on `board` under
+    /// `rules`.
+    ///
+    /// SAN takes precedence when both forms are present. Coordinate moves are
+    /// matched on from/to/promotion so clients need not set the castling and
+    /// en passant flags themselves.
+    pub fn resolve_move(&self, board: &Board, rules: &dyn Ruleset) -> ChessResult<Move> {
+        if let Some(ref san) = self.san {
+            return Move::from_san_with(san, board, rules).map_err(|e| {
+                ChessServerError::InvalidMove {
+                    reason: e.to_string(),
+                }
+            });
+        }
+
+        let submitted = self
+            .chess_move
+            .ok_or_else(|| ChessServerError::MissingRequiredField {
+                field: "chess_move".to_string(),
+            })?;
+
+        Ok(rules
+            .legal_moves(board)
+            .into_iter()
+            .find(|m| {
+                m.from == submitted.from
+                    && m.to == submitted.to
+                    && m.promotion == submitted.promotion
+                    && m.drop == submitted.drop
+            })
+            .unwrap_or(submitted))
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GameUpdateNotification {
     pub game_id: String,
     pub game_state: GameStateSnapshot,
     pub last_move: Option<Move>,
+    #[serde(default)]
+    pub last_move_san: Option<String>,
     pub player_to_move: Color,
     pub is_check: bool,
     pub game_result: Option<GameResult>,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct YourMoveNotification {
+    pub game_id: String,
+    pub opponent: Option<PlayerDisplayInfo>,
+    pub move_count: u32,
+    /// The opponent's last move, if there has been one.
+    pub last_move: Option<Move>,
+    /// When the move is due, in unix seconds.
+    pub deadline: Option<u64>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MoveUpdateNotification {
     pub game_id: String,
     pub chess_move: Move,
+    pub san: String,
     pub player: Color,
     pub move_number: u32,
     pub time_taken_ms: Option<u64>,
     pub resulting_position: String, // FEN
+    /// What the move blew up, in Atomic games.
+    #[serde(default)]
+    pub explosion: Option<Explosion>,
+    #[serde(default)]
+    pub white_time_remaining_ms: Option<u64>,
+    #[serde(default)]
+    pub black_time_remaining_ms: Option<u64>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -184,6 +355,25 @@ pub struct RespondToDrawRequest {
     pub accept: bool,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DrawOfferStatus {
+    Offered,
+    Accepted,
+    Declined,
+    /// Withdrawn as its maker moved on without an answer.
+    Cancelled,
+    /// Gone unanswered for too long.
+    Expired,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DrawOfferNotification {
+    pub game_id: String,
+    pub offered_by: Color,
+    pub status: DrawOfferStatus,
+    pub message: Option<String>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ResignRequest {
     pub game_id: String,
@@ -211,6 +401,8 @@ pub struct GetPlayerInfoRequest {
 pub struct GetPlayerInfoResponse {
     pub player_info: PlayerDisplayInfo,
     pub detailed_stats: Option<PlayerStats>,
+    #[serde(default)]
+    pub lag_stats: Option<LagStats>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -239,7 +431,7 @@ pub struct GetGameListRequest {
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GetGameListResponse {
-    pub games: Vec<GameInfo>,
+    pub games: Vec<GameSummary>,
     pub total_count: u32,
 }
 
@@ -259,6 +451,17 @@ pub struct GetLegalMovesResponse {
     pub in_check: bool,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GetPgnRequest {
+    pub game_id: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GetPgnResponse {
+    pub game_id: String,
+    pub pgn: String,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ChatMessageRequest {
     pub game_id: Option<String>, // Global chat if None
@@ -282,13 +485,139 @@ pub struct ServerInfo {
     pub max_players: u32,
     pub current_players: u32,
     pub features: Vec<String>,
+    /// Kinds of game `CreateGame` accepts: chess, and those hosted alongside
+    /// it.
+    #[serde(default)]
+    pub game_kinds: Vec<String>,
+    /// Time controls `CreateGame` accepts by name.
+    #[serde(default)]
+    pub time_control_presets: Vec<TimeControl>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct TimeControl {
-    pub initial_time_secs: u32,
-    pub increment_secs: u32,
-    pub name: String,
+pub struct JoinTurnBasedGameResponse {
+    pub game_id: String,
+    pub seat: usize,
+    pub game_state: TurnBasedSnapshot,
+}
+
+/// A move in the game's own format, such as `"e2e4"` in chess or a cell
+/// index in tic-tac-toe.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlayTurnRequest {
+    pub game_id: String,
+    pub game_move: Value,
+    /// How long the move took on the client's side, as in `MakeMove`.
+    #[serde(default)]
+    pub move_time_ms: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TurnBasedUpdateNotification {
+    pub game_id: String,
+    pub game_state: TurnBasedSnapshot,
+}
+
+/// A game as the lobby lists it, whatever its kind. `GetGameInfo` has the
+/// rest of a chess game.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GameSummary {
+    pub game_id: String,
+    /// `chess`, or another of `ServerInfo::game_kinds`.
+    pub kind: String,
+    /// The variant, in chess games.
+    #[serde(default)]
+    pub variant: Option<Variant>,
+    /// Player ID in each seat; White then Black in chess.
+    pub players: Vec<Option<String>>,
+    pub status: GameStatus,
+    pub move_count: usize,
+    pub created_at: u64,
+}
+
+impl From<&GameState> for GameSummary {
+    fn from(game: &GameState) -> Self {
+        let status = if game.result != GameResult::Ongoing {
+            GameStatus::Finished
+        } else if game.is_ready_to_start() {
+            GameStatus::Active
+        } else {
+            GameStatus::Waiting
+        };
+
+        Self {
+            game_id: game.id.clone(),
+            kind: CHESS_KIND.to_string(),
+            variant: Some(game.variant),
+            players: vec![game.white_player.clone(), game.black_player.clone()],
+            status,
+            move_count: game.move_history.len(),
+            created_at: game.created_at,
+        }
+    }
+}
+
+impl From<&Table> for GameSummary {
+    fn from(table: &Table) -> Self {
+        let status = if table.is_over() {
+            GameStatus::Finished
+        } else if table.is_full() {
+            GameStatus::Active
+        } else {
+            GameStatus::Waiting
+        };
+
+        Self {
+            game_id: table.id.clone(),
+            kind: table.kind().to_string(),
+            variant: None,
+            players: table.players.clone(),
+            status,
+            move_count: table.move_count,
+            created_at: table.created_at,
+        }
+    }
+}
+
+/// A game of any kind as one player sees it. `view` and `outcome` are in
+/// the game's own format.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TurnBasedSnapshot {
+    pub kind: String,
+    /// Player ID in each seat.
+    pub players: Vec<Option<String>>,
+    pub to_move: usize,
+    pub view: Value,
+    pub outcome: Option<Value>,
+    /// How the game ended away from the board, if it did.
+    #[serde(default)]
+    pub result: Option<GameResult>,
+    #[serde(default)]
+    pub time_control: Option<TimeControl>,
+    /// Each seat's time left, in timed games.
+    #[serde(default)]
+    pub remaining_ms: Vec<Option<u64>>,
+    /// The draw offer standing, if there is one.
+    #[serde(default)]
+    pub draw_offer: Option<DrawOffer>,
+}
+
+impl TurnBasedSnapshot {
+    pub fn new(table: &Table, player_id: &str) -> Self {
+        Self {
+            kind: table.game.kind().to_string(),
+            players: table.players.clone(),
+            to_move: table.game.to_move(),
+            view: table.view_for(player_id),
+            outcome: table.game.outcome(),
+            result: table.result.clone(),
+            time_control: table.clock.as_ref().map(|clock| clock.time_control.clone()),
+            remaining_ms: (0..table.players.len())
+                .map(|seat| table.remaining_ms(seat))
+                .collect(),
+            draw_offer: table.pending_draw_offer().cloned(),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -303,6 +632,38 @@ pub struct GameStateSnapshot {
     pub time_control: Option<TimeControl>,
     pub white_time_remaining_ms: Option<u64>,
     pub black_time_remaining_ms: Option<u64>,
+    /// Days per move and the current deadline, in correspondence games.
+    #[serde(default)]
+    pub correspondence: Option<Correspondence>,
+    /// The draw offer standing, if there is one.
+    #[serde(default)]
+    pub draw_offer: Option<DrawOffer>,
+    /// Pieces in hand, for variants with drops.
+    #[serde(default)]
+    pub pockets: Option<PocketsSnapshot>,
+    /// Checks given by each side, in Three-check.
+    #[serde(default)]
+    pub check_counts: Option<CheckCounts>,
+    /// The squares the recipient can see, in fog-of-war games. Enemy pieces
+    /// elsewhere are left out of `board_fen` and `move_history` holds only
+    /// the recipient's own moves.
+    #[serde(default)]
+    pub visible_squares: Option<Vec<Position>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PocketsSnapshot {
+    pub white: Vec<PieceType>,
+    pub black: Vec<PieceType>,
+}
+
+impl From<Pockets> for PocketsSnapshot {
+    fn from(pockets: Pockets) -> Self {
+        Self {
+            white: pockets.pieces(Color::White),
+            black: pockets.pieces(Color::Black),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -446,6 +807,7 @@ impl Message {
                 | MessageType::GetGameList(_)
                 | MessageType::GetGameInfo(_)
                 | MessageType::GetLegalMoves(_)
+                | MessageType::GetPgn(_)
                 | MessageType::GetOnlinePlayers(_)
                 | MessageType::SendMessage(_)
                 | MessageType::OfferDraw(_)
@@ -464,6 +826,7 @@ impl Message {
                 | MessageType::GetGameListResponse(_)
                 | MessageType::GetGameInfoResponse(_)
                 | MessageType::GetLegalMovesResponse(_)
+                | MessageType::GetPgnResponse(_)
                 | MessageType::GetOnlinePlayersResponse(_)
                 | MessageType::Success(_)
                 | MessageType::Error(_)
@@ -475,6 +838,8 @@ impl Message {
             self.message_type,
             MessageType::GameUpdate(_)
                 | MessageType::MoveUpdate(_)
+                | MessageType::YourMove(_)
+                | MessageType::DrawOfferUpdate(_)
                 | MessageType::ChatMessage(_)
                 | MessageType::Heartbeat
         )
@@ -493,11 +858,14 @@ impl Message {
             MessageType::JoinGameResponse(_) => "JoinGameResponse",
             MessageType::LeaveGame(_) => "LeaveGame",
             MessageType::SpectateGame(_) => "SpectateGame",
+            MessageType::SpectateGameResponse(_) => "SpectateGameResponse",
             MessageType::MakeMove(_) => "MakeMove",
             MessageType::GameUpdate(_) => "GameUpdate",
             MessageType::MoveUpdate(_) => "MoveUpdate",
+            MessageType::YourMove(_) => "YourMove",
             MessageType::OfferDraw(_) => "OfferDraw",
             MessageType::RespondToDraw(_) => "RespondToDraw",
+            MessageType::DrawOfferUpdate(_) => "DrawOfferUpdate",
             MessageType::Resign(_) => "Resign",
             MessageType::RequestUndo(_) => "RequestUndo",
             MessageType::RespondToUndo(_) => "RespondToUndo",
@@ -512,6 +880,12 @@ impl Message {
             MessageType::GetGameInfoResponse(_) => "GetGameInfoResponse",
             MessageType::GetLegalMoves(_) => "GetLegalMoves",
             MessageType::GetLegalMovesResponse(_) => "GetLegalMovesResponse",
+            MessageType::GetPgn(_) => "GetPgn",
+            MessageType::GetPgnResponse(_) => "GetPgnResponse",
+            MessageType::JoinTurnBasedGameResponse(_) => "JoinTurnBasedGameResponse",
+            MessageType::SpectateTurnBasedGameResponse(_) => "SpectateTurnBasedGameResponse",
+            MessageType::PlayTurn(_) => "PlayTurn",
+            MessageType::TurnBasedUpdate(_) => "TurnBasedUpdate",
             MessageType::SendMessage(_) => "SendMessage",
             MessageType::ChatMessage(_) => "ChatMessage",
             MessageType::Ping => "Ping",
@@ -549,7 +923,17 @@ pub fn create_connect_request(
 pub fn create_make_move_request(game_id: String, chess_move: Move) -> Message {
     Message::request(MessageType::MakeMove(MakeMoveRequest {
         game_id,
-        chess_move,
+        chess_move: Some(chess_move),
+        san: None,
+        move_time_ms: None,
+    }))
+}
+
+pub fn create_san_move_request(game_id: String, san: String) -> Message {
+    Message::request(MessageType::MakeMove(MakeMoveRequest {
+        game_id,
+        chess_move: None,
+        san: Some(san),
         move_time_ms: None,
     }))
 }
@@ -566,6 +950,7 @@ pub fn create_game_update_notification(
         game_id,
         game_state,
         last_move,
+        last_move_san: None,
         player_to_move,
         is_check,
         game_result,
@@ -575,7 +960,7 @@ pub fn create_game_update_notification(
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::game::Position;
+    use crate::game::{Position, StandardRules};
 
     #[test]
     fn test_message_serialization() {
@@ -606,13 +991,99 @@ mod tests {
         match deserialized.message_type {
             MessageType::MakeMove(req) => {
                 assert_eq!(req.game_id, "game123");
-                assert_eq!(req.chess_move.from.to_algebraic(), "e2");
-                assert_eq!(req.chess_move.to.to_algebraic(), "e4");
+                let chess_move = req.chess_move.unwrap();
+                assert_eq!(chess_move.from.to_algebraic(), "e2");
+                assert_eq!(chess_move.to.to_algebraic(), "e4");
             }
             _ => panic!("Expected MakeMove message"),
         }
     }
 
+    #[test]
+    fn test_san_move_request() {
+        let move_msg = create_san_move_request("game123".to_string(), "Nf3".to_string());
+
+        let json = move_msg.to_json().unwrap();
+        let deserialized = Message::from_json(&json).unwrap();
+
+        let req = match deserialized.message_type {
+            MessageType::MakeMove(req) => req,
+            _ => panic!("Expected MakeMove message"),
+        };
+
+        let board = Board::new();
+        let chess_move = req.resolve_move(&board, &StandardRules).unwrap();
+        assert_eq!(chess_move.to_algebraic(), "g1f3");
+
+        let bad = MakeMoveRequest {
+            san: Some("Nf4".to_string()),
+            ..req
+        };
+        assert!(bad.resolve_move(&board, &StandardRules).is_err());
+    }
+
+    #[test]
+    fn test_create_game_variant() {
+        let req: CreateGameRequest = serde_json::from_str(
+            r#"{"time_control":null,"color_preference":null,"is_private":false,"password":null}"#,
+        )
+        .unwrap();
+        assert_eq!(req.variant, Variant::Standard);
+        assert_eq!(req.start_position, None);
+
+        let req: CreateGameRequest = serde_json::from_str(
+            r#"{"time_control":null,"color_preference":null,"is_private":false,"password":null,"variant":"Chess960","start_position":42}"#,
+        )
+        .unwrap();
+        assert_eq!(req.variant, Variant::Chess960);
+        assert_eq!(req.start_position, Some(42));
+    }
+
+    #[test]
+    fn test_coordinate_move_gets_castle_flag() {
+        let board = Board::from_fen("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1").unwrap();
+        let req = MakeMoveRequest {
+            game_id: "game123".to_string(),
+            chess_move: Move::from_algebraic("e1g1"),
+            san: None,
+            move_time_ms: None,
+        };
+
+        assert!(req.resolve_move(&board, &StandardRules).unwrap().is_castle);
+    }
+
+    #[test]
+    fn test_drop_move_request() {
+        let board = Board::from_fen("4k3/8/8/8/8/8/8/4K3[N] w - - 0 1").unwrap();
+        let req = MakeMoveRequest {
+            game_id: "game123".to_string(),
+            chess_move: Move::from_algebraic("N@f3"),
+            san: None,
+            move_time_ms: None,
+        };
+        assert_eq!(
+            req.resolve_move(&board, &StandardRules).unwrap().drop,
+            Some(PieceType::Knight)
+        );
+
+        let pockets = PocketsSnapshot::from(*board.pockets().unwrap());
+        assert_eq!(pockets.white, [PieceType::Knight]);
+        assert!(pockets.black.is_empty());
+    }
+
+    #[test]
+    fn test_play_turn_payload() {
+        let message = Message::from_json(
+            r#"{"id":null,"version":"1.0","timestamp":0,"message_type":{"type":"PlayTurn","data":{"game_id":"game123","game_move":4}}}"#,
+        )
+        .unwrap();
+
+        match message.message_type {
+            MessageType::PlayTurn(req) => assert_eq!(req.game_move, 4),
+            _ => panic!("Expected PlayTurn"),
+        }
+    }
+
     #[test]
     fn test_message_size_limit() {
         let large_string = "a".repeat(MAX_MESSAGE_SIZE + 1);
@@ -1,21 +1,39 @@
+use std::collections::HashMap;
 use std::net::SocketAddr;
 use std::sync::Arc;
 
+use tokio::io::AsyncWriteExt;
 use tokio::net::{TcpListener, TcpStream};
 use tokio::sync::RwLock;
 use tokio::time::{Duration, interval};
 
-use crate::game::GameManager;
+use crate::game::{
+    BughouseManager, CHESS_KIND, Clock, Color, CorrespondenceManager, DrawOffer, GameManager,
+    GameResult, GameState, GameStore, Move, Position, Variant, Viewer,
+};
 use crate::network::client::{Client, ClientManager, MessageHandler};
 use crate::network::protocol::*;
 use crate::player::{PlayerManager, Session};
-use crate::utils::{ChessResult, ChessServerError, ServerConfig, current_timestamp};
+use crate::turn_based::{Table, seat_color};
+use crate::utils::{
+    ChessResult, ChessServerError, ServerConfig, current_timestamp, current_timestamp_millis,
+};
+
+/// Pings still unanswered: ping message ID -> (player ID, when it was sent
+/// in unix milliseconds).
+type PendingPings = HashMap<String, (String, u64)>;
+
+/// How long a ping may go unanswered before it is given up on.
+const PING_TIMEOUT_MS: u64 = 30_000;
 
 pub struct ChessServer {
     config: ServerConfig,
     client_manager: Arc<ClientManager>,
     player_manager: Arc<RwLock<PlayerManager>>,
     game_manager: Arc<RwLock<GameManager>>,
+    bughouse_manager: Arc<RwLock<BughouseManager>>,
+    correspondence_manager: Arc<RwLock<CorrespondenceManager>>,
+    pending_pings: Arc<RwLock<PendingPings>>,
     server_info: ServerInfo,
     is_running: Arc<RwLock<bool>>,
     statistics: Arc<RwLock<ServerStatistics>>,
@@ -34,6 +52,7 @@ pub struct ServerStatistics {
 
 impl ChessServer {
     pub fn new(config: ServerConfig) -> Self {
+        let game_manager = GameManager::new();
         let server_info = ServerInfo {
             server_name: "Chess Server".to_string(),
             version: env!("CARGO_PKG_VERSION").to_string(),
@@ -44,8 +63,16 @@ impl ChessServer {
                 "spectator_mode".to_string(),
                 "chat".to_string(),
                 "rating_system".to_string(),
+                "turn_based_games".to_string(),
             ],
+            game_kinds: game_manager.kinds().into_iter().map(String::from).collect(),
+            time_control_presets: config.game.time_control_preset_list(),
         };
+        let game_store = config.game.correspondence_dir.as_ref().and_then(|dir| {
+            GameStore::open(dir)
+                .map_err(|e| eprintln!("Failed to open correspondence store {}: {}", dir, e))
+                .ok()
+        });
 
         Self {
             config: config.clone(),
@@ -53,7 +80,10 @@ impl ChessServer {
             player_manager: Arc::new(RwLock::new(PlayerManager::new(
                 config.security.session_timeout_secs,
             ))),
-            game_manager: Arc::new(RwLock::new(GameManager::new())),
+            game_manager: Arc::new(RwLock::new(game_manager)),
+            bughouse_manager: Arc::new(RwLock::new(BughouseManager::new())),
+            correspondence_manager: Arc::new(RwLock::new(CorrespondenceManager::new(game_store))),
+            pending_pings: Arc::new(RwLock::new(HashMap::new())),
             server_info,
             is_running: Arc::new(RwLock::new(false)),
             statistics: Arc::new(RwLock::new(ServerStatistics {
@@ -79,6 +109,7 @@ impl ChessServer {
             *is_running = true;
         }
 
+        self.restore_correspondence_games().await;
         self.start_cleanup_tasks().await;
 
         // Accept connections
@@ -91,7 +122,7 @@ impl ChessServer {
             }
 
             match listener.accept().await {
-                Ok((stream, addr)) => {
+                Ok((mut stream, addr)) => {
                     if self.client_manager.get_client_count().await
                         >= self.config.server.max_connections
                     {
@@ -140,15 +171,22 @@ impl ChessServer {
         println!("Chess server stopped");
     }
 
-    async fn handle_new_client(&self, stream: TcpStream, addr: SocketAddr) {
-        let handler = Arc::new(ServerMessageHandler {
+    fn message_handler(&self) -> ServerMessageHandler {
+        ServerMessageHandler {
             client_manager: Arc::clone(&self.client_manager),
             player_manager: Arc::clone(&self.player_manager),
             game_manager: Arc::clone(&self.game_manager),
+            bughouse_manager: Arc::clone(&self.bughouse_manager),
+            correspondence_manager: Arc::clone(&self.correspondence_manager),
+            pending_pings: Arc::clone(&self.pending_pings),
             server_info: self.server_info.clone(),
             config: self.config.clone(),
             statistics: Arc::clone(&self.statistics),
-        });
+        }
+    }
+
+    async fn handle_new_client(&self, stream: TcpStream, addr: SocketAddr) {
+        let handler = Arc::new(self.message_handler());
 
         match Client::new(stream, addr, handler).await {
             Ok(client) => {
@@ -162,10 +200,78 @@ impl ChessServer {
         }
     }
 
+    /// Hosts the stored correspondence games again, giving their players
+    /// back the IDs they were seated under.
+    async fn restore_correspondence_games(&self) {
+        let stored_games = match self.correspondence_manager.write().await.load() {
+            Ok(stored_games) => stored_games,
+            Err(e) => {
+                eprintln!("Failed to load correspondence games: {}", e);
+                return;
+            }
+        };
+
+        let mut game_manager = self.game_manager.write().await;
+        let mut player_manager = self.player_manager.write().await;
+        for stored in &stored_games {
+            let seats = [
+                (&stored.game.white_player, &stored.white_name),
+                (&stored.game.black_player, &stored.black_name),
+            ];
+            for (player_id, name) in seats {
+                if let (Some(player_id), Some(name)) = (player_id, name)
+                    && let Err(e) = player_manager.restore_player(player_id, name)
+                {
+                    eprintln!("Failed to restore player {}: {}", name, e);
+                }
+            }
+        }
+
+        let restored = stored_games.len();
+        for stored in stored_games {
+            game_manager.restore_game(stored.game);
+        }
+        if restored > 0 {
+            println!("Restored {} correspondence games", restored);
+        }
+    }
+
     async fn start_cleanup_tasks(&self) {
+        // Correspondence deadlines, which run for days, are checked as they
+        // come due rather than with a timer per game
+        {
+            let handler = self.message_handler();
+            let is_running = Arc::clone(&self.is_running);
+
+            tokio::spawn(async move {
+                loop {
+                    {
+                        let is_running = is_running.read().await;
+                        if !*is_running {
+                            break;
+                        }
+                    }
+
+                    let now = current_timestamp();
+                    let due = handler.correspondence_manager.write().await.take_due(now);
+                    for game_id in due {
+                        handler.flag_game(&game_id).await;
+                    }
+
+                    let next_deadline = handler.correspondence_manager.read().await.next_deadline();
+                    let wait_secs = next_deadline
+                        .map_or(60, |deadline| deadline.saturating_sub(now))
+                        .clamp(1, 60);
+                    tokio::time::sleep(Duration::from_secs(wait_secs)).await;
+                }
+            });
+        }
+
         {
             let client_manager = Arc::clone(&self.client_manager);
             let player_manager = Arc::clone(&self.player_manager);
+            let game_manager = Arc::clone(&self.game_manager);
+            let game_timeout_secs = self.config.game.game_timeout_secs;
             let is_running = Arc::clone(&self.is_running);
 
             tokio::spawn(async move {
@@ -193,6 +299,58 @@ impl ChessServer {
                     if expired_cnt > 0 {
                         println!("Cleaned up {} expired sessions", expired_cnt);
                     }
+
+                    game_manager.write().await.cleanup_tables(game_timeout_secs);
+                }
+            });
+        }
+
+        // Players' round trips, measured for lag compensation
+        {
+            let client_manager = Arc::clone(&self.client_manager);
+            let pending_pings = Arc::clone(&self.pending_pings);
+            let ping_interval_secs = self.config.server.ping_interval_secs;
+            let is_running = Arc::clone(&self.is_running);
+
+            tokio::spawn(async move {
+                let mut interval = interval(Duration::from_secs(ping_interval_secs));
+
+                loop {
+                    interval.tick().await;
+
+                    {
+                        let is_running = is_running.read().await;
+                        if !*is_running {
+                            break;
+                        }
+                    }
+
+                    let now = current_timestamp_millis();
+                    pending_pings
+                        .write()
+                        .await
+                        .retain(|_, (_, sent_at)| now.saturating_sub(*sent_at) < PING_TIMEOUT_MS);
+
+                    for player_id in client_manager.get_player_ids().await {
+                        let ping = Message::request(MessageType::Ping);
+                        let Some(ping_id) = ping.id.clone() else {
+                            continue;
+                        };
+
+                        // Recorded before sending, so a pong answering at
+                        // once still finds its ping
+                        pending_pings.write().await.insert(
+                            ping_id.clone(),
+                            (player_id.clone(), current_timestamp_millis()),
+                        );
+                        if client_manager
+                            .send_to_player(&player_id, ping)
+                            .await
+                            .is_err()
+                        {
+                            pending_pings.write().await.remove(&ping_id);
+                        }
+                    }
                 }
             });
         }
@@ -234,10 +392,14 @@ impl ChessServer {
     }
 }
 
+#[derive(Clone)]
 struct ServerMessageHandler {
     client_manager: Arc<ClientManager>,
     player_manager: Arc<RwLock<PlayerManager>>,
     game_manager: Arc<RwLock<GameManager>>,
+    bughouse_manager: Arc<RwLock<BughouseManager>>,
+    correspondence_manager: Arc<RwLock<CorrespondenceManager>>,
+    pending_pings: Arc<RwLock<PendingPings>>,
     server_info: ServerInfo,
     config: ServerConfig,
     statistics: Arc<RwLock<ServerStatistics>>,
@@ -271,6 +433,10 @@ impl MessageHandler for ServerMessageHandler {
                 self.handle_join_game(req, &client_info, session, message.id)
                     .await
             }
+            MessageType::SpectateGame(req) => {
+                self.handle_spectate_game(req, &client_info, session, message.id)
+                    .await
+            }
             MessageType::MakeMove(req) => {
                 self.handle_make_move(req, &client_info, session, message.id)
                     .await
@@ -291,6 +457,7 @@ impl MessageHandler for ServerMessageHandler {
                 self.handle_get_legal_moves(req, &client_info, session, message.id)
                     .await
             }
+            MessageType::GetPgn(req) => self.handle_get_pgn(req, session, message.id).await,
             MessageType::GetOnlinePlayers(req) => {
                 self.handle_get_online_players(req, &client_info, message.id)
                     .await
@@ -307,11 +474,19 @@ impl MessageHandler for ServerMessageHandler {
                 self.handle_respond_to_draw(req, &client_info, session, message.id)
                     .await
             }
+            MessageType::PlayTurn(req) => {
+                self.handle_play_turn(req, &client_info, session, message.id)
+                    .await
+            }
             MessageType::SendMessage(req) => {
                 self.handle_send_message(req, &client_info, session, message.id)
                     .await
             }
             MessageType::Ping => Some(Message::response(MessageType::Pong, message.id)),
+            MessageType::Pong => {
+                self.handle_pong(message.id).await;
+                None
+            }
             MessageType::Heartbeat => {
                 // update client's last activity
                 None
@@ -396,6 +571,7 @@ impl ServerMessageHandler {
                 request_id,
             ));
         }
+        self.deliver_queued_moves(&player_id);
 
         Some(Message::response(
             MessageType::ConnectResponse(ConnectResponse {
@@ -431,6 +607,7 @@ impl ServerMessageHandler {
                 return Some(Message::error(e, request_id));
             }
         }
+        self.deliver_queued_moves(&player_id);
 
         let player = match player_manager.get_player(&player_id) {
             Some(p) => p,
@@ -469,18 +646,91 @@ impl ServerMessageHandler {
             }
         };
 
+        let time_control = match &req.time_control_preset {
+            Some(name) => match self.config.game.time_control_preset(name) {
+                Some(time_control) => Some(time_control),
+                None => {
+                    return Some(Message::error(
+                        ChessServerError::InvalidMessage {
+                            details: format!("Unknown time control preset '{}'", name),
+                        },
+                        request_id,
+                    ));
+                }
+            },
+            None => req.time_control.clone(),
+        };
+
+        // Correspondence games are timed in days per move, not on a clock
+        if req.days_per_move.is_some()
+            && (time_control.is_some() || req.variant == Variant::Bughouse)
+        {
+            return Some(Message::error(
+                ChessServerError::InvalidMessage {
+                    details: "Correspondence games take no clock and cannot be Bughouse"
+                        .to_string(),
+                },
+                request_id,
+            ));
+        }
+
         let mut game_manager = self.game_manager.write().await;
         let mut player_manager = self.player_manager.write().await;
 
-        let game_id = game_manager.create_game();
+        if req.kind.as_deref().is_some_and(|kind| kind != CHESS_KIND) {
+            return self
+                .create_table_game(
+                    &req,
+                    time_control,
+                    &session,
+                    &mut game_manager,
+                    &mut player_manager,
+                    request_id,
+                )
+                .await;
+        }
+
+        if req.variant == Variant::Bughouse {
+            return self
+                .create_bughouse_match(
+                    &req,
+                    time_control,
+                    &session,
+                    &mut game_manager,
+                    &mut player_manager,
+                    request_id,
+                )
+                .await;
+        }
+
+        let game_id = match game_manager.create_game_with_variant(req.variant, req.start_position) {
+            Ok(game_id) => game_id,
+            Err(details) => {
+                return Some(Message::error(
+                    ChessServerError::InvalidMessage { details },
+                    request_id,
+                ));
+            }
+        };
+        if let Some(game) = game_manager.get_game_mut(&game_id) {
+            if let Some(time_control) = &time_control {
+                game.set_clock(self.clock(time_control));
+            }
+            if let Some(days_per_move) = req.days_per_move {
+                game.set_correspondence(days_per_move);
+            }
+        }
 
         let player_color =
             match game_manager.join_game(&game_id, session.player_id.clone(), req.color_preference)
             {
                 Ok(color) => color,
-                Err(e) => {
+                Err(details) => {
                     game_manager.remove_game(&game_id);
-                    return Some(Message::error(e, request_id));
+                    return Some(Message::error(
+                        ChessServerError::InvalidMessage { details },
+                        request_id,
+                    ));
                 }
             };
 
@@ -488,6 +738,9 @@ impl ServerMessageHandler {
             game_manager.remove_game(&game_id);
             return Some(Message::error(e, request_id));
         }
+        if let Some(game) = game_manager.get_game(&game_id) {
+            self.save_correspondence_game(game, &player_manager).await;
+        }
 
         {
             let mut stats = self.statistics.write().await;
@@ -498,6 +751,154 @@ impl ServerMessageHandler {
             MessageType::CreateGameResponse(CreateGameResponse {
                 game_id,
                 player_color,
+                bughouse: None,
+            }),
+            request_id,
+        ))
+    }
+
+    async fn create_bughouse_match(
+        &self,
+        req: &CreateGameRequest,
+        time_control: Option<TimeControl>,
+        session: &Session,
+        game_manager: &mut GameManager,
+        player_manager: &mut PlayerManager,
+        request_id: Option<String>,
+    ) -> Option<Message> {
+        let mut bughouse_manager = self.bughouse_manager.write().await;
+
+        let match_id = match bughouse_manager.create_match(game_manager) {
+            Ok(match_id) => match_id,
+            Err(details) => {
+                return Some(Message::error(
+                    ChessServerError::InvalidMessage { details },
+                    request_id,
+                ));
+            }
+        };
+        let boards = match bughouse_manager.get_match(&match_id) {
+            Some(bughouse) => bughouse.boards.clone(),
+            None => {
+                return Some(Message::error(
+                    ChessServerError::GameNotFound { game_id: match_id },
+                    request_id,
+                ));
+            }
+        };
+        if let Some(time_control) = &time_control {
+            for board in &boards {
+                if let Some(game) = game_manager.get_game_mut(board) {
+                    game.set_clock(self.clock(time_control));
+                }
+            }
+        }
+
+        let (game_id, seat) = match bughouse_manager.join_match(
+            game_manager,
+            &match_id,
+            session.player_id.clone(),
+            req.seat_preference(),
+        ) {
+            Ok(seated) => seated,
+            Err(details) => {
+                for board in &boards {
+                    game_manager.remove_game(board);
+                }
+                bughouse_manager.cleanup(game_manager);
+                return Some(Message::error(
+                    ChessServerError::InvalidMessage { details },
+                    request_id,
+                ));
+            }
+        };
+
+        if let Err(e) = player_manager.add_player_to_game(&session.player_id, &game_id) {
+            for board in &boards {
+                game_manager.remove_game(board);
+            }
+            bughouse_manager.cleanup(game_manager);
+            return Some(Message::error(e, request_id));
+        }
+
+        {
+            let mut stats = self.statistics.write().await;
+            stats.total_games_created += 1;
+        }
+
+        Some(Message::response(
+            MessageType::CreateGameResponse(CreateGameResponse {
+                game_id,
+                player_color: seat.color,
+                bughouse: Some(BughouseSeating::new(match_id, boards, seat)),
+            }),
+            request_id,
+        ))
+    }
+
+    /// Starts a game of `req.kind`, other than chess, and seats its creator.
+    async fn create_table_game(
+        &self,
+        req: &CreateGameRequest,
+        time_control: Option<TimeControl>,
+        session: &Session,
+        game_manager: &mut GameManager,
+        player_manager: &mut PlayerManager,
+        request_id: Option<String>,
+    ) -> Option<Message> {
+        if req.variant != Variant::Standard || req.days_per_move.is_some() {
+            return Some(Message::error(
+                ChessServerError::InvalidMessage {
+                    details: "Games of other kinds take no variant or correspondence".to_string(),
+                },
+                request_id,
+            ));
+        }
+
+        let kind = req.kind.as_deref().unwrap_or_default();
+        let game_id = match game_manager.create_game_of_kind(kind) {
+            Ok(game_id) => game_id,
+            Err(details) => {
+                return Some(Message::error(
+                    ChessServerError::InvalidMessage { details },
+                    request_id,
+                ));
+            }
+        };
+
+        if let Some(time_control) = &time_control
+            && let Some(table) = game_manager.get_table_mut(&game_id)
+        {
+            table.set_clock(self.clock(time_control));
+        }
+
+        let seat = req.color_preference.map(Color::index);
+        let seat = match game_manager.join_table(&game_id, session.player_id.clone(), seat) {
+            Ok(seat) => seat,
+            Err(details) => {
+                game_manager.remove_game(&game_id);
+                return Some(Message::error(
+                    ChessServerError::InvalidMessage { details },
+                    request_id,
+                ));
+            }
+        };
+
+        if let Err(e) = player_manager.add_player_to_game(&session.player_id, &game_id) {
+            game_manager.remove_game(&game_id);
+            return Some(Message::error(e, request_id));
+        }
+
+        {
+            let mut stats = self.statistics.write().await;
+            stats.total_games_created += 1;
+        }
+
+        Some(Message::response(
+            MessageType::CreateGameResponse(CreateGameResponse {
+                game_id,
+                player_color: seat_color(seat),
+                bughouse: None,
             }),
             request_id,
         ))
@@ -523,37 +924,76 @@ impl ServerMessageHandler {
         let mut game_manager = self.game_manager.write().await;
         let mut player_manager = self.player_manager.write().await;
 
-        let player_color = match game_manager.join_game(
-            &req.game_id,
-            session.palyer_id.clone(),
-            req.color_preference,
-        ) {
-            Ok(color) => color,
-            Err(e) => return Some(Message::error(e, request_id)),
+        if game_manager.get_table(&req.game_id).is_some() {
+            return self.join_table_game(
+                req,
+                &session,
+                &mut game_manager,
+                &mut player_manager,
+                request_id,
+            );
+        }
+
+        let bughouse_manager = self.bughouse_manager.read().await;
+
+        // Bughouse players join the match and are seated on one of its boards
+        let (game_id, player_color, bughouse) = match bughouse_manager.find_match(&req.game_id) {
+            Some(bughouse) => match bughouse_manager.join_match(
+                &mut game_manager,
+                &bughouse.id,
+                session.player_id.clone(),
+                req.seat_preference(),
+            ) {
+                Ok((game_id, seat)) => {
+                    let seating =
+                        BughouseSeating::new(bughouse.id.clone(), bughouse.boards.clone(), seat);
+                    (game_id, seat.color, Some(seating))
+                }
+                Err(details) => {
+                    return Some(Message::error(
+                        ChessServerError::InvalidMessage { details },
+                        request_id,
+                    ));
+                }
+            },
+            None => match game_manager.join_game(
+                &req.game_id,
+                session.player_id.clone(),
+                req.color_preference,
+            ) {
+                Ok(color) => (req.game_id.clone(), color, None),
+                Err(details) => {
+                    return Some(Message::error(
+                        ChessServerError::InvalidMessage { details },
+                        request_id,
+                    ));
+                }
+            },
         };
 
-        if let Err(e) = player_manager.add_player_to_game(&session.player_id, &req.game_id) {
+        if let Err(e) = player_manager.add_player_to_game(&session.player_id, &game_id) {
             return Some(Message::error(e, request_id));
         }
 
-        let game = match game_manager.get_game(&req.game_id) {
+        let game = match game_manager.get_game(&game_id) {
             Some(g) => g,
             None => {
                 return Some(Message::error(
-                    ChessServerError::GameNotFound {
-                        game_id: req.game_id,
-                    },
+                    ChessServerError::GameNotFound { game_id },
                     request_id,
                 ));
             }
         };
+        // The clock starts once both players are in
+        self.schedule_flag_check(&game_id, &game_manager);
+        self.save_correspondence_game(game, &player_manager).await;
 
         let opponent_id = match player_color {
             crate::game::Color::White => &game.black_player,
             crate::game::Color::Black => &game.white_player,
         };
 
-        let opponent_info = if let Some(ref opp_id) = opponent_id {
+        let opponent_info = if let Some(opp_id) = opponent_id {
             player_manager
                 .get_player(opp_id)
                 .map(|p| p.get_display_info())
@@ -561,92 +1001,366 @@ impl ServerMessageHandler {
             None
         };
 
-        let game_state = self.create_game_state_snapshot(game, &player_manager).await;
+        let game_state = self
+            .create_game_state_snapshot(game, &player_manager, Viewer::Player(player_color))
+            .await;
 
         Some(Message::response(
             MessageType::JoinGameResponse(JoinGameResponse {
-                game_id: req.game_id,
+                game_id,
                 player_color,
                 opponent_info,
                 game_state,
+                bughouse,
             }),
             request_id,
         ))
     }
 
-    async fn handle_make_move(
+    /// Seats a player at a game of a kind other than chess.
+    fn join_table_game(
         &self,
-        req: MakeMoveRequest,
+        req: JoinGameRequest,
+        session: &Session,
+        game_manager: &mut GameManager,
+        player_manager: &mut PlayerManager,
+        request_id: Option<String>,
+    ) -> Option<Message> {
+        let seat = req.seat.or(req.color_preference.map(Color::index));
+        let seat = match game_manager.join_table(&req.game_id, session.player_id.clone(), seat) {
+            Ok(seat) => seat,
+            Err(_) => return Some(Message::error(ChessServerError::GameFull, request_id)),
+        };
+
+        if let Err(e) = player_manager.add_player_to_game(&session.player_id, &req.game_id) {
+            return Some(Message::error(e, request_id));
+        }
+
+        // The clock starts once every seat is taken
+        self.schedule_flag_check(&req.game_id, game_manager);
+
+        let table = game_manager.get_table(&req.game_id)?;
+        let game_state = TurnBasedSnapshot::new(table, &session.player_id);
+
+        // The others at the table see the seat taken
+        let deliveries = Self::table_update_deliveries(table)
+            .into_iter()
+            .filter(|(player_ids, _)| player_ids[0] != session.player_id)
+            .collect();
+        self.spawn_deliveries(deliveries);
+
+        Some(Message::response(
+            MessageType::JoinTurnBasedGameResponse(JoinTurnBasedGameResponse {
+                game_id: req.game_id,
+                seat,
+                game_state,
+            }),
+            request_id,
+        ))
+    }
+
+    async fn handle_spectate_game(
+        &self,
+        req: SpectateGameRequest,
         _client_info: &crate::network::client::ClientInfo,
         session: Option<Session>,
         request_id: Option<String>,
     ) -> Option<Message> {
         let session = match session {
-            Some(s) => s,
-            None => {
+            Some(s) if s.can_spectate() && self.config.game.allow_spectators => s,
+            _ => {
                 return Some(Message::error(
-                    ChessServerError::AuthenticationFailed,
+                    ChessServerError::InsufficientPermissions,
                     request_id,
                 ));
             }
         };
 
-        let mut game_manager = self.game_manager.write().await;
-        let player_manager = self.player_manager.read().await;
-
-        if let Err(e) =
-            game_manager.make_move(&req.game_id, &session.player_id, req.chess_move.clone())
+        if self
+            .game_manager
+            .read()
+            .await
+            .get_table(&req.game_id)
+            .is_some()
         {
-            return Some(Message::error(e, request_id));
+            return Some(
+                self.spectate_table_game(req.game_id, session, request_id)
+                    .await,
+            );
         }
 
-        {
-            let mut stats = self.statistics.write().await;
-            stats.total_moves_player += 1;
-        }
+        let game_manager = self.game_manager.read().await;
+        let player_manager = self.player_manager.read().await;
 
         let game = match game_manager.get_game(&req.game_id) {
             Some(g) => g,
             None => {
                 return Some(Message::error(
                     ChessServerError::GameNotFound {
-                        game_id: req.game_id.clone(),
+                        game_id: req.game_id,
                     },
                     request_id,
                 ));
             }
         };
 
-        let game_state = self.create_game_state_snapshot(game, &player_manager).await;
-        let update_notification =
-            Message::notification(MessageType::GameUpdate(GameUpdateNotification {
-                game_id: req.game_id.clone(),
-                game_state,
-                last_move: Some(req.chess_move),
-                player_to_move: game.board.get_to_move(),
-                is_check: game.is_in_check(),
-                game_result: if game.result == crate::game::GameResult::Ongoing {
-                    None
-                } else {
-                    Some(game.result.clone())
-                },
-            }));
+        // Players watching their own game still only see their side of the fog
+        let viewer = game.viewer(&session.player_id);
+        let game_state = self
+            .create_game_state_snapshot(game, &player_manager, viewer)
+            .await;
 
-        let player_ids = vec![game.white_player.clone(), game.black_player.clone()]
-            .into_iter()
-            .flatten()
-            .collect::<Vec<_>>();
+        Some(Message::response(
+            MessageType::SpectateGameResponse(SpectateGameResponse {
+                game_id: req.game_id,
+                game_state,
+            }),
+            request_id,
+        ))
+    }
+
+    async fn spectate_table_game(
+        &self,
+        game_id: String,
+        session: Session,
+        request_id: Option<String>,
+    ) -> Message {
+        let mut game_manager = self.game_manager.write().await;
+        if game_manager
+            .spectate_table(&game_id, session.player_id.clone())
+            .is_err()
+        {
+            return Message::error(ChessServerError::GameNotFound { game_id }, request_id);
+        }
+
+        let Some(table) = game_manager.get_table(&game_id) else {
+            return Message::error(ChessServerError::GameNotFound { game_id }, request_id);
+        };
+        let game_state = TurnBasedSnapshot::new(table, &session.player_id);
+
+        Message::response(
+            MessageType::SpectateTurnBasedGameResponse(TurnBasedUpdateNotification {
+                game_id,
+                game_state,
+            }),
+            request_id,
+        )
+    }
+
+    async fn handle_make_move(
+        &self,
+        req: MakeMoveRequest,
+        _client_info: &crate::network::client::ClientInfo,
+        session: Option<Session>,
+        request_id: Option<String>,
+    ) -> Option<Message> {
+        let session = match session {
+            Some(s) => s,
+            None => {
+                return Some(Message::error(
+                    ChessServerError::AuthenticationFailed,
+                    request_id,
+                ));
+            }
+        };
+
+        if self.flag_game(&req.game_id).await {
+            return Some(Message::error(
+                ChessServerError::InvalidMove {
+                    reason: "Time has run out".to_string(),
+                },
+                request_id,
+            ));
+        }
+
+        let mut game_manager = self.game_manager.write().await;
+        let player_manager = self.player_manager.read().await;
+
+        let (chess_move, san, lag_ms) = match game_manager.get_game(&req.game_id) {
+            Some(game) => match req.resolve_move(&game.board, game.ruleset()) {
+                Ok(chess_move) => (
+                    chess_move,
+                    chess_move.to_san_with(&game.board, game.ruleset()),
+                    Self::lag_claim_ms(
+                        game.clock.as_ref(),
+                        &player_manager,
+                        &session.player_id,
+                        req.move_time_ms,
+                    ),
+                ),
+                Err(e) => return Some(Message::error(e, request_id)),
+            },
+            None => {
+                return Some(Message::error(
+                    ChessServerError::GameNotFound {
+                        game_id: req.game_id.clone(),
+                    },
+                    request_id,
+                ));
+            }
+        };
+
+        // Moving on withdraws the mover's own draw offer
+        let standing_offer = game_manager.pending_draw_offer(&req.game_id).cloned();
+
+        let bughouse_manager = self.bughouse_manager.read().await;
+        let bughouse = bughouse_manager.match_for_game(&req.game_id);
+
+        // The partner board is found before the move, which cannot be undone
+        let partner_id = match bughouse.map(|bughouse| bughouse.partner_board(&req.game_id)) {
+            Some(None) => {
+                return Some(Message::error(
+                    ChessServerError::InternalServerError {
+                        details: "Board not found in its Bughouse match".to_string(),
+                    },
+                    request_id,
+                ));
+            }
+            partner_id => partner_id.flatten().cloned(),
+        };
+
+        // Bughouse moves may pass a capture to, or end, the partner board
+        let partner_changed = match bughouse {
+            Some(_) => bughouse_manager
+                .make_move(
+                    &mut game_manager,
+                    &req.game_id,
+                    &session.player_id,
+                    chess_move,
+                    lag_ms,
+                )
+                .map(|outcome| outcome.transferred.is_some() || outcome.finished),
+            None => game_manager
+                .make_move_with_lag(&req.game_id, &session.player_id, chess_move, lag_ms)
+                .map(|_| false),
+        };
+        let partner_changed = match partner_changed {
+            Ok(changed) => changed,
+            Err(e) => {
+                return Some(Message::error(
+                    ChessServerError::InvalidMove { reason: e },
+                    request_id,
+                ));
+            }
+        };
+
+        {
+            let mut stats = self.statistics.write().await;
+            stats.total_moves_player += 1;
+        }
+
+        let game = match game_manager.get_game(&req.game_id) {
+            Some(g) => g,
+            None => {
+                return Some(Message::error(
+                    ChessServerError::GameNotFound {
+                        game_id: req.game_id.clone(),
+                    },
+                    request_id,
+                ));
+            }
+        };
+        self.schedule_flag_check(&req.game_id, &game_manager);
+        self.save_correspondence_game(game, &player_manager).await;
+        let your_move = self.your_move_notification(game, &player_manager);
+        let lag_credit_ms = match game.result {
+            GameResult::Ongoing => game
+                .clock
+                .as_ref()
+                .and_then(|clock| clock.last_lag_credit_ms),
+            _ => None,
+        };
+
+        // Each player gets their own view of the game, which in fog-of-war
+        // hides the opponent's pieces and moves
+        let mut deliveries = Vec::new();
+        match bughouse {
+            // Bughouse boards have no fog, so all four players share one view
+            Some(bughouse) => {
+                let player_ids = bughouse.players(&game_manager);
+                let mut notifications = vec![
+                    self.game_update_notification(
+                        game,
+                        Viewer::Spectator,
+                        Some(san.clone()),
+                        &player_manager,
+                    )
+                    .await,
+                    Self::move_update_notification(game, Viewer::Spectator, chess_move, &san),
+                ];
+
+                if partner_changed
+                    && let Some(partner) = partner_id.and_then(|id| game_manager.get_game(&id))
+                {
+                    notifications.push(
+                        self.game_update_notification(
+                            partner,
+                            Viewer::Spectator,
+                            None,
+                            &player_manager,
+                        )
+                        .await,
+                    );
+                }
+
+                for notification in notifications {
+                    deliveries.push((player_ids.clone(), notification));
+                }
+            }
+            None => {
+                let seats = [
+                    (&game.white_player, Color::White),
+                    (&game.black_player, Color::Black),
+                ];
+                for (player_id, color) in seats {
+                    let Some(player_id) = player_id else {
+                        continue;
+                    };
+                    let viewer = Viewer::Player(color);
+
+                    let notification = self
+                        .game_update_notification(game, viewer, Some(san.clone()), &player_manager)
+                        .await;
+                    deliveries.push((vec![player_id.clone()], notification));
+
+                    if game.last_move_for(viewer).is_some() {
+                        let notification =
+                            Self::move_update_notification(game, viewer, chess_move, &san);
+                        deliveries.push((vec![player_id.clone()], notification));
+                    }
+                }
+            }
+        }
+        deliveries.extend(Self::cancelled_offer_update(
+            &req.game_id,
+            standing_offer,
+            &game_manager,
+        ));
 
+        drop(bughouse_manager);
         drop(player_manager);
         drop(game_manager);
 
+        if let Some(credit_ms) = lag_credit_ms {
+            let _ = self
+                .player_manager
+                .write()
+                .await
+                .record_lag_credit(&session.player_id, credit_ms);
+        }
+
         tokio::spawn({
-            let client_manager = Arc::clone(&self.client_manager);
-            let notification = update_notification.clone();
+            let handler = self.clone();
             async move {
-                client_manager
-                    .send_to_players(&player_ids, notification)
-                    .await;
+                for (player_ids, notification) in deliveries {
+                    handler
+                        .client_manager
+                        .send_to_players(&player_ids, notification)
+                        .await;
+                }
+                if let Some((player_id, notification)) = your_move {
+                    handler.send_your_move(&player_id, notification).await;
+                }
             }
         });
 
@@ -685,6 +1399,7 @@ impl ServerMessageHandler {
             MessageType::GetPlayerInfoResponse(GetPlayerInfoResponse {
                 player_info: player.get_display_info(),
                 detailed_stats: Some(player.stats.clone()),
+                lag_stats: Some(player.lag),
             }),
             request_id,
         ))
@@ -698,54 +1413,40 @@ impl ServerMessageHandler {
     ) -> Option<Message> {
         let game_manager = self.game_manager.read().await;
 
-        let games = game_manager.get_active_games();
-        let mut game_infos = Vec::new();
-
-        for game in games {
-            let game_info = game.get_game_info();
-
-            // Filter
-            let mut matches = true;
-
-            if let Some(ref status_filter) = req.filter.status {
-                let game_status = match game_info.result {
-                    crate::game::GameResult::Ongoing => {
-                        if game_info.white_player.is_some() && game_info.black_player.is_some() {
-                            GameStatus::Active
-                        } else {
-                            GameStatus::Waiting
-                        }
-                    }
-                    _ => GameStatus::Finished,
-                };
-
-                if *status_filter != game_status {
-                    matches = false;
-                }
-            }
-
-            if matches {
-                game_infos.push(game_info);
-            }
-        }
+        // Chess games and games of other kinds share one list
+        let mut games: Vec<GameSummary> = game_manager
+            .get_active_games()
+            .into_iter()
+            .map(GameSummary::from)
+            .chain(
+                game_manager
+                    .get_active_tables()
+                    .into_iter()
+                    .map(GameSummary::from),
+            )
+            .filter(|summary| {
+                req.filter
+                    .status
+                    .as_ref()
+                    .is_none_or(|status| *status == summary.status)
+            })
+            .collect();
+        games.sort_by_key(|summary| summary.created_at);
 
         // Pagination
         let offset = req.offset.unwrap_or(0) as usize;
         let limit = req.limit.unwrap_or(50) as usize;
-        let total_count = game_infos.len() as u32;
+        let total_count = games.len() as u32;
 
-        if offset < game_infos.len() {
-            let end = std::cmp::min(offset + limit, game_infos.len());
-            game_infos = game_infos[offset..end].to_vec();
+        if offset < games.len() {
+            let end = std::cmp::min(offset + limit, games.len());
+            games = games[offset..end].to_vec();
         } else {
-            game_infos.clear();
+            games.clear();
         }
 
         Some(Message::response(
-            MessageType::GetGameListResponse(GetGameListResponse {
-                games: game_infos,
-                total_count,
-            }),
+            MessageType::GetGameListResponse(GetGameListResponse { games, total_count }),
             request_id,
         ))
     }
@@ -819,6 +1520,56 @@ impl ServerMessageHandler {
         ))
     }
 
+    async fn handle_get_pgn(
+        &self,
+        req: GetPgnRequest,
+        session: Option<Session>,
+        request_id: Option<String>,
+    ) -> Option<Message> {
+        if session.is_none() {
+            return Some(Message::error(
+                ChessServerError::AuthenticationFailed,
+                request_id,
+            ));
+        }
+
+        let game_manager = self.game_manager.read().await;
+        let player_manager = self.player_manager.read().await;
+
+        let game = match game_manager.get_game(&req.game_id) {
+            Some(g) => g,
+            None => {
+                return Some(Message::error(
+                    ChessServerError::GameNotFound {
+                        game_id: req.game_id,
+                    },
+                    request_id,
+                ));
+            }
+        };
+
+        // The PGN holds every move, which the fog hides until the game ends
+        if game.is_fogged() {
+            return Some(Message::error(
+                ChessServerError::InvalidMessage {
+                    details: "The PGN of a fog-of-war game is withheld until it is over"
+                        .to_string(),
+                },
+                request_id,
+            ));
+        }
+
+        let pgn = game.to_pgn_with_tags(&player_manager.pgn_tags(game));
+
+        Some(Message::response(
+            MessageType::GetPgnResponse(GetPgnResponse {
+                game_id: req.game_id,
+                pgn,
+            }),
+            request_id,
+        ))
+    }
+
     async fn handle_get_online_players(
         &self,
         req: GetOnlinePlayersRequest,
@@ -873,20 +1624,36 @@ impl ServerMessageHandler {
 
         let mut game_manager = self.game_manager.write().await;
 
-        let game = match game_manager.get_game_mut(&req.game_id) {
-            Some(g) => g,
-            None => {
-                return Some(Message::error(
-                    ChessServerError::GameNotFound {
-                        game_id: req.game_id,
-                    },
-                    request_id,
-                ));
-            }
-        };
+        if !game_manager.has_game(&req.game_id) {
+            return Some(Message::error(
+                ChessServerError::GameNotFound {
+                    game_id: req.game_id,
+                },
+                request_id,
+            ));
+        }
 
-        if let Err(e) = game.resign(&session.player_id) {
-            return Some(Message::error(e, request_id));
+        if let Err(details) = game_manager.resign(&req.game_id, &session.player_id) {
+            return Some(Message::error(
+                ChessServerError::InvalidMessage { details },
+                request_id,
+            ));
+        }
+
+        // Resigning one Bughouse board concedes the match, and everyone in
+        // the game hears of it
+        let player_manager = self.player_manager.read().await;
+        let deliveries = self
+            .game_over_deliveries(&req.game_id, &mut game_manager, &player_manager)
+            .await;
+
+        drop(player_manager);
+        drop(game_manager);
+
+        for (player_ids, notification) in deliveries {
+            self.client_manager
+                .send_to_players(&player_ids, notification)
+                .await;
         }
 
         Some(Message::success("Resignation recorded", request_id))
@@ -894,55 +1661,231 @@ impl ServerMessageHandler {
 
     async fn handle_offer_draw(
         &self,
-        _req: OfferDrawRequest,
+        req: OfferDrawRequest,
         _client_info: &crate::network::client::ClientInfo,
-        _session: Option<Session>,
+        session: Option<Session>,
         request_id: Option<String>,
     ) -> Option<Message> {
-        Some(Message::success("draw offer sent", request_id))
-    }
+        let session = match session {
+            Some(s) => s,
+            None => {
+                return Some(Message::error(
+                    ChessServerError::AuthenticationFailed,
+                    request_id,
+                ));
+            }
+        };
 
-    async fn handle_respond_to_draw(
-        &self,
-        _req: OfferDrawRequest,
-        _client_info: &crate::network::client::ClientInfo,
-        _session: Option<Session>,
-        request_id: Option<String>,
-    ) -> Option<Message> {
-        Some(Message::success("Draw response recorded", request_id))
+        // An offer left standing too long lapses before this one is weighed
+        self.expire_draw_offer(&req.game_id).await;
+
+        let mut game_manager = self.game_manager.write().await;
+        let player_manager = self.player_manager.read().await;
+
+        let (drawn, deliveries) = match self
+            .offer_draw_deliveries(
+                &req.game_id,
+                &session.player_id,
+                req.message,
+                &mut game_manager,
+                &player_manager,
+            )
+            .await
+        {
+            Ok(outcome) => outcome,
+            Err(e) => return Some(Message::error(e, request_id)),
+        };
+        self.schedule_draw_offer_expiry(&req.game_id, &game_manager);
+
+        drop(player_manager);
+        drop(game_manager);
+
+        for (player_ids, notification) in deliveries {
+            self.client_manager
+                .send_to_players(&player_ids, notification)
+                .await;
+        }
+
+        match drawn {
+            true => Some(Message::success("Draw agreed", request_id)),
+            false => Some(Message::success("Draw offer sent", request_id)),
+        }
     }
 
-    async fn handle_send_message(
+    async fn handle_respond_to_draw(
         &self,
-        req: ChatMessageRequest,
+        req: RespondToDrawRequest,
         _client_info: &crate::network::client::ClientInfo,
         session: Option<Session>,
         request_id: Option<String>,
     ) -> Option<Message> {
         let session = match session {
-            Some(s) if s.can_chat() => s,
-            _ => {
+            Some(s) => s,
+            None => {
                 return Some(Message::error(
-                    ChessServerError::InsufficientPermissions,
+                    ChessServerError::AuthenticationFailed,
                     request_id,
                 ));
             }
         };
 
+        // Offers left standing too long can no longer be taken up
+        self.expire_draw_offer(&req.game_id).await;
+
+        let mut game_manager = self.game_manager.write().await;
         let player_manager = self.player_manager.read().await;
-        let sender = match player_manager.get_player(&session.player_id) {
-            Some(p) => p.get_display_info(),
-            None => {
-                return Some(Message::error(
-                    ChessServerError::PlayerNotFound {
-                        player_id: session.player_id,
-                    },
-                    request_id,
-                ));
-            }
+
+        let deliveries = match self
+            .respond_to_draw_deliveries(
+                &req.game_id,
+                &session.player_id,
+                req.accept,
+                &mut game_manager,
+                &player_manager,
+            )
+            .await
+        {
+            Ok(deliveries) => deliveries,
+            Err(e) => return Some(Message::error(e, request_id)),
         };
 
-        let chat_notification =
+        drop(player_manager);
+        drop(game_manager);
+
+        for (player_ids, notification) in deliveries {
+            self.client_manager
+                .send_to_players(&player_ids, notification)
+                .await;
+        }
+
+        Some(Message::success("Draw response recorded", request_id))
+    }
+
+    async fn handle_play_turn(
+        &self,
+        req: PlayTurnRequest,
+        _client_info: &crate::network::client::ClientInfo,
+        session: Option<Session>,
+        request_id: Option<String>,
+    ) -> Option<Message> {
+        let session = match session {
+            Some(s) => s,
+            None => {
+                return Some(Message::error(
+                    ChessServerError::AuthenticationFailed,
+                    request_id,
+                ));
+            }
+        };
+
+        if self.flag_game(&req.game_id).await {
+            return Some(Message::error(
+                ChessServerError::InvalidMove {
+                    reason: "Time has run out".to_string(),
+                },
+                request_id,
+            ));
+        }
+
+        let mut game_manager = self.game_manager.write().await;
+        let player_manager = self.player_manager.read().await;
+
+        let lag_ms = match game_manager.get_table(&req.game_id) {
+            Some(table) => Self::lag_claim_ms(
+                table.clock.as_ref(),
+                &player_manager,
+                &session.player_id,
+                req.move_time_ms,
+            ),
+            None => {
+                return Some(Message::error(
+                    ChessServerError::GameNotFound {
+                        game_id: req.game_id,
+                    },
+                    request_id,
+                ));
+            }
+        };
+        drop(player_manager);
+
+        // Moving on withdraws the mover's own draw offer
+        let standing_offer = game_manager.pending_draw_offer(&req.game_id).cloned();
+
+        if let Err(e) =
+            game_manager.play_turn_with_lag(&req.game_id, &session.player_id, req.game_move, lag_ms)
+        {
+            return Some(Message::error(
+                ChessServerError::InvalidMove { reason: e },
+                request_id,
+            ));
+        }
+
+        {
+            let mut stats = self.statistics.write().await;
+            stats.total_moves_player += 1;
+        }
+
+        self.schedule_flag_check(&req.game_id, &game_manager);
+        let table = game_manager.get_table(&req.game_id)?;
+        let mut deliveries = Self::table_update_deliveries(table);
+        deliveries.extend(Self::cancelled_offer_update(
+            &req.game_id,
+            standing_offer,
+            &game_manager,
+        ));
+        let lag_credit_ms = match table.is_over() {
+            false => table
+                .clock
+                .as_ref()
+                .and_then(|clock| clock.last_lag_credit_ms),
+            true => None,
+        };
+        drop(game_manager);
+
+        if let Some(credit_ms) = lag_credit_ms {
+            let _ = self
+                .player_manager
+                .write()
+                .await
+                .record_lag_credit(&session.player_id, credit_ms);
+        }
+
+        self.spawn_deliveries(deliveries);
+
+        Some(Message::success("Move made successfully", request_id))
+    }
+
+    async fn handle_send_message(
+        &self,
+        req: ChatMessageRequest,
+        _client_info: &crate::network::client::ClientInfo,
+        session: Option<Session>,
+        request_id: Option<String>,
+    ) -> Option<Message> {
+        let session = match session {
+            Some(s) if s.can_chat() => s,
+            _ => {
+                return Some(Message::error(
+                    ChessServerError::InsufficientPermissions,
+                    request_id,
+                ));
+            }
+        };
+
+        let player_manager = self.player_manager.read().await;
+        let sender = match player_manager.get_player(&session.player_id) {
+            Some(p) => p.get_display_info(),
+            None => {
+                return Some(Message::error(
+                    ChessServerError::PlayerNotFound {
+                        player_id: session.player_id,
+                    },
+                    request_id,
+                ));
+            }
+        };
+
+        let chat_notification =
             Message::notification(MessageType::ChatMessage(ChatMessageNotification {
                 game_id: req.game_id.clone(),
                 sender,
@@ -988,10 +1931,12 @@ impl ServerMessageHandler {
         Some(Message::success("Message sent", request_id))
     }
 
+    /// The game as `viewer` may see it.
     async fn create_game_state_snapshot(
         &self,
         game: &crate::game::GameState,
         player_manager: &crate::player::PlayerManager,
+        viewer: Viewer,
     ) -> GameStateSnapshot {
         let white_player_info = if let Some(ref white_id) = game.white_player {
             player_manager
@@ -1010,8 +1955,8 @@ impl ServerMessageHandler {
         };
 
         GameStateSnapshot {
-            board_fen: game.board.to_fen(),
-            move_history: game.move_history.clone(),
+            board_fen: game.board_for(viewer).to_fen(),
+            move_history: game.moves_for(viewer),
             white_player: white_player_info,
             black_player: black_player_info,
             to_move: game.board.get_to_move(),
@@ -1021,16 +1966,612 @@ impl ServerMessageHandler {
             } else {
                 Some(game.result.clone())
             },
-            time_control: None,
-            white_time_remaining_ms: None,
-            black_time_remaining_ms: None,
+            time_control: game.clock.as_ref().map(|clock| clock.time_control.clone()),
+            white_time_remaining_ms: game.remaining_ms(Color::White),
+            black_time_remaining_ms: game.remaining_ms(Color::Black),
+            correspondence: game.correspondence,
+            draw_offer: game.pending_draw_offer().cloned(),
+            pockets: game.board.pockets().copied().map(PocketsSnapshot::from),
+            check_counts: game.board.check_counts().copied(),
+            visible_squares: game
+                .visible_squares_for(viewer)
+                .map(|visible| visible.squares().filter_map(Position::from_index).collect()),
+        }
+    }
+
+    /// A `GameUpdate` for `viewer`. `last_move_san` is the SAN of the last
+    /// move, passed on only if `viewer` may know about it.
+    async fn game_update_notification(
+        &self,
+        game: &GameState,
+        viewer: Viewer,
+        last_move_san: Option<String>,
+        player_manager: &crate::player::PlayerManager,
+    ) -> Message {
+        let game_state = self
+            .create_game_state_snapshot(game, player_manager, viewer)
+            .await;
+        let last_move = game.last_move_for(viewer);
+
+        Message::notification(MessageType::GameUpdate(GameUpdateNotification {
+            game_id: game.id.clone(),
+            game_state,
+            last_move,
+            last_move_san: last_move.and(last_move_san),
+            player_to_move: game.board.get_to_move(),
+            is_check: game.is_in_check(),
+            game_result: if game.result == crate::game::GameResult::Ongoing {
+                None
+            } else {
+                Some(game.result.clone())
+            },
+        }))
+    }
+
+    /// A `MoveUpdate` for `chess_move`, just played as `san`, with the
+    /// position it left as `viewer` sees it. Carries Atomic explosions for
+    /// clients to animate.
+    fn move_update_notification(
+        game: &GameState,
+        viewer: Viewer,
+        chess_move: Move,
+        san: &str,
+    ) -> Message {
+        Message::notification(MessageType::MoveUpdate(MoveUpdateNotification {
+            game_id: game.id.clone(),
+            chess_move,
+            san: san.to_string(),
+            player: game.board.get_to_move().opposite(),
+            move_number: game
+                .undo_history
+                .last()
+                .map_or(1, |undo| undo.fullmove_number),
+            time_taken_ms: game.clock.as_ref().and_then(|clock| clock.last_move_ms),
+            resulting_position: game.board_for(viewer).to_fen(),
+            explosion: game.last_explosion(),
+            white_time_remaining_ms: game.remaining_ms(Color::White),
+            black_time_remaining_ms: game.remaining_ms(Color::Black),
+        }))
+    }
+
+    /// A clock for `time_control`, giving back network lag as configured.
+    fn clock(&self, time_control: &TimeControl) -> Clock {
+        let clock = time_control.clock();
+        match self.config.game.lag_compensation {
+            Some(limits) => clock.with_lag_compensation(limits),
+            None => clock,
+        }
+    }
+
+    /// The lag `player_id` may claim for a move on `clock`, from how long
+    /// the server saw the move take and the `move_time_ms` the client
+    /// reports it took.
+    fn lag_claim_ms(
+        clock: Option<&Clock>,
+        player_manager: &PlayerManager,
+        player_id: &str,
+        move_time_ms: Option<u64>,
+    ) -> u64 {
+        let elapsed_ms = clock.and_then(|clock| clock.running_ms(current_timestamp_millis()));
+        match (elapsed_ms, player_manager.get_player(player_id)) {
+            (Some(elapsed_ms), Some(player)) => player.lag.lag_claim_ms(elapsed_ms, move_time_ms),
+            _ => 0,
+        }
+    }
+
+    /// Records the round trip of the ping a `Pong` answers.
+    async fn handle_pong(&self, ping_id: Option<String>) {
+        let Some(ping_id) = ping_id else {
+            return;
+        };
+        let Some((player_id, sent_at)) = self.pending_pings.write().await.remove(&ping_id) else {
+            return;
+        };
+
+        let rtt_ms = current_timestamp_millis().saturating_sub(sent_at);
+        let _ = self
+            .player_manager
+            .write()
+            .await
+            .record_ping(&player_id, rtt_ms);
+    }
+
+    /// A `DrawOfferUpdate` for both players in `game_id`, saying `offer`
+    /// now stands as `status`.
+    fn draw_offer_update(
+        game_id: &str,
+        offer: &DrawOffer,
+        status: DrawOfferStatus,
+        game_manager: &GameManager,
+    ) -> (Vec<String>, Message) {
+        let player_ids = [Color::White, Color::Black]
+            .into_iter()
+            .filter_map(|color| game_manager.player_id(game_id, color).cloned())
+            .collect();
+        let notification =
+            Message::notification(MessageType::DrawOfferUpdate(DrawOfferNotification {
+                game_id: game_id.to_string(),
+                offered_by: offer.from,
+                status,
+                message: offer.message.clone(),
+            }));
+        (player_ids, notification)
+    }
+
+    /// Records `player_id`'s draw offer in `game_id`, which an opponent who
+    /// takes every draw answers at once. Returns whether the game was drawn
+    /// and the updates to send, which tell both players of each change to
+    /// the offer.
+    async fn offer_draw_deliveries(
+        &self,
+        game_id: &str,
+        player_id: &str,
+        message: Option<String>,
+        game_manager: &mut GameManager,
+        player_manager: &PlayerManager,
+    ) -> Result<(bool, Vec<(Vec<String>, Message)>), ChessServerError> {
+        if !game_manager.has_game(game_id) {
+            return Err(ChessServerError::GameNotFound {
+                game_id: game_id.to_string(),
+            });
         }
+
+        // Offering while the opponent's offer stands accepts theirs
+        let standing = game_manager.pending_draw_offer(game_id).cloned();
+        let mut drawn = game_manager
+            .offer_draw(game_id, player_id, message)
+            .map_err(|details| ChessServerError::InvalidMessage { details })?;
+        let (offer, status) = match drawn {
+            true => (standing, DrawOfferStatus::Accepted),
+            false => (
+                game_manager.pending_draw_offer(game_id).cloned(),
+                DrawOfferStatus::Offered,
+            ),
+        };
+
+        let mut deliveries = Vec::new();
+        if let Some(offer) = offer {
+            deliveries.push(Self::draw_offer_update(
+                game_id,
+                &offer,
+                status,
+                game_manager,
+            ));
+
+            // Opponents who take every draw answer for themselves
+            if let Some(opponent_id) = game_manager
+                .player_id(game_id, offer.from.opposite())
+                .cloned()
+                && !drawn
+                && player_manager
+                    .get_player(&opponent_id)
+                    .is_some_and(|opponent| opponent.preferences.auto_accept_draws)
+                && game_manager
+                    .respond_to_draw(game_id, &opponent_id, true)
+                    .is_ok()
+            {
+                drawn = true;
+                deliveries.push(Self::draw_offer_update(
+                    game_id,
+                    &offer,
+                    DrawOfferStatus::Accepted,
+                    game_manager,
+                ));
+            }
+        }
+
+        let settled = match drawn {
+            true => {
+                self.game_over_deliveries(game_id, game_manager, player_manager)
+                    .await
+            }
+            false => {
+                self.draw_offer_changed_deliveries(game_id, game_manager, player_manager)
+                    .await
+            }
+        };
+        deliveries.extend(settled);
+        Ok((drawn, deliveries))
+    }
+
+    /// Answers the draw offered to `player_id` in `game_id`, returning the
+    /// updates to send, which tell both players of the answer.
+    async fn respond_to_draw_deliveries(
+        &self,
+        game_id: &str,
+        player_id: &str,
+        accept: bool,
+        game_manager: &mut GameManager,
+        player_manager: &PlayerManager,
+    ) -> Result<Vec<(Vec<String>, Message)>, ChessServerError> {
+        if !game_manager.has_game(game_id) {
+            return Err(ChessServerError::GameNotFound {
+                game_id: game_id.to_string(),
+            });
+        }
+
+        let offer = game_manager
+            .respond_to_draw(game_id, player_id, accept)
+            .map_err(|details| ChessServerError::InvalidMessage { details })?;
+        let status = match accept {
+            true => DrawOfferStatus::Accepted,
+            false => DrawOfferStatus::Declined,
+        };
+
+        let mut deliveries = vec![Self::draw_offer_update(
+            game_id,
+            &offer,
+            status,
+            game_manager,
+        )];
+        let settled = match accept {
+            true => {
+                self.game_over_deliveries(game_id, game_manager, player_manager)
+                    .await
+            }
+            false => {
+                self.draw_offer_changed_deliveries(game_id, game_manager, player_manager)
+                    .await
+            }
+        };
+        deliveries.extend(settled);
+        Ok(deliveries)
+    }
+
+    /// A `DrawOfferUpdate` for both players if the move just made in
+    /// `game_id` withdrew `standing`, the offer that stood before it.
+    fn cancelled_offer_update(
+        game_id: &str,
+        standing: Option<DrawOffer>,
+        game_manager: &GameManager,
+    ) -> Option<(Vec<String>, Message)> {
+        let offer = standing?;
+        if game_manager.pending_draw_offer(game_id) == Some(&offer) {
+            return None;
+        }
+        Some(Self::draw_offer_update(
+            game_id,
+            &offer,
+            DrawOfferStatus::Cancelled,
+            game_manager,
+        ))
+    }
+
+    /// Stores `game_id` once its draw offer changed while it goes on.
+    /// Returns the updates everyone at a table is to be sent, who see the
+    /// offer in the game state.
+    async fn draw_offer_changed_deliveries(
+        &self,
+        game_id: &str,
+        game_manager: &GameManager,
+        player_manager: &PlayerManager,
+    ) -> Vec<(Vec<String>, Message)> {
+        if let Some(table) = game_manager.get_table(game_id) {
+            return Self::table_update_deliveries(table);
+        }
+        if let Some(game) = game_manager.get_game(game_id) {
+            self.save_correspondence_game(game, player_manager).await;
+        }
+        Vec::new()
+    }
+
+    /// Wakes up when the draw offer standing in `game_id` would lapse, so
+    /// its players hear of it even if neither does anything. Offers made or
+    /// answered meanwhile leave this check with nothing to do.
+    fn schedule_draw_offer_expiry(&self, game_id: &str, game_manager: &GameManager) {
+        let Some(time_to_expiry_ms) = game_manager.time_to_draw_offer_expiry_ms(game_id) else {
+            return;
+        };
+
+        tokio::spawn({
+            let handler = self.clone();
+            let game_id = game_id.to_string();
+            async move {
+                tokio::time::sleep(Duration::from_millis(time_to_expiry_ms)).await;
+                handler.expire_draw_offer(&game_id).await;
+            }
+        });
+    }
+
+    /// Withdraws the draw offer standing in `game_id` if it has gone
+    /// unanswered too long, and tells its players. Returns whether it did.
+    async fn expire_draw_offer(&self, game_id: &str) -> bool {
+        let mut game_manager = self.game_manager.write().await;
+        let player_manager = self.player_manager.read().await;
+        let deliveries = self
+            .expired_offer_deliveries(game_id, &mut game_manager, &player_manager)
+            .await;
+
+        drop(player_manager);
+        drop(game_manager);
+
+        let expired = !deliveries.is_empty();
+        for (player_ids, notification) in deliveries {
+            self.client_manager
+                .send_to_players(&player_ids, notification)
+                .await;
+        }
+        expired
+    }
+
+    /// Withdraws the draw offer standing in `game_id` if it has gone
+    /// unanswered too long, returning the updates to send if it did.
+    async fn expired_offer_deliveries(
+        &self,
+        game_id: &str,
+        game_manager: &mut GameManager,
+        player_manager: &PlayerManager,
+    ) -> Vec<(Vec<String>, Message)> {
+        let Some(offer) = game_manager.expire_draw_offer(game_id) else {
+            return Vec::new();
+        };
+
+        let mut deliveries = vec![Self::draw_offer_update(
+            game_id,
+            &offer,
+            DrawOfferStatus::Expired,
+            game_manager,
+        )];
+        deliveries.extend(
+            self.draw_offer_changed_deliveries(game_id, game_manager, player_manager)
+                .await,
+        );
+        deliveries
+    }
+
+    /// Wakes up when the side to move in `game_id` would run out of time,
+    /// so the game ends on time even if nobody moves. Moves made meanwhile
+    /// schedule their own checks, leaving this one with nothing to do.
+    fn schedule_flag_check(&self, game_id: &str, game_manager: &GameManager) {
+        let Some(time_to_flag_ms) = game_manager.time_to_flag_ms(game_id) else {
+            return;
+        };
+
+        tokio::spawn({
+            let handler = self.clone();
+            let game_id = game_id.to_string();
+            async move {
+                tokio::time::sleep(Duration::from_millis(time_to_flag_ms)).await;
+                handler.flag_game(&game_id).await;
+            }
+        });
+    }
+
+    /// Ends `game_id` on time if the side to move has run out, and sends its
+    /// players the result. Returns whether the flag fell.
+    async fn flag_game(&self, game_id: &str) -> bool {
+        let mut game_manager = self.game_manager.write().await;
+        if !game_manager.check_flag(game_id) {
+            return false;
+        }
+
+        let player_manager = self.player_manager.read().await;
+        let deliveries = self
+            .game_over_deliveries(game_id, &mut game_manager, &player_manager)
+            .await;
+
+        drop(player_manager);
+        drop(game_manager);
+
+        for (player_ids, notification) in deliveries {
+            self.client_manager
+                .send_to_players(&player_ids, notification)
+                .await;
+        }
+        true
+    }
+
+    /// Settles `game_id` once it is over: ends the partner board if it is
+    /// part of a Bughouse match and stores it if it is played by
+    /// correspondence. Returns the updates its players are to be sent.
+    async fn game_over_deliveries(
+        &self,
+        game_id: &str,
+        game_manager: &mut GameManager,
+        player_manager: &PlayerManager,
+    ) -> Vec<(Vec<String>, Message)> {
+        if let Some(table) = game_manager.get_table(game_id) {
+            return Self::table_update_deliveries(table);
+        }
+
+        let bughouse_manager = self.bughouse_manager.read().await;
+
+        let mut deliveries = Vec::new();
+        match bughouse_manager.match_for_game(game_id) {
+            // One Bughouse board ending ends the match
+            Some(bughouse) => {
+                if let Err(e) = bughouse_manager.sync_result(game_manager, game_id) {
+                    eprintln!("Failed to end partner board of {}: {}", game_id, e);
+                }
+                let player_ids = bughouse.players(game_manager);
+                for board in &bughouse.boards {
+                    if let Some(game) = game_manager.get_game(board) {
+                        let notification = self
+                            .game_update_notification(game, Viewer::Spectator, None, player_manager)
+                            .await;
+                        deliveries.push((player_ids.clone(), notification));
+                    }
+                }
+            }
+            None => {
+                if let Some(game) = game_manager.get_game(game_id) {
+                    self.save_correspondence_game(game, player_manager).await;
+                    let seats = [
+                        (&game.white_player, Color::White),
+                        (&game.black_player, Color::Black),
+                    ];
+                    for (player_id, color) in seats {
+                        let Some(player_id) = player_id else {
+                            continue;
+                        };
+                        let notification = self
+                            .game_update_notification(
+                                game,
+                                Viewer::Player(color),
+                                None,
+                                player_manager,
+                            )
+                            .await;
+                        deliveries.push((vec![player_id.clone()], notification));
+                    }
+                }
+            }
+        }
+        deliveries
+    }
+
+    /// A `TurnBasedUpdate` for everyone at `table`, each with their own view
+    /// in case the game hides anything.
+    fn table_update_deliveries(table: &Table) -> Vec<(Vec<String>, Message)> {
+        table
+            .watcher_ids()
+            .into_iter()
+            .map(|player_id| {
+                let notification = Message::notification(MessageType::TurnBasedUpdate(
+                    TurnBasedUpdateNotification {
+                        game_id: table.id.clone(),
+                        game_state: TurnBasedSnapshot::new(table, &player_id),
+                    },
+                ));
+                (vec![player_id], notification)
+            })
+            .collect()
+    }
+
+    /// Sends `deliveries` without holding up the handler, which may still
+    /// hold the managers' locks.
+    fn spawn_deliveries(&self, deliveries: Vec<(Vec<String>, Message)>) {
+        tokio::spawn({
+            let client_manager = Arc::clone(&self.client_manager);
+            async move {
+                for (player_ids, notification) in deliveries {
+                    client_manager
+                        .send_to_players(&player_ids, notification)
+                        .await;
+                }
+            }
+        });
+    }
+
+    /// Stores `game`, if it is played by correspondence, so it outlasts a
+    /// restart, and keeps its deadline on schedule.
+    async fn save_correspondence_game(&self, game: &GameState, player_manager: &PlayerManager) {
+        if game.correspondence.is_none() {
+            return;
+        }
+
+        let name = |player_id: &Option<String>| {
+            player_id
+                .as_ref()
+                .and_then(|id| player_manager.get_player(id))
+                .map(|player| player.name.clone())
+        };
+        let (white_name, black_name) = (name(&game.white_player), name(&game.black_player));
+        if let Err(e) = self
+            .correspondence_manager
+            .write()
+            .await
+            .save(game, white_name, black_name)
+        {
+            eprintln!("Failed to store correspondence game {}: {}", game.id, e);
+        }
+    }
+
+    /// A `YourMove` for the player to move in `game`, if it is an ongoing
+    /// correspondence game, along with that player's ID.
+    fn your_move_notification(
+        &self,
+        game: &GameState,
+        player_manager: &PlayerManager,
+    ) -> Option<(String, Message)> {
+        let correspondence = game.correspondence?;
+        if game.result != GameResult::Ongoing {
+            return None;
+        }
+
+        let to_move = game.board.get_to_move();
+        let (player_id, opponent_id) = match to_move {
+            Color::White => (game.white_player.as_ref()?, game.black_player.as_ref()),
+            Color::Black => (game.black_player.as_ref()?, game.white_player.as_ref()),
+        };
+        let opponent = opponent_id
+            .and_then(|id| player_manager.get_player(id))
+            .map(|player| player.get_display_info());
+
+        let notification = Message::notification(MessageType::YourMove(YourMoveNotification {
+            game_id: game.id.clone(),
+            opponent,
+            move_count: game.move_history.len() as u32,
+            last_move: game.last_move_for(Viewer::Player(to_move)),
+            deadline: correspondence.deadline,
+        }));
+        Some((player_id.clone(), notification))
+    }
+
+    /// Sends a `YourMove`, or holds it until `player_id` connects again.
+    async fn send_your_move(&self, player_id: &str, notification: Message) {
+        let MessageType::YourMove(your_move) = &notification.message_type else {
+            return;
+        };
+        let game_id = your_move.game_id.clone();
+
+        if self
+            .client_manager
+            .send_to_player(player_id, notification)
+            .await
+            .is_err()
+            && let Err(e) = self
+                .correspondence_manager
+                .write()
+                .await
+                .queue_your_move(player_id, &game_id)
+        {
+            eprintln!("Failed to queue move notice for {}: {}", player_id, e);
+        }
+    }
+
+    /// Sends `player_id` the moves that came in while they were away, for
+    /// the games still waiting on them.
+    fn deliver_queued_moves(&self, player_id: &str) {
+        tokio::spawn({
+            let handler = self.clone();
+            let player_id = player_id.to_string();
+            async move {
+                let game_ids = match handler
+                    .correspondence_manager
+                    .write()
+                    .await
+                    .take_inbox(&player_id)
+                {
+                    Ok(game_ids) => game_ids,
+                    Err(e) => {
+                        eprintln!("Failed to read move notices for {}: {}", player_id, e);
+                        return;
+                    }
+                };
+
+                let notifications = {
+                    let game_manager = handler.game_manager.read().await;
+                    let player_manager = handler.player_manager.read().await;
+                    game_ids
+                        .iter()
+                        .filter_map(|game_id| game_manager.get_game(game_id))
+                        .filter_map(|game| handler.your_move_notification(game, &player_manager))
+                        .filter(|(to_move, _)| *to_move == player_id)
+                        .collect::<Vec<_>>()
+                };
+                for (_, notification) in notifications {
+                    handler.send_your_move(&player_id, notification).await;
+                }
+            }
+        });
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::game::{DrawOffers, SeatPreference};
     use crate::utils::ServerConfig;
 
     #[tokio::test]
@@ -1052,4 +2593,268 @@ mod tests {
         assert_eq!(info.server_name, "Chess Server");
         assert!(info.features.contains(&"multiplayer".to_string()));
     }
+
+    #[tokio::test]
+    async fn test_bughouse_resignation_reaches_all_players() {
+        let handler = ChessServer::new(ServerConfig::test()).message_handler();
+        let mut game_manager = handler.game_manager.write().await;
+        let player_manager = handler.player_manager.read().await;
+        let mut bughouse_manager = handler.bughouse_manager.write().await;
+
+        let match_id = bughouse_manager.create_match(&mut game_manager).unwrap();
+        let players = ["p1", "p2", "p3", "p4"].map(String::from);
+        let mut boards = Vec::new();
+        for player_id in &players {
+            let (game_id, _) = bughouse_manager
+                .join_match(
+                    &mut game_manager,
+                    &match_id,
+                    player_id.clone(),
+                    SeatPreference::default(),
+                )
+                .unwrap();
+            boards.push(game_id);
+        }
+        drop(bughouse_manager);
+
+        game_manager.resign(&boards[0], &players[0]).unwrap();
+        let deliveries = handler
+            .game_over_deliveries(&boards[0], &mut game_manager, &player_manager)
+            .await;
+
+        // Both boards are over, and all four players see each
+        for board in &boards {
+            assert_ne!(
+                game_manager.get_game(board).unwrap().result,
+                GameResult::Ongoing
+            );
+        }
+        assert_eq!(deliveries.len(), 2);
+        for (player_ids, _) in &deliveries {
+            let mut player_ids = player_ids.clone();
+            player_ids.sort();
+            assert_eq!(player_ids, players);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_fog_of_war_pgn_withheld() {
+        let handler = ChessServer::new(ServerConfig::test()).message_handler();
+        let (game_id, white, black) = {
+            let mut game_manager = handler.game_manager.write().await;
+            let game_id = game_manager
+                .create_game_with_variant(Variant::FogOfWar, None)
+                .unwrap();
+            for (player_id, color) in [("white", Color::White), ("black", Color::Black)] {
+                game_manager
+                    .join_game(&game_id, player_id.to_string(), Some(color))
+                    .unwrap();
+            }
+            play(&mut game_manager, &game_id, "white", "e2e4");
+            (game_id, "white".to_string(), "black".to_string())
+        };
+        let get_pgn = |player_id: &str| {
+            let req = GetPgnRequest {
+                game_id: game_id.clone(),
+            };
+            let session = Session::new(player_id.to_string(), "127.0.0.1".to_string(), None);
+            handler.handle_get_pgn(req, Some(session), None)
+        };
+
+        // Black may not read White's hidden move from the PGN
+        let response = get_pgn(&black).await.unwrap();
+        assert!(matches!(response.message_type, MessageType::Error(_)));
+        let req = GetPgnRequest {
+            game_id: game_id.clone(),
+        };
+        let response = handler.handle_get_pgn(req, None, None).await.unwrap();
+        assert!(matches!(response.message_type, MessageType::Error(_)));
+
+        // The fog lifts once the game is over
+        handler
+            .game_manager
+            .write()
+            .await
+            .resign(&game_id, &white)
+            .unwrap();
+        let response = get_pgn(&black).await.unwrap();
+        let MessageType::GetPgnResponse(response) = response.message_type else {
+            panic!("expected a PGN");
+        };
+        assert!(response.pgn.contains("1. e4"));
+    }
+
+    type DrawOfferUpdate = (Vec<String>, Color, DrawOfferStatus);
+
+    /// The `DrawOfferUpdate`s among `deliveries`: who each goes to, whose
+    /// offer it is about and what became of it.
+    fn draw_offer_updates(deliveries: &[(Vec<String>, Message)]) -> Vec<DrawOfferUpdate> {
+        deliveries
+            .iter()
+            .filter_map(|(player_ids, message)| match &message.message_type {
+                MessageType::DrawOfferUpdate(update) => {
+                    Some((player_ids.clone(), update.offered_by, update.status))
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// A chess game between two new players of the names given, with the
+    /// players' IDs.
+    async fn seated_game(
+        handler: &ServerMessageHandler,
+        [white, black]: [&str; 2],
+    ) -> (String, String, String) {
+        let mut player_manager = handler.player_manager.write().await;
+        let white = player_manager.register_player(white.to_string()).unwrap();
+        let black = player_manager.register_player(black.to_string()).unwrap();
+
+        let mut game_manager = handler.game_manager.write().await;
+        let game_id = game_manager.create_game();
+        for (player_id, color) in [(&white, Color::White), (&black, Color::Black)] {
+            game_manager
+                .join_game(&game_id, player_id.clone(), Some(color))
+                .unwrap();
+        }
+        (game_id, white, black)
+    }
+
+    fn play(game_manager: &mut GameManager, game_id: &str, player_id: &str, uci: &str) {
+        let chess_move = Move::from_algebraic(uci).unwrap();
+        game_manager
+            .make_move(game_id, player_id, chess_move)
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_draw_offer_updates() {
+        let handler = ChessServer::new(ServerConfig::test()).message_handler();
+        let (game_id, white, black) = seated_game(&handler, ["white", "black"]).await;
+        let mut game_manager = handler.game_manager.write().await;
+        let player_manager = handler.player_manager.read().await;
+        let both = vec![white.clone(), black.clone()];
+
+        let (drawn, deliveries) = handler
+            .offer_draw_deliveries(&game_id, &white, None, &mut game_manager, &player_manager)
+            .await
+            .unwrap();
+        assert!(!drawn);
+        assert_eq!(
+            draw_offer_updates(&deliveries),
+            [(both.clone(), Color::White, DrawOfferStatus::Offered)]
+        );
+
+        let deliveries = handler
+            .respond_to_draw_deliveries(&game_id, &black, false, &mut game_manager, &player_manager)
+            .await
+            .unwrap();
+        assert_eq!(
+            draw_offer_updates(&deliveries),
+            [(both.clone(), Color::White, DrawOfferStatus::Declined)]
+        );
+
+        // An offer is withdrawn by its maker's next move, not the opponent's
+        handler
+            .offer_draw_deliveries(&game_id, &black, None, &mut game_manager, &player_manager)
+            .await
+            .unwrap();
+        let standing = game_manager.pending_draw_offer(&game_id).cloned();
+        play(&mut game_manager, &game_id, &white, "e2e4");
+        assert!(
+            ServerMessageHandler::cancelled_offer_update(&game_id, standing.clone(), &game_manager)
+                .is_none()
+        );
+        play(&mut game_manager, &game_id, &black, "e7e5");
+        let cancelled =
+            ServerMessageHandler::cancelled_offer_update(&game_id, standing, &game_manager);
+        assert_eq!(
+            draw_offer_updates(&Vec::from_iter(cancelled)),
+            [(both.clone(), Color::Black, DrawOfferStatus::Cancelled)]
+        );
+
+        // An offer left unanswered lapses
+        let game = game_manager.get_game_mut(&game_id).unwrap();
+        game.draw_offers = DrawOffers::new();
+        game.offer_draw(&white, None).unwrap();
+        let deliveries = handler
+            .expired_offer_deliveries(&game_id, &mut game_manager, &player_manager)
+            .await;
+        assert!(deliveries.is_empty());
+
+        let game = game_manager.get_game_mut(&game_id).unwrap();
+        game.draw_offers.pending.as_mut().unwrap().made_at = 0;
+        let deliveries = handler
+            .expired_offer_deliveries(&game_id, &mut game_manager, &player_manager)
+            .await;
+        assert_eq!(
+            draw_offer_updates(&deliveries),
+            [(both, Color::White, DrawOfferStatus::Expired)]
+        );
+        assert_eq!(game_manager.pending_draw_offer(&game_id), None);
+    }
+
+    #[tokio::test]
+    async fn test_accepted_draw_updates() {
+        let handler = ChessServer::new(ServerConfig::test()).message_handler();
+
+        // Accepted in answer
+        let (game_id, white, black) = seated_game(&handler, ["a1", "b1"]).await;
+        let mut game_manager = handler.game_manager.write().await;
+        let player_manager = handler.player_manager.read().await;
+        handler
+            .offer_draw_deliveries(&game_id, &white, None, &mut game_manager, &player_manager)
+            .await
+            .unwrap();
+        let deliveries = handler
+            .respond_to_draw_deliveries(&game_id, &black, true, &mut game_manager, &player_manager)
+            .await
+            .unwrap();
+        assert_eq!(
+            draw_offer_updates(&deliveries),
+            [(vec![white, black], Color::White, DrawOfferStatus::Accepted)]
+        );
+        drop(player_manager);
+        drop(game_manager);
+
+        // Accepted by offering back, which the first offerer hears of too
+        let (game_id, white, black) = seated_game(&handler, ["a2", "b2"]).await;
+        let mut game_manager = handler.game_manager.write().await;
+        let player_manager = handler.player_manager.read().await;
+        handler
+            .offer_draw_deliveries(&game_id, &white, None, &mut game_manager, &player_manager)
+            .await
+            .unwrap();
+        let (drawn, deliveries) = handler
+            .offer_draw_deliveries(&game_id, &black, None, &mut game_manager, &player_manager)
+            .await
+            .unwrap();
+        assert!(drawn);
+        assert_eq!(
+            draw_offer_updates(&deliveries),
+            [(vec![white, black], Color::White, DrawOfferStatus::Accepted)]
+        );
+        drop(player_manager);
+        drop(game_manager);
+
+        // Accepted at once by an opponent who takes every draw
+        let (game_id, white, black) = seated_game(&handler, ["a3", "b3"]).await;
+        let mut game_manager = handler.game_manager.write().await;
+        let mut player_manager = handler.player_manager.write().await;
+        let opponent = player_manager.get_player_mut(&black).unwrap();
+        opponent.preferences.auto_accept_draws = true;
+        let (drawn, deliveries) = handler
+            .offer_draw_deliveries(&game_id, &white, None, &mut game_manager, &player_manager)
+            .await
+            .unwrap();
+        assert!(drawn);
+        let both = vec![white, black];
+        assert_eq!(
+            draw_offer_updates(&deliveries),
+            [
+                (both.clone(), Color::White, DrawOfferStatus::Offered),
+                (both, Color::White, DrawOfferStatus::Accepted),
+            ]
+        );
+    }
 }
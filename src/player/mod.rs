@@ -4,6 +4,7 @@ pub mod session;
 pub use player::*;
 pub use session::*;
 
+use crate::game::{GameState, PgnTags};
 use crate::utils::{ChessResult, ChessServerError};
 use std::collections::HashMap;
 
@@ -40,6 +41,24 @@ impl PlayerManager {
         Ok(player_id)
     }
 
+    /// Registers `name` under an ID it was given before, as when games
+    /// stored with it are read back after a restart. Returns the ID the
+    /// name now has, which is the existing one if it is already registered.
+    pub fn restore_player(&mut self, player_id: &str, name: &str) -> ChessResult<String> {
+        if let Some(existing_id) = self.get_player_id_by_name(name) {
+            return Ok(existing_id);
+        }
+
+        let mut player = Player::new(name.to_string())?;
+        player.id = player_id.to_string();
+        player.set_status(PlayerStatus::Offline);
+
+        self.name_to_id
+            .insert(player.name.clone(), player_id.to_string());
+        self.players.insert(player_id.to_string(), player);
+        Ok(player_id.to_string())
+    }
+
     pub fn get_player(&self, player_id: &str) -> Option<&Player> {
         self.players.get(player_id)
     }
@@ -139,6 +158,32 @@ impl PlayerManager {
         Ok(())
     }
 
+    /// Records a ping round trip to `player_id`'s client.
+    pub fn record_ping(&mut self, player_id: &str, rtt_ms: u64) -> ChessResult<()> {
+        let player =
+            self.players
+                .get_mut(player_id)
+                .ok_or_else(|| ChessServerError::PlayerNotFound {
+                    player_id: player_id.to_string(),
+                })?;
+
+        player.lag.record_rtt(rtt_ms);
+        Ok(())
+    }
+
+    /// Records the lag given back to `player_id` for a move.
+    pub fn record_lag_credit(&mut self, player_id: &str, credit_ms: u64) -> ChessResult<()> {
+        let player =
+            self.players
+                .get_mut(player_id)
+                .ok_or_else(|| ChessServerError::PlayerNotFound {
+                    player_id: player_id.to_string(),
+                })?;
+
+        player.lag.record_credit(credit_ms);
+        Ok(())
+    }
+
     pub fn update_player_rating(&mut self, player_id: &str, new_rating: u32) -> ChessResult<()> {
         let player =
             self.players
@@ -312,6 +357,23 @@ impl PlayerManager {
             current_games: player.current_games.clone(),
         })
     }
+
+    /// PGN tags for `game` with player ids resolved to display names.
+    pub fn pgn_tags(&self, game: &GameState) -> PgnTags {
+        let display_name = |player_id: &Option<String>| {
+            player_id
+                .as_deref()
+                .and_then(|id| self.get_player(id))
+                .map(|player| player.name.clone())
+                .unwrap_or_else(|| "?".to_string())
+        };
+
+        PgnTags {
+            white: display_name(&game.white_player),
+            black: display_name(&game.black_player),
+            ..PgnTags::for_game(game)
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -358,6 +420,24 @@ mod tests {
         assert!(manager.register_player("TestPlayer".to_string()).is_err());
     }
 
+    #[test]
+    fn test_pgn_tags_use_display_names() {
+        let mut manager = PlayerManager::new(3600);
+        let alice_id = manager.register_player("Alice".to_string()).unwrap();
+
+        let mut game = GameState::new();
+        game.set_clock(crate::game::TimeControl::new(300, 2).clock());
+        game.add_player(alice_id, Some(crate::game::Color::White))
+            .unwrap();
+        game.add_player("unregistered".to_string(), Some(crate::game::Color::Black))
+            .unwrap();
+
+        let pgn = game.to_pgn_with_tags(&manager.pgn_tags(&game));
+        assert!(pgn.contains("[White \"Alice\"]"));
+        assert!(pgn.contains("[Black \"?\"]"));
+        assert!(pgn.contains("[TimeControl \"300+2\"]"));
+    }
+
     #[test]
     fn test_player_search() {
         let mut manager = PlayerManager::new(3600);
@@ -1,3 +1,5 @@
+use crate::game::LagStats;
+pub use crate::game::TimeControl;
 use crate::utils::{ChessResult, ChessServerError, current_timestamp, generate_id};
 use serde::{Deserialize, Serialize};
 
@@ -123,6 +125,9 @@ pub struct Player {
     pub current_games: Vec<String>,
     pub preferences: PlayerPreferences,
     pub connection_info: Option<ConnectionInfo>,
+    /// Network lag measured with pings, and given back on the clock.
+    #[serde(default)]
+    pub lag: LagStats,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -137,13 +142,6 @@ pub struct PlayerPreferences {
     pub auto_promote_to_queen: bool,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct TimeControl {
-    pub initial_time_secs: u32,
-    pub increment_secs: u32,
-    pub name: String,
-}
-
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ConnectionInfo {
     pub ip_address: String,
@@ -193,6 +191,7 @@ impl Player {
             current_games: Vec::new(),
             preferences: PlayerPreferences::default(),
             connection_info: None,
+            lag: LagStats::default(),
         })
     }
 
@@ -0,0 +1,199 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::{SeatedGame, TurnBasedGame};
+use crate::game::{CHESS_KIND, Color, DrawOffer, GameResult, GameState, Move, Viewer};
+
+/// Chess as a `TurnBasedGame`, the reference implementation, with
+/// `GameState` as its state. Seat 0 plays White. Moves are sent as SAN or
+/// UCI strings, and views are the FEN of the position each seat may see.
+///
+/// The registry hosts chess on `GameState`s themselves, which carry the
+/// variants, clocks and the rest of the chess protocol; their `SeatedGame`
+/// moves are read by these rules.
+pub struct Chess;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChessView {
+    pub board_fen: String,
+    pub move_history: Vec<String>,
+    pub to_move: Color,
+    pub is_check: bool,
+}
+
+impl Chess {
+    /// Reads a SAN or UCI move under the game's rules, matching UCI against
+    /// the legal moves so the castling and en passant flags are filled in.
+    fn parse_move(state: &GameState, notation: &str) -> Result<Move, String> {
+        let rules = state.ruleset();
+        if let Ok(chess_move) = Move::from_san_with(notation, &state.board, rules) {
+            return Ok(chess_move);
+        }
+
+        let submitted = Move::from_algebraic(notation)
+            .ok_or_else(|| format!("Cannot read move '{}'", notation))?;
+        Ok(rules
+            .legal_moves(&state.board)
+            .into_iter()
+            .find(|m| {
+                m.from == submitted.from
+                    && m.to == submitted.to
+                    && m.promotion == submitted.promotion
+                    && m.drop == submitted.drop
+            })
+            .unwrap_or(submitted))
+    }
+}
+
+impl TurnBasedGame for Chess {
+    type Move = String;
+    type State = GameState;
+    type View = ChessView;
+    type Outcome = GameResult;
+
+    fn kind(&self) -> &'static str {
+        CHESS_KIND
+    }
+
+    fn new_state(&self) -> GameState {
+        GameState::new()
+    }
+
+    fn to_move(&self, state: &GameState) -> usize {
+        state.board.get_to_move().index()
+    }
+
+    fn legal_moves(&self, state: &GameState) -> Vec<String> {
+        state
+            .get_legal_moves()
+            .iter()
+            .map(Move::to_algebraic)
+            .collect()
+    }
+
+    fn apply_move(&self, state: &mut GameState, notation: String) -> Result<(), String> {
+        let chess_move = Self::parse_move(state, &notation)?;
+        state.apply_move(chess_move)
+    }
+
+    fn view(&self, state: &GameState, seat: Option<usize>) -> ChessView {
+        let viewer = match seat {
+            Some(0) => Viewer::Player(Color::White),
+            Some(_) => Viewer::Player(Color::Black),
+            None => Viewer::Spectator,
+        };
+
+        ChessView {
+            board_fen: state.board_for(viewer).to_fen(),
+            move_history: state
+                .moves_for(viewer)
+                .iter()
+                .map(Move::to_algebraic)
+                .collect(),
+            to_move: state.board.get_to_move(),
+            is_check: state.is_in_check(),
+        }
+    }
+
+    fn outcome(&self, state: &GameState) -> Option<GameResult> {
+        (state.result != GameResult::Ongoing).then(|| state.result.clone())
+    }
+}
+
+impl SeatedGame for GameState {
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn kind(&self) -> &'static str {
+        CHESS_KIND
+    }
+
+    fn is_over(&self) -> bool {
+        self.result != GameResult::Ongoing
+    }
+
+    fn player_id(&self, color: Color) -> Option<&String> {
+        GameState::player_id(self, color)
+    }
+
+    fn play(&mut self, player_id: &str, payload: Value, lag_ms: u64) -> Result<(), String> {
+        let notation: String = serde_json::from_value(payload).map_err(|e| e.to_string())?;
+        let chess_move = Chess::parse_move(self, &notation)?;
+        self.make_move_with_lag(player_id, chess_move, lag_ms)
+    }
+
+    fn resign(&mut self, player_id: &str) -> Result<(), String> {
+        GameState::resign(self, player_id)
+    }
+
+    fn offer_draw(&mut self, player_id: &str, message: Option<String>) -> Result<bool, String> {
+        GameState::offer_draw(self, player_id, message)
+    }
+
+    fn respond_to_draw(&mut self, player_id: &str, accept: bool) -> Result<DrawOffer, String> {
+        GameState::respond_to_draw(self, player_id, accept)
+    }
+
+    fn pending_draw_offer(&self) -> Option<&DrawOffer> {
+        GameState::pending_draw_offer(self)
+    }
+
+    fn expire_draw_offer(&mut self) -> Option<DrawOffer> {
+        GameState::expire_draw_offer(self)
+    }
+
+    fn time_to_draw_offer_expiry_ms(&self) -> Option<u64> {
+        GameState::time_to_draw_offer_expiry_ms(self)
+    }
+
+    fn check_flag(&mut self) -> bool {
+        GameState::check_flag(self)
+    }
+
+    fn time_to_flag_ms(&self) -> Option<u64> {
+        GameState::time_to_flag_ms(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_san_and_uci_moves() {
+        let mut state = Chess.new_state();
+        for notation in ["e4", "e7e5", "Nf3", "b8c6", "Bc4", "g8f6", "O-O"] {
+            Chess.apply_move(&mut state, notation.to_string()).unwrap();
+        }
+
+        assert!(state.get_last_move().unwrap().is_castle);
+        assert_eq!(Chess.to_move(&state), 1);
+        assert!(Chess.apply_move(&mut state, "e5e4".to_string()).is_err());
+
+        let view = Chess.view(&state, Some(0));
+        assert_eq!(view.move_history.len(), 7);
+        assert_eq!(view.to_move, Color::Black);
+        assert_eq!(Chess.outcome(&state), None);
+    }
+
+    #[test]
+    fn test_seated_moves() {
+        let mut game = GameState::new();
+        game.add_player("white".to_string(), Some(Color::White))
+            .unwrap();
+        game.add_player("black".to_string(), Some(Color::Black))
+            .unwrap();
+
+        // Moves are checked against the seat to move, as `MakeMove`s are
+        assert!(game.play("black", json!("e5"), 0).is_err());
+        game.play("white", json!("e2e4"), 0).unwrap();
+        game.play("black", json!("e5"), 0).unwrap();
+        assert!(game.play("white", json!(4), 0).is_err());
+        assert_eq!(game.move_history.len(), 2);
+
+        SeatedGame::resign(&mut game, "white").unwrap();
+        assert!(SeatedGame::is_over(&game));
+    }
+}
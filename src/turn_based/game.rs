@@ -0,0 +1,163 @@
+use std::any::Any;
+use std::sync::Arc;
+
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+
+use crate::game::{Color, DrawOffer};
+
+/// The rules of a game the server can host: the moves players send, the
+/// state they act on, what each seat is shown and how the game ends.
+///
+/// Implementations are stateless rule sets, like chess's `Ruleset`; all
+/// game data lives in `State`. Register one with a `GameRegistry` and the
+/// network layer can host it, exchanging moves, views and outcomes as JSON.
+pub trait TurnBasedGame: Send + Sync + 'static {
+    type Move: Serialize + DeserializeOwned;
+    type State: Send + Sync;
+    type View: Serialize;
+    type Outcome: Serialize;
+
+    /// The name clients pick this game by.
+    fn kind(&self) -> &'static str;
+
+    /// How many players sit at a game, numbered from 0.
+    fn seats(&self) -> usize {
+        2
+    }
+
+    fn new_state(&self) -> Self::State;
+
+    /// The seat whose turn it is.
+    fn to_move(&self, state: &Self::State) -> usize;
+
+    fn legal_moves(&self, state: &Self::State) -> Vec<Self::Move>;
+
+    /// Plays `game_move` for the seat to move, rejecting it if illegal.
+    fn apply_move(&self, state: &mut Self::State, game_move: Self::Move) -> Result<(), String>;
+
+    /// What `seat` sees of the game, or spectators when `None`.
+    fn view(&self, state: &Self::State, seat: Option<usize>) -> Self::View;
+
+    /// How the game ended, or `None` while it goes on.
+    fn outcome(&self, state: &Self::State) -> Option<Self::Outcome>;
+}
+
+/// A game of any kind in progress, with moves, views and outcomes as JSON so
+/// that games of different kinds can be hosted side by side.
+pub trait HostedGame: Send + Sync {
+    fn kind(&self) -> &'static str;
+
+    fn seats(&self) -> usize;
+
+    fn to_move(&self) -> usize;
+
+    fn legal_moves(&self) -> Vec<Value>;
+
+    fn apply_move(&mut self, payload: Value) -> Result<(), String>;
+
+    fn view(&self, seat: Option<usize>) -> Value;
+
+    fn outcome(&self) -> Option<Value>;
+
+    fn is_over(&self) -> bool {
+        self.outcome().is_some()
+    }
+}
+
+/// A game of any kind as `GameManager` hosts it: the board together with
+/// the players seated at it, their clock and their draw offers. Chess games
+/// are `GameState`s and games of other kinds are `Table`s; both are created
+/// through a `GameRegistry`. Seat 0 is White wherever colors are shared.
+pub trait SeatedGame: Send + Sync + Any {
+    fn id(&self) -> &str;
+
+    fn kind(&self) -> &'static str;
+
+    fn is_over(&self) -> bool;
+
+    /// The player in `color`'s seat.
+    fn player_id(&self, color: Color) -> Option<&String>;
+
+    /// Plays a move sent as JSON in the game's own move format for
+    /// `player_id`, giving back up to `lag_ms` of network lag on the clock.
+    fn play(&mut self, player_id: &str, payload: Value, lag_ms: u64) -> Result<(), String>;
+
+    fn resign(&mut self, player_id: &str) -> Result<(), String>;
+
+    /// Offers the opponent a draw, returning `true` if it answered their
+    /// own offer and drew the game.
+    fn offer_draw(&mut self, player_id: &str, message: Option<String>) -> Result<bool, String>;
+
+    /// Accepts or declines the draw offered to `player_id`, returning the
+    /// offer answered.
+    fn respond_to_draw(&mut self, player_id: &str, accept: bool) -> Result<DrawOffer, String>;
+
+    fn pending_draw_offer(&self) -> Option<&DrawOffer>;
+
+    /// Withdraws the draw offer standing if it has gone unanswered too
+    /// long, returning it.
+    fn expire_draw_offer(&mut self) -> Option<DrawOffer>;
+
+    fn time_to_draw_offer_expiry_ms(&self) -> Option<u64>;
+
+    /// Ends the game on time if the side to move has run out, returning
+    /// whether it did.
+    fn check_flag(&mut self) -> bool;
+
+    fn time_to_flag_ms(&self) -> Option<u64>;
+}
+
+/// A `TurnBasedGame` together with the state of one game of it.
+pub struct Hosted<G: TurnBasedGame> {
+    game: Arc<G>,
+    state: G::State,
+}
+
+impl<G: TurnBasedGame> Hosted<G> {
+    pub fn new(game: Arc<G>) -> Self {
+        let state = game.new_state();
+        Self { game, state }
+    }
+
+    pub fn state(&self) -> &G::State {
+        &self.state
+    }
+}
+
+impl<G: TurnBasedGame> HostedGame for Hosted<G> {
+    fn kind(&self) -> &'static str {
+        self.game.kind()
+    }
+
+    fn seats(&self) -> usize {
+        self.game.seats()
+    }
+
+    fn to_move(&self) -> usize {
+        self.game.to_move(&self.state)
+    }
+
+    fn legal_moves(&self) -> Vec<Value> {
+        self.game
+            .legal_moves(&self.state)
+            .iter()
+            .filter_map(|m| serde_json::to_value(m).ok())
+            .collect()
+    }
+
+    fn apply_move(&mut self, payload: Value) -> Result<(), String> {
+        let game_move = serde_json::from_value(payload).map_err(|e| e.to_string())?;
+        self.game.apply_move(&mut self.state, game_move)
+    }
+
+    fn view(&self, seat: Option<usize>) -> Value {
+        serde_json::to_value(self.game.view(&self.state, seat)).unwrap_or(Value::Null)
+    }
+
+    fn outcome(&self) -> Option<Value> {
+        let outcome = self.game.outcome(&self.state)?;
+        serde_json::to_value(outcome).ok()
+    }
+}
@@ -0,0 +1,15 @@
+pub mod chess;
+pub mod game;
+pub mod registry;
+pub mod shogi;
+pub mod table;
+pub mod tic_tac_toe;
+pub mod xiangqi;
+
+pub use chess::*;
+pub use game::*;
+pub use registry::*;
+pub use shogi::Shogi;
+pub use table::*;
+pub use tic_tac_toe::*;
+pub use xiangqi::Xiangqi;
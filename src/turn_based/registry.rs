@@ -0,0 +1,82 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use super::{Chess, Hosted, SeatedGame, Shogi, Table, TicTacToe, TurnBasedGame, Xiangqi};
+use crate::game::GameState;
+
+type Factory = Box<dyn Fn() -> Box<dyn SeatedGame> + Send + Sync>;
+
+/// The kinds of game the server hosts, by name, each with how a new game of
+/// it is set up for `GameManager`.
+#[derive(Default)]
+pub struct GameRegistry {
+    kinds: HashMap<&'static str, Factory>,
+}
+
+impl GameRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A registry with every game this crate ships.
+    pub fn with_builtin_games() -> Self {
+        let mut registry = Self::new();
+        registry.register_chess();
+        registry.register(Shogi::default());
+        registry.register(Shogi::with_try_rule());
+        registry.register(TicTacToe);
+        registry.register(Xiangqi);
+        registry
+    }
+
+    /// Makes `game` available under its kind, played at a `Table`, replacing
+    /// any game already registered under the same name.
+    pub fn register<G: TurnBasedGame>(&mut self, game: G) {
+        let game = Arc::new(game);
+        self.kinds.insert(
+            game.kind(),
+            Box::new(move || Box::new(Table::new(Box::new(Hosted::new(Arc::clone(&game)))))),
+        );
+    }
+
+    /// Makes chess available. Its games are `GameState`s, which play by the
+    /// `Chess` rules and carry the rest of the chess protocol besides.
+    pub fn register_chess(&mut self) {
+        self.kinds
+            .insert(Chess.kind(), Box::new(|| Box::new(GameState::new())));
+    }
+
+    pub fn kinds(&self) -> Vec<&'static str> {
+        let mut kinds: Vec<_> = self.kinds.keys().copied().collect();
+        kinds.sort_unstable();
+        kinds
+    }
+
+    pub fn create(&self, kind: &str) -> Option<Box<dyn SeatedGame>> {
+        self.kinds.get(kind).map(|factory| factory())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::any::Any;
+
+    use super::*;
+
+    #[test]
+    fn test_registry() {
+        let registry = GameRegistry::with_builtin_games();
+        assert_eq!(
+            registry.kinds(),
+            ["chess", "shogi", "shogi-try", "tic-tac-toe", "xiangqi"]
+        );
+        assert!(registry.create("go").is_none());
+
+        // Chess is hosted like any other kind, on its own game state
+        let chess = registry.create("chess").unwrap();
+        assert_eq!(chess.kind(), "chess");
+        assert!((chess.as_ref() as &dyn Any).is::<GameState>());
+        let xiangqi = registry.create("xiangqi").unwrap();
+        assert!((xiangqi.as_ref() as &dyn Any).is::<Table>());
+    }
+}
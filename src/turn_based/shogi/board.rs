@@ -0,0 +1,604 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+/// The starting position in SFEN.
+pub const SHOGI_START: &str = "lnsgkgsnl/1r5b1/ppppppppp/9/9/9/PPPPPPPPP/1B5R1/LNSGKGSNL b - 1";
+
+/// The two players. Sente moves first, sits in seat 0 and is written in
+/// upper case in SFEN.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Side {
+    Sente,
+    Gote,
+}
+
+impl Side {
+    pub fn opposite(self) -> Self {
+        match self {
+            Side::Sente => Side::Gote,
+            Side::Gote => Side::Sente,
+        }
+    }
+
+    /// The seat this side plays from.
+    pub fn index(self) -> usize {
+        match self {
+            Side::Sente => 0,
+            Side::Gote => 1,
+        }
+    }
+
+    /// The rank step towards the opponent: Sente plays up the board towards
+    /// rank a.
+    pub fn forward(self) -> i8 {
+        match self {
+            Side::Sente => -1,
+            Side::Gote => 1,
+        }
+    }
+}
+
+/// A square of the 9x9 board. Files run 1-9 from Sente's right and ranks
+/// a-i from Gote's side, so the index follows SFEN order: rank a first,
+/// file 9 to file 1 within a rank.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Square(u8);
+
+impl Square {
+    pub fn new(file: u8, rank: u8) -> Option<Self> {
+        ((1..=9).contains(&file) && (1..=9).contains(&rank))
+            .then(|| Square((rank - 1) * 9 + (9 - file)))
+    }
+
+    pub fn from_index(index: usize) -> Option<Self> {
+        (index < 81).then_some(Square(index as u8))
+    }
+
+    pub fn all() -> impl Iterator<Item = Square> {
+        (0..81).map(Square)
+    }
+
+    pub fn index(self) -> usize {
+        self.0 as usize
+    }
+
+    pub fn file(self) -> u8 {
+        9 - self.0 % 9
+    }
+
+    /// 1 for rank a through 9 for rank i.
+    pub fn rank(self) -> u8 {
+        self.0 / 9 + 1
+    }
+
+    /// The square `files` and `ranks` away, if still on the board.
+    pub fn offset(self, files: i8, ranks: i8) -> Option<Self> {
+        let file = self.file() as i8 + files;
+        let rank = self.rank() as i8 + ranks;
+        Square::new(u8::try_from(file).ok()?, u8::try_from(rank).ok()?)
+    }
+
+    /// How many ranks into `side`'s advance this square is, 1 being its own
+    /// back rank and 9 the opponent's.
+    pub fn relative_rank(self, side: Side) -> u8 {
+        match side {
+            Side::Sente => 10 - self.rank(),
+            Side::Gote => self.rank(),
+        }
+    }
+
+    /// Whether this square is in the three ranks where `side` promotes.
+    pub fn in_promotion_zone(self, side: Side) -> bool {
+        self.relative_rank(side) >= 7
+    }
+
+    /// Reads USI notation, e.g. `7g`.
+    pub fn from_usi(s: &str) -> Option<Self> {
+        let mut chars = s.chars();
+        let file = chars.next()?.to_digit(10)?;
+        let rank = chars.next().filter(|c| ('a'..='i').contains(c))?;
+        if chars.next().is_some() {
+            return None;
+        }
+        Square::new(file as u8, rank as u8 - b'a' + 1)
+    }
+
+    pub fn to_usi(self) -> String {
+        format!("{}{}", self.file(), (b'a' + self.rank() - 1) as char)
+    }
+}
+
+impl fmt::Display for Square {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.to_usi())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum PieceKind {
+    Pawn,
+    Lance,
+    Knight,
+    Silver,
+    Gold,
+    Bishop,
+    Rook,
+    King,
+}
+
+impl PieceKind {
+    /// Kinds a hand can hold, in the order SFEN lists them.
+    pub const HAND: [PieceKind; 7] = [
+        PieceKind::Rook,
+        PieceKind::Bishop,
+        PieceKind::Gold,
+        PieceKind::Silver,
+        PieceKind::Knight,
+        PieceKind::Lance,
+        PieceKind::Pawn,
+    ];
+
+    fn hand_index(self) -> Option<usize> {
+        Self::HAND.iter().position(|&kind| kind == self)
+    }
+
+    pub fn can_promote(self) -> bool {
+        !matches!(self, PieceKind::Gold | PieceKind::King)
+    }
+
+    /// Rooks and bishops count 5 points towards an impasse declaration,
+    /// kings nothing and everything else 1.
+    pub fn impasse_points(self) -> u32 {
+        match self {
+            PieceKind::Rook | PieceKind::Bishop => 5,
+            PieceKind::King => 0,
+            _ => 1,
+        }
+    }
+
+    /// The last `n` ranks where an unpromoted piece of this kind would have
+    /// no moves, and so can neither be dropped nor left unpromoted.
+    pub fn dead_ranks(self) -> u8 {
+        match self {
+            PieceKind::Pawn | PieceKind::Lance => 1,
+            PieceKind::Knight => 2,
+            _ => 0,
+        }
+    }
+
+    pub fn to_sfen_char(self) -> char {
+        match self {
+            PieceKind::Pawn => 'p',
+            PieceKind::Lance => 'l',
+            PieceKind::Knight => 'n',
+            PieceKind::Silver => 's',
+            PieceKind::Gold => 'g',
+            PieceKind::Bishop => 'b',
+            PieceKind::Rook => 'r',
+            PieceKind::King => 'k',
+        }
+    }
+
+    pub fn from_sfen_char(c: char) -> Option<Self> {
+        match c.to_ascii_lowercase() {
+            'p' => Some(PieceKind::Pawn),
+            'l' => Some(PieceKind::Lance),
+            'n' => Some(PieceKind::Knight),
+            's' => Some(PieceKind::Silver),
+            'g' => Some(PieceKind::Gold),
+            'b' => Some(PieceKind::Bishop),
+            'r' => Some(PieceKind::Rook),
+            'k' => Some(PieceKind::King),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Piece {
+    pub side: Side,
+    pub kind: PieceKind,
+    pub promoted: bool,
+}
+
+impl Piece {
+    pub fn new(side: Side, kind: PieceKind) -> Self {
+        Self {
+            side,
+            kind,
+            promoted: false,
+        }
+    }
+
+    pub fn to_sfen(self) -> String {
+        let c = self.kind.to_sfen_char();
+        let c = match self.side {
+            Side::Sente => c.to_ascii_uppercase(),
+            Side::Gote => c,
+        };
+        match self.promoted {
+            true => format!("+{}", c),
+            false => c.to_string(),
+        }
+    }
+}
+
+/// Pieces each side has captured and may drop. Captured pieces lose their
+/// promotion.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Hands {
+    counts: [[u8; 7]; 2],
+}
+
+impl Hands {
+    pub fn count(&self, side: Side, kind: PieceKind) -> u8 {
+        match kind.hand_index() {
+            Some(i) => self.counts[side.index()][i],
+            None => 0,
+        }
+    }
+
+    pub fn add(&mut self, side: Side, kind: PieceKind) {
+        if let Some(i) = kind.hand_index() {
+            self.counts[side.index()][i] += 1;
+        }
+    }
+
+    pub fn take(&mut self, side: Side, kind: PieceKind) -> bool {
+        match kind.hand_index() {
+            Some(i) if self.counts[side.index()][i] > 0 => {
+                self.counts[side.index()][i] -= 1;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// The kinds `side` holds at least one of.
+    pub fn kinds(&self, side: Side) -> impl Iterator<Item = PieceKind> + '_ {
+        PieceKind::HAND
+            .into_iter()
+            .filter(move |&kind| self.count(side, kind) > 0)
+    }
+
+    pub fn to_sfen(&self) -> String {
+        let mut sfen = String::new();
+        for side in [Side::Sente, Side::Gote] {
+            for kind in PieceKind::HAND {
+                let count = self.count(side, kind);
+                if count > 1 {
+                    sfen.push_str(&count.to_string());
+                }
+                if count > 0 {
+                    sfen.push_str(&Piece::new(side, kind).to_sfen());
+                }
+            }
+        }
+        if sfen.is_empty() {
+            sfen.push('-');
+        }
+        sfen
+    }
+}
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum SfenError {
+    #[error("expected 3 or 4 fields, found {0}")]
+    WrongFieldCount(usize),
+
+    #[error("invalid piece placement: {0}")]
+    InvalidPlacement(String),
+
+    #[error("invalid side to move: {0}")]
+    InvalidSideToMove(String),
+
+    #[error("invalid pieces in hand: {0}")]
+    InvalidHands(String),
+
+    #[error("invalid move number: {0}")]
+    InvalidMoveNumber(String),
+
+    #[error("{0:?} must have exactly one king")]
+    KingCount(Side),
+
+    #[error("piece with no moves at {0}")]
+    DeadPiece(String),
+
+    #[error("two unpromoted pawns on file {0}")]
+    DoublePawn(u8),
+}
+
+/// A shogi position: the pieces on the board and in hand, and whose move
+/// it is.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ShogiBoard {
+    cells: [Option<Piece>; 81],
+    pub hands: Hands,
+    pub to_move: Side,
+    pub move_number: u32,
+}
+
+impl Default for ShogiBoard {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ShogiBoard {
+    pub fn new() -> Self {
+        Self::from_sfen(SHOGI_START).expect("start position is valid SFEN")
+    }
+
+    pub fn empty() -> Self {
+        Self {
+            cells: [None; 81],
+            hands: Hands::default(),
+            to_move: Side::Sente,
+            move_number: 1,
+        }
+    }
+
+    pub fn piece_at(&self, square: Square) -> Option<Piece> {
+        self.cells[square.index()]
+    }
+
+    pub fn set_piece(&mut self, square: Square, piece: Option<Piece>) {
+        self.cells[square.index()] = piece;
+    }
+
+    pub fn pieces(&self) -> impl Iterator<Item = (Square, Piece)> + '_ {
+        Square::all().filter_map(|sq| self.piece_at(sq).map(|piece| (sq, piece)))
+    }
+
+    pub fn king_square(&self, side: Side) -> Option<Square> {
+        self.pieces()
+            .find(|(_, piece)| piece.side == side && piece.kind == PieceKind::King)
+            .map(|(sq, _)| sq)
+    }
+
+    /// Whether `side` has an unpromoted pawn on `file`.
+    pub fn has_pawn_on_file(&self, side: Side, file: u8) -> bool {
+        (1..=9).any(|rank| {
+            Square::new(file, rank).and_then(|sq| self.piece_at(sq))
+                == Some(Piece::new(side, PieceKind::Pawn))
+        })
+    }
+
+    /// Reads SFEN, e.g. `lnsgkgsnl/1r5b1/ppppppppp/9/9/9/PPPPPPPPP/1B5R1/LNSGKGSNL b - 1`.
+    /// The move number may be left off.
+    pub fn from_sfen(sfen: &str) -> Result<Self, SfenError> {
+        let fields: Vec<&str> = sfen.split_whitespace().collect();
+        if !(3..=4).contains(&fields.len()) {
+            return Err(SfenError::WrongFieldCount(fields.len()));
+        }
+
+        let mut board = Self::empty();
+        board.parse_placement(fields[0])?;
+
+        board.to_move = match fields[1] {
+            "b" => Side::Sente,
+            "w" => Side::Gote,
+            other => return Err(SfenError::InvalidSideToMove(other.to_string())),
+        };
+
+        board.parse_hands(fields[2])?;
+
+        if let Some(field) = fields.get(3) {
+            board.move_number = field
+                .parse()
+                .ok()
+                .filter(|&n| n > 0)
+                .ok_or_else(|| SfenError::InvalidMoveNumber(field.to_string()))?;
+        }
+
+        board.validate()?;
+        Ok(board)
+    }
+
+    fn parse_placement(&mut self, placement: &str) -> Result<(), SfenError> {
+        let invalid = || SfenError::InvalidPlacement(placement.to_string());
+        let rows: Vec<&str> = placement.split('/').collect();
+        if rows.len() != 9 {
+            return Err(invalid());
+        }
+
+        for (rank, row) in (1..=9).zip(rows) {
+            let mut file = 10u8;
+            let mut promoted = false;
+            for c in row.chars() {
+                if c == '+' {
+                    promoted = true;
+                    continue;
+                }
+                if let Some(skip) = c.to_digit(10).filter(|_| !promoted) {
+                    file = file.checked_sub(skip as u8).ok_or_else(invalid)?;
+                    continue;
+                }
+
+                let kind = PieceKind::from_sfen_char(c).ok_or_else(invalid)?;
+                if promoted && !kind.can_promote() {
+                    return Err(invalid());
+                }
+                file -= 1;
+                let side = match c.is_ascii_uppercase() {
+                    true => Side::Sente,
+                    false => Side::Gote,
+                };
+                let square = Square::new(file, rank).ok_or_else(invalid)?;
+                self.set_piece(
+                    square,
+                    Some(Piece {
+                        side,
+                        kind,
+                        promoted,
+                    }),
+                );
+                promoted = false;
+            }
+            if file != 1 || promoted {
+                return Err(invalid());
+            }
+        }
+        Ok(())
+    }
+
+    fn parse_hands(&mut self, field: &str) -> Result<(), SfenError> {
+        if field == "-" {
+            return Ok(());
+        }
+
+        let invalid = || SfenError::InvalidHands(field.to_string());
+        let mut count = String::new();
+        for c in field.chars() {
+            if c.is_ascii_digit() {
+                count.push(c);
+                continue;
+            }
+
+            let kind = PieceKind::from_sfen_char(c)
+                .filter(|&kind| kind != PieceKind::King)
+                .ok_or_else(invalid)?;
+            let side = match c.is_ascii_uppercase() {
+                true => Side::Sente,
+                false => Side::Gote,
+            };
+            let n: u8 = match count.as_str() {
+                "" => 1,
+                digits => digits.parse().map_err(|_| invalid())?,
+            };
+            for _ in 0..n {
+                self.hands.add(side, kind);
+            }
+            count.clear();
+        }
+        match count.is_empty() {
+            true => Ok(()),
+            false => Err(invalid()),
+        }
+    }
+
+    fn validate(&self) -> Result<(), SfenError> {
+        for side in [Side::Sente, Side::Gote] {
+            let kings = self
+                .pieces()
+                .filter(|(_, piece)| piece.side == side && piece.kind == PieceKind::King)
+                .count();
+            if kings != 1 {
+                return Err(SfenError::KingCount(side));
+            }
+
+            for file in 1..=9 {
+                let pawns = (1..=9)
+                    .filter_map(|rank| Square::new(file, rank))
+                    .filter(|&sq| self.piece_at(sq) == Some(Piece::new(side, PieceKind::Pawn)))
+                    .count();
+                if pawns > 1 {
+                    return Err(SfenError::DoublePawn(file));
+                }
+            }
+        }
+
+        for (square, piece) in self.pieces() {
+            if !piece.promoted && square.relative_rank(piece.side) > 9 - piece.kind.dead_ranks() {
+                return Err(SfenError::DeadPiece(square.to_usi()));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn to_sfen(&self) -> String {
+        format!("{} {}", self.position_key(), self.move_number)
+    }
+
+    /// The SFEN without the move number, which identifies the position for
+    /// repetition.
+    pub fn position_key(&self) -> String {
+        let mut placement = String::new();
+        for rank in 1..=9 {
+            let mut empty = 0;
+            for file in (1..=9).rev() {
+                match Square::new(file, rank).and_then(|sq| self.piece_at(sq)) {
+                    Some(piece) => {
+                        if empty > 0 {
+                            placement.push_str(&empty.to_string());
+                            empty = 0;
+                        }
+                        placement.push_str(&piece.to_sfen());
+                    }
+                    None => empty += 1,
+                }
+            }
+            if empty > 0 {
+                placement.push_str(&empty.to_string());
+            }
+            if rank < 9 {
+                placement.push('/');
+            }
+        }
+
+        let side = match self.to_move {
+            Side::Sente => 'b',
+            Side::Gote => 'w',
+        };
+        format!("{} {} {}", placement, side, self.hands.to_sfen())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_squares() {
+        let square = Square::from_usi("7g").unwrap();
+        assert_eq!((square.file(), square.rank()), (7, 7));
+        assert_eq!(square.to_usi(), "7g");
+        assert_eq!(square.offset(0, -1), Square::from_usi("7f"));
+        assert_eq!(Square::from_usi("1a").unwrap().offset(-1, 0), None);
+        assert!(Square::from_usi("0a").is_none());
+        assert!(Square::from_usi("5j").is_none());
+
+        assert!(
+            Square::from_usi("3c")
+                .unwrap()
+                .in_promotion_zone(Side::Sente)
+        );
+        assert!(
+            !Square::from_usi("3c")
+                .unwrap()
+                .in_promotion_zone(Side::Gote)
+        );
+    }
+
+    #[test]
+    fn test_sfen_round_trip() {
+        assert_eq!(ShogiBoard::new().to_sfen(), SHOGI_START);
+
+        let sfen = "8l/1l+R2P3/p2pBG1pp/kps1p4/Nn1P2G2/P1P1P2PP/1PS6/1KSG3+r1/LN2+p3L w Sbgn3p 124";
+        assert_eq!(ShogiBoard::from_sfen(sfen).unwrap().to_sfen(), sfen);
+    }
+
+    #[test]
+    fn test_invalid_sfen() {
+        for (sfen, error) in [
+            ("9/9/9/9/9/9/9/9/9 b -", SfenError::KingCount(Side::Sente)),
+            ("4k4/9/9/9/9/P8/9/P8/4K4 b - 1", SfenError::DoublePawn(9)),
+            (
+                "P3k4/9/9/9/9/9/9/9/4K4 w - 1",
+                SfenError::DeadPiece("9a".to_string()),
+            ),
+            (
+                "4k4/9/9/9/9/9/9/9/4K4 x -",
+                SfenError::InvalidSideToMove("x".to_string()),
+            ),
+            (
+                "4k4/9/9/9/9/9/9/9/4K4 b 2",
+                SfenError::InvalidHands("2".to_string()),
+            ),
+        ] {
+            assert_eq!(ShogiBoard::from_sfen(sfen), Err(error));
+        }
+    }
+}
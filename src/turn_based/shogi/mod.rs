@@ -0,0 +1,338 @@
+pub mod board;
+pub mod rules;
+
+pub use board::*;
+pub use rules::*;
+
+use serde::{Deserialize, Serialize};
+
+use super::TurnBasedGame;
+
+/// The move a player sends to declare a win by impasse, as in USI.
+pub const DECLARE_WIN: &str = "win";
+
+/// Shogi as a `TurnBasedGame`. Seat 0 is Sente. Moves are USI strings, or
+/// `win` to declare an impasse win, and views carry the position as SFEN.
+///
+/// Impasse is settled by declaration under the 27-point rule. The try rule,
+/// where a king reaching the opposing king's starting square wins, is off
+/// unless hosted as `shogi-try`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Shogi {
+    pub try_rule: bool,
+}
+
+impl Shogi {
+    pub fn with_try_rule() -> Self {
+        Self { try_rule: true }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ShogiWinReason {
+    Checkmate,
+    /// The side to move has no legal move without being in check.
+    NoLegalMoves,
+    /// The loser kept checking through a fourfold repetition.
+    PerpetualCheck,
+    Impasse,
+    Try,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ShogiOutcome {
+    Win(usize, ShogiWinReason),
+    /// Sennichite: the same position four times.
+    Draw,
+}
+
+/// A game of shogi: the current position and every position before it,
+/// which repetition is judged on.
+#[derive(Debug, Clone)]
+pub struct ShogiGame {
+    pub board: ShogiBoard,
+    pub moves: Vec<ShogiMove>,
+    /// Every position so far, starting with the initial one.
+    history: Vec<Reached>,
+    outcome: Option<ShogiOutcome>,
+}
+
+/// A position in a game's history and the move that led to it.
+#[derive(Debug, Clone)]
+struct Reached {
+    key: String,
+    mover: Option<Side>,
+    gave_check: bool,
+}
+
+impl Default for ShogiGame {
+    fn default() -> Self {
+        Self::new(ShogiBoard::new())
+    }
+}
+
+impl ShogiGame {
+    pub fn new(board: ShogiBoard) -> Self {
+        let mut game = Self {
+            history: vec![Reached {
+                key: board.position_key(),
+                mover: None,
+                gave_check: false,
+            }],
+            board,
+            moves: Vec::new(),
+            outcome: None,
+        };
+        game.outcome = game.judge_mate();
+        game
+    }
+
+    pub fn from_sfen(sfen: &str) -> Result<Self, SfenError> {
+        ShogiBoard::from_sfen(sfen).map(Self::new)
+    }
+
+    pub fn to_sfen(&self) -> String {
+        self.board.to_sfen()
+    }
+
+    pub fn outcome(&self) -> Option<ShogiOutcome> {
+        self.outcome
+    }
+
+    pub fn legal_moves(&self) -> Vec<ShogiMove> {
+        match self.outcome {
+            Some(_) => Vec::new(),
+            None => legal_moves(&self.board),
+        }
+    }
+
+    pub fn make_move(&mut self, shogi_move: ShogiMove, try_rule: bool) -> Result<(), String> {
+        if self.outcome.is_some() {
+            return Err("Game is already finished".to_string());
+        }
+        if !legal_moves(&self.board).contains(&shogi_move) {
+            return Err(format!("Illegal move {}", shogi_move));
+        }
+
+        let mover = self.board.to_move;
+        self.board = apply(&self.board, shogi_move);
+        self.moves.push(shogi_move);
+        self.history.push(Reached {
+            key: self.board.position_key(),
+            mover: Some(mover),
+            gave_check: is_in_check(&self.board, mover.opposite()),
+        });
+
+        let reached_try = try_rule
+            && matches!(shogi_move, ShogiMove::Board { to, .. }
+                if to == try_square(mover) && self.board.king_square(mover) == Some(to));
+        self.outcome = match reached_try {
+            true => Some(ShogiOutcome::Win(mover.index(), ShogiWinReason::Try)),
+            false => self.judge_repetition().or_else(|| self.judge_mate()),
+        };
+        Ok(())
+    }
+
+    /// Ends the game in the mover's favour if they may declare impasse.
+    pub fn declare_impasse(&mut self) -> Result<(), String> {
+        if self.outcome.is_some() {
+            return Err("Game is already finished".to_string());
+        }
+
+        let side = self.board.to_move;
+        if !can_declare_impasse(&self.board, side) {
+            return Err("Impasse declaration requirements are not met".to_string());
+        }
+        self.outcome = Some(ShogiOutcome::Win(side.index(), ShogiWinReason::Impasse));
+        Ok(())
+    }
+
+    /// Shogi has no stalemate: a side without legal moves loses.
+    fn judge_mate(&self) -> Option<ShogiOutcome> {
+        let side = self.board.to_move;
+        if has_legal_moves(&self.board) {
+            return None;
+        }
+
+        let reason = match is_in_check(&self.board, side) {
+            true => ShogiWinReason::Checkmate,
+            false => ShogiWinReason::NoLegalMoves,
+        };
+        Some(ShogiOutcome::Win(side.opposite().index(), reason))
+    }
+
+    /// Sennichite: the fourth occurrence of a position is a draw, unless
+    /// one side gave check with every move since it first occurred, in which
+    /// case that side loses.
+    fn judge_repetition(&self) -> Option<ShogiOutcome> {
+        let key = &self.history.last()?.key;
+        let first = self
+            .history
+            .iter()
+            .position(|reached| &reached.key == key)?;
+        let occurrences = self.history[first..]
+            .iter()
+            .filter(|reached| &reached.key == key)
+            .count();
+        if occurrences < 4 {
+            return None;
+        }
+
+        let cycle = &self.history[first + 1..];
+        for side in [Side::Sente, Side::Gote] {
+            let always_checked = cycle
+                .iter()
+                .filter(|reached| reached.mover == Some(side))
+                .all(|reached| reached.gave_check);
+            if always_checked {
+                return Some(ShogiOutcome::Win(
+                    side.opposite().index(),
+                    ShogiWinReason::PerpetualCheck,
+                ));
+            }
+        }
+        Some(ShogiOutcome::Draw)
+    }
+}
+
+/// Nothing is hidden, so every seat sees the same.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ShogiView {
+    pub sfen: String,
+    pub moves: Vec<String>,
+    pub to_move: usize,
+    pub is_check: bool,
+}
+
+impl TurnBasedGame for Shogi {
+    type Move = String;
+    type State = ShogiGame;
+    type View = ShogiView;
+    type Outcome = ShogiOutcome;
+
+    fn kind(&self) -> &'static str {
+        match self.try_rule {
+            true => "shogi-try",
+            false => "shogi",
+        }
+    }
+
+    fn new_state(&self) -> ShogiGame {
+        ShogiGame::default()
+    }
+
+    fn to_move(&self, state: &ShogiGame) -> usize {
+        state.board.to_move.index()
+    }
+
+    fn legal_moves(&self, state: &ShogiGame) -> Vec<String> {
+        let mut moves: Vec<String> = state.legal_moves().iter().map(|m| m.to_usi()).collect();
+        if state.outcome.is_none() && can_declare_impasse(&state.board, state.board.to_move) {
+            moves.push(DECLARE_WIN.to_string());
+        }
+        moves
+    }
+
+    fn apply_move(&self, state: &mut ShogiGame, usi: String) -> Result<(), String> {
+        if usi == DECLARE_WIN {
+            return state.declare_impasse();
+        }
+
+        let shogi_move =
+            ShogiMove::from_usi(&usi).ok_or_else(|| format!("Cannot read move '{}'", usi))?;
+        state.make_move(shogi_move, self.try_rule)
+    }
+
+    fn view(&self, state: &ShogiGame, _seat: Option<usize>) -> ShogiView {
+        ShogiView {
+            sfen: state.to_sfen(),
+            moves: state.moves.iter().map(|m| m.to_usi()).collect(),
+            to_move: self.to_move(state),
+            is_check: is_in_check(&state.board, state.board.to_move),
+        }
+    }
+
+    fn outcome(&self, state: &ShogiGame) -> Option<ShogiOutcome> {
+        state.outcome
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn play(game: &mut ShogiGame, moves: &[&str]) {
+        for usi in moves {
+            Shogi::default().apply_move(game, usi.to_string()).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_checkmate() {
+        let mut game = ShogiGame::from_sfen("7nk/9/7GP/9/9/9/9/9/K8 b - 1").unwrap();
+        play(&mut game, &["1c1b"]);
+        assert_eq!(
+            game.outcome(),
+            Some(ShogiOutcome::Win(0, ShogiWinReason::Checkmate))
+        );
+        assert!(Shogi::default().legal_moves(&game).is_empty());
+    }
+
+    #[test]
+    fn test_sennichite() {
+        let mut game = ShogiGame::default();
+        let shuffle = ["2h3h", "8b7b", "3h2h", "7b8b"];
+        play(&mut game, &[shuffle, shuffle].concat());
+        play(&mut game, &shuffle[..3]);
+        assert_eq!(game.outcome(), None);
+
+        play(&mut game, &shuffle[3..]);
+        assert_eq!(game.outcome(), Some(ShogiOutcome::Draw));
+    }
+
+    #[test]
+    fn test_perpetual_check() {
+        // Sente checks with every move, so the repetition loses for Sente
+        let mut game = ShogiGame::from_sfen("8k/9/9/9/9/9/9/9/R3K4 b - 1").unwrap();
+        play(&mut game, &["9i9a"]);
+        let cycle = ["1a1b", "9a9b", "1b1a", "9b9a"];
+        play(&mut game, &[cycle, cycle, cycle].concat());
+        assert_eq!(
+            game.outcome(),
+            Some(ShogiOutcome::Win(1, ShogiWinReason::PerpetualCheck))
+        );
+    }
+
+    #[test]
+    fn test_impasse_and_try() {
+        let sfen = "+R+BGGSS+N+NK/LL7/PPPP5/9/9/9/9/9/k8 b 6P 1";
+        let mut game = ShogiGame::from_sfen(sfen).unwrap();
+        assert!(
+            Shogi::default()
+                .legal_moves(&game)
+                .contains(&DECLARE_WIN.to_string())
+        );
+        play(&mut game, &[DECLARE_WIN]);
+        assert_eq!(
+            game.outcome(),
+            Some(ShogiOutcome::Win(0, ShogiWinReason::Impasse))
+        );
+
+        // Only counts with the try rule on
+        let sfen = "k8/4K4/9/9/9/9/9/9/9 b - 1";
+        let mut game = ShogiGame::from_sfen(sfen).unwrap();
+        Shogi::default()
+            .apply_move(&mut game, "5b5a".to_string())
+            .unwrap();
+        assert_eq!(game.outcome(), None);
+
+        let mut game = ShogiGame::from_sfen(sfen).unwrap();
+        Shogi::with_try_rule()
+            .apply_move(&mut game, "5b5a".to_string())
+            .unwrap();
+        assert_eq!(
+            game.outcome(),
+            Some(ShogiOutcome::Win(0, ShogiWinReason::Try))
+        );
+    }
+}
@@ -0,0 +1,404 @@
+use std::fmt;
+
+use super::board::{Piece, PieceKind, ShogiBoard, Side, Square};
+
+/// A move in USI notation: `7g7f`, `8h2b+` for a promotion, `P*5e` for a
+/// drop.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ShogiMove {
+    Board {
+        from: Square,
+        to: Square,
+        promote: bool,
+    },
+    Drop {
+        kind: PieceKind,
+        to: Square,
+    },
+}
+
+impl ShogiMove {
+    pub fn to(self) -> Square {
+        match self {
+            ShogiMove::Board { to, .. } | ShogiMove::Drop { to, .. } => to,
+        }
+    }
+
+    pub fn from_usi(usi: &str) -> Option<Self> {
+        if let Some((piece, to)) = usi.split_once('*') {
+            let mut chars = piece.chars();
+            let c = chars.next().filter(|c| c.is_ascii_uppercase())?;
+            let kind = PieceKind::from_sfen_char(c).filter(|&kind| kind != PieceKind::King)?;
+            if chars.next().is_some() {
+                return None;
+            }
+            return Some(ShogiMove::Drop {
+                kind,
+                to: Square::from_usi(to)?,
+            });
+        }
+
+        let (squares, promote) = match usi.strip_suffix('+') {
+            Some(squares) => (squares, true),
+            None => (usi, false),
+        };
+        if squares.len() != 4 {
+            return None;
+        }
+        Some(ShogiMove::Board {
+            from: Square::from_usi(squares.get(..2)?)?,
+            to: Square::from_usi(squares.get(2..)?)?,
+            promote,
+        })
+    }
+
+    pub fn to_usi(self) -> String {
+        match self {
+            ShogiMove::Board { from, to, promote } => {
+                format!("{}{}{}", from, to, if promote { "+" } else { "" })
+            }
+            ShogiMove::Drop { kind, to } => {
+                format!("{}*{}", kind.to_sfen_char().to_ascii_uppercase(), to)
+            }
+        }
+    }
+}
+
+impl fmt::Display for ShogiMove {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.to_usi())
+    }
+}
+
+const KING_STEPS: [(i8, i8); 8] = [
+    (-1, -1),
+    (0, -1),
+    (1, -1),
+    (-1, 0),
+    (1, 0),
+    (-1, 1),
+    (0, 1),
+    (1, 1),
+];
+const DIAGONALS: [(i8, i8); 4] = [(-1, -1), (1, -1), (-1, 1), (1, 1)];
+const ORTHOGONALS: [(i8, i8); 4] = [(0, -1), (-1, 0), (1, 0), (0, 1)];
+
+/// Steps as (files, ranks forward) for the pieces that only step.
+fn steps(piece: Piece) -> &'static [(i8, i8)] {
+    const GOLD: [(i8, i8); 6] = [(-1, 1), (0, 1), (1, 1), (-1, 0), (1, 0), (0, -1)];
+    const SILVER: [(i8, i8); 5] = [(-1, 1), (0, 1), (1, 1), (-1, -1), (1, -1)];
+    const KNIGHT: [(i8, i8); 2] = [(-1, 2), (1, 2)];
+    const PAWN: [(i8, i8); 1] = [(0, 1)];
+
+    match (piece.kind, piece.promoted) {
+        (PieceKind::King, _) => &KING_STEPS,
+        (PieceKind::Bishop, true) => &ORTHOGONALS,
+        (PieceKind::Rook, true) => &DIAGONALS,
+        (PieceKind::Bishop | PieceKind::Rook, false) => &[],
+        (PieceKind::Gold, _) | (_, true) => &GOLD,
+        (PieceKind::Silver, false) => &SILVER,
+        (PieceKind::Knight, false) => &KNIGHT,
+        (PieceKind::Pawn, false) => &PAWN,
+        (PieceKind::Lance, false) => &[],
+    }
+}
+
+/// Directions as (files, ranks forward) a piece slides along.
+fn slides(piece: Piece) -> &'static [(i8, i8)] {
+    const FORWARD: [(i8, i8); 1] = [(0, 1)];
+
+    match (piece.kind, piece.promoted) {
+        (PieceKind::Bishop, _) => &DIAGONALS,
+        (PieceKind::Rook, _) => &ORTHOGONALS,
+        (PieceKind::Lance, false) => &FORWARD,
+        _ => &[],
+    }
+}
+
+/// The squares the piece on `from` attacks.
+pub fn attacks(board: &ShogiBoard, from: Square) -> Vec<Square> {
+    let Some(piece) = board.piece_at(from) else {
+        return Vec::new();
+    };
+    let forward = piece.side.forward();
+
+    let mut targets: Vec<Square> = steps(piece)
+        .iter()
+        .filter_map(|&(files, ranks)| from.offset(files, ranks * forward))
+        .collect();
+    for &(files, ranks) in slides(piece) {
+        let mut square = from;
+        while let Some(next) = square.offset(files, ranks * forward) {
+            targets.push(next);
+            if board.piece_at(next).is_some() {
+                break;
+            }
+            square = next;
+        }
+    }
+    targets
+}
+
+pub fn is_attacked(board: &ShogiBoard, square: Square, by: Side) -> bool {
+    board
+        .pieces()
+        .filter(|(_, piece)| piece.side == by)
+        .any(|(from, _)| attacks(board, from).contains(&square))
+}
+
+pub fn is_in_check(board: &ShogiBoard, side: Side) -> bool {
+    board
+        .king_square(side)
+        .is_some_and(|king| is_attacked(board, king, side.opposite()))
+}
+
+/// Whether an unpromoted `kind` of `side` could still move from `square`.
+fn has_moves_from(kind: PieceKind, side: Side, square: Square) -> bool {
+    square.relative_rank(side) <= 9 - kind.dead_ranks()
+}
+
+/// Moves that follow how the pieces move, without regard to the mover's
+/// king or the special drop rules beyond dead squares and nifu.
+pub fn pseudo_legal_moves(board: &ShogiBoard) -> Vec<ShogiMove> {
+    let side = board.to_move;
+    let mut moves = Vec::new();
+
+    for (from, piece) in board.pieces().filter(|(_, piece)| piece.side == side) {
+        for to in attacks(board, from) {
+            if board.piece_at(to).is_some_and(|target| target.side == side) {
+                continue;
+            }
+
+            let may_promote = piece.kind.can_promote()
+                && !piece.promoted
+                && (from.in_promotion_zone(side) || to.in_promotion_zone(side));
+            if may_promote {
+                moves.push(ShogiMove::Board {
+                    from,
+                    to,
+                    promote: true,
+                });
+            }
+            if piece.promoted || has_moves_from(piece.kind, side, to) {
+                moves.push(ShogiMove::Board {
+                    from,
+                    to,
+                    promote: false,
+                });
+            }
+        }
+    }
+
+    for kind in board.hands.kinds(side) {
+        for to in Square::all() {
+            let allowed = board.piece_at(to).is_none()
+                && has_moves_from(kind, side, to)
+                && !(kind == PieceKind::Pawn && board.has_pawn_on_file(side, to.file()));
+            if allowed {
+                moves.push(ShogiMove::Drop { kind, to });
+            }
+        }
+    }
+    moves
+}
+
+/// Plays a move without checking it, returning the board after it.
+pub fn apply(board: &ShogiBoard, shogi_move: ShogiMove) -> ShogiBoard {
+    let mut next = board.clone();
+    let side = board.to_move;
+
+    match shogi_move {
+        ShogiMove::Board { from, to, promote } => {
+            if let Some(captured) = next.piece_at(to) {
+                next.hands.add(side, captured.kind);
+            }
+            let piece = next.piece_at(from).map(|piece| Piece {
+                promoted: piece.promoted || promote,
+                ..piece
+            });
+            next.set_piece(from, None);
+            next.set_piece(to, piece);
+        }
+        ShogiMove::Drop { kind, to } => {
+            next.hands.take(side, kind);
+            next.set_piece(to, Some(Piece::new(side, kind)));
+        }
+    }
+
+    next.to_move = side.opposite();
+    next.move_number += 1;
+    next
+}
+
+/// Every legal move: those that do not leave the mover's king attacked,
+/// and no pawn drop that mates (uchifuzume).
+pub fn legal_moves(board: &ShogiBoard) -> Vec<ShogiMove> {
+    pseudo_legal_moves(board)
+        .into_iter()
+        .filter(|&shogi_move| is_legal_pseudo_move(board, shogi_move))
+        .collect()
+}
+
+pub fn has_legal_moves(board: &ShogiBoard) -> bool {
+    pseudo_legal_moves(board)
+        .into_iter()
+        .any(|shogi_move| is_legal_pseudo_move(board, shogi_move))
+}
+
+fn is_legal_pseudo_move(board: &ShogiBoard, shogi_move: ShogiMove) -> bool {
+    let side = board.to_move;
+    let next = apply(board, shogi_move);
+    if is_in_check(&next, side) {
+        return false;
+    }
+
+    // Each nested check uses up a pawn in hand, so this always ends
+    let is_pawn_drop = matches!(
+        shogi_move,
+        ShogiMove::Drop {
+            kind: PieceKind::Pawn,
+            ..
+        }
+    );
+    !(is_pawn_drop && is_in_check(&next, side.opposite()) && !has_legal_moves(&next))
+}
+
+/// Whether `side` may declare a win by impasse (nyugyoku) under the
+/// 27-point rule: its king in the promotion zone and not in check, ten other
+/// pieces there with it, and 28 points for Sente or 27 for Gote counting
+/// those pieces and the hand.
+pub fn can_declare_impasse(board: &ShogiBoard, side: Side) -> bool {
+    let Some(king) = board.king_square(side) else {
+        return false;
+    };
+    if !king.in_promotion_zone(side) || is_in_check(board, side) {
+        return false;
+    }
+
+    let in_zone: Vec<Piece> = board
+        .pieces()
+        .filter(|(sq, piece)| {
+            piece.side == side && piece.kind != PieceKind::King && sq.in_promotion_zone(side)
+        })
+        .map(|(_, piece)| piece)
+        .collect();
+    let points: u32 = in_zone
+        .iter()
+        .map(|piece| piece.kind.impasse_points())
+        .chain(
+            PieceKind::HAND
+                .into_iter()
+                .map(|kind| kind.impasse_points() * u32::from(board.hands.count(side, kind))),
+        )
+        .sum();
+    let needed = match side {
+        Side::Sente => 28,
+        Side::Gote => 27,
+    };
+
+    in_zone.len() >= 10 && points >= needed
+}
+
+/// The square a king must reach to win under the try rule: the opposing
+/// king's starting square.
+pub fn try_square(side: Side) -> Square {
+    match side {
+        Side::Sente => Square::new(5, 1),
+        Side::Gote => Square::new(5, 9),
+    }
+    .expect("5a and 5i are on the board")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn usi(moves: &[ShogiMove]) -> Vec<String> {
+        let mut usi: Vec<String> = moves.iter().map(|m| m.to_usi()).collect();
+        usi.sort();
+        usi
+    }
+
+    #[test]
+    fn test_usi_notation() {
+        for notation in ["7g7f", "8h2b+", "P*5e", "S*1a"] {
+            assert_eq!(ShogiMove::from_usi(notation).unwrap().to_usi(), notation);
+        }
+        for notation in ["7g7", "K*5e", "p*5e", "7g7f=", "0a1a"] {
+            assert!(ShogiMove::from_usi(notation).is_none());
+        }
+    }
+
+    #[test]
+    fn test_start_position_moves() {
+        let board = ShogiBoard::new();
+        assert_eq!(legal_moves(&board).len(), 30);
+
+        // Bishops are blocked and cannot move at all
+        let next = apply(&board, ShogiMove::from_usi("7g7f").unwrap());
+        let next = apply(&next, ShogiMove::from_usi("3c3d").unwrap());
+        let moves = usi(&legal_moves(&next));
+        assert!(moves.contains(&"8h2b+".to_string()));
+        assert!(moves.contains(&"8h2b".to_string()));
+        assert!(moves.contains(&"8h3c+".to_string()));
+    }
+
+    #[test]
+    fn test_forced_promotion() {
+        // Pawn and lance must promote on the last rank, the knight on the
+        // last two
+        let board = ShogiBoard::from_sfen("4k4/P8/9/L7N/9/9/9/9/4K4 b - 1").unwrap();
+        let moves = usi(&legal_moves(&board));
+        assert!(moves.contains(&"9b9a+".to_string()));
+        assert!(!moves.contains(&"9b9a".to_string()));
+        assert!(moves.contains(&"9d9c+".to_string()));
+        assert!(moves.contains(&"9d9c".to_string()));
+        assert!(moves.contains(&"1d2b+".to_string()));
+        assert!(!moves.contains(&"1d2b".to_string()));
+    }
+
+    #[test]
+    fn test_illegal_drops() {
+        // Nifu: no second unpromoted pawn on a file, and nothing dropped
+        // where it could never move
+        let board = ShogiBoard::from_sfen("4k4/9/9/9/9/9/4P4/9/4K4 b PNL 1").unwrap();
+        let moves = usi(&legal_moves(&board));
+        assert!(!moves.iter().any(|m| m.starts_with("P*5")));
+        assert!(moves.contains(&"P*4e".to_string()));
+        assert!(!moves.contains(&"P*4a".to_string()));
+        assert!(!moves.contains(&"L*4a".to_string()));
+        assert!(!moves.contains(&"N*4b".to_string()));
+        assert!(moves.contains(&"N*4c".to_string()));
+    }
+
+    #[test]
+    fn test_uchifuzume() {
+        // P*1b would mate the cornered king, so it is not allowed; the same
+        // mate by a pushed pawn is fine
+        let board = ShogiBoard::from_sfen("7nk/9/7G1/9/9/9/9/9/K8 b P 1").unwrap();
+        let drop = ShogiMove::from_usi("P*1b").unwrap();
+        assert!(!legal_moves(&board).contains(&drop));
+
+        let board = ShogiBoard::from_sfen("7nk/9/7GP/9/9/9/9/9/K8 b - 1").unwrap();
+        let push = ShogiMove::from_usi("1c1b").unwrap();
+        assert!(legal_moves(&board).contains(&push));
+        let mated = apply(&board, push);
+        assert!(is_in_check(&mated, Side::Gote));
+        assert!(!has_legal_moves(&mated));
+
+        // A pawn drop check that can be answered is legal
+        let board = ShogiBoard::from_sfen("8k/9/9/9/9/9/9/9/K8 b P 1").unwrap();
+        assert!(legal_moves(&board).contains(&drop));
+    }
+
+    #[test]
+    fn test_impasse_declaration() {
+        let board = ShogiBoard::from_sfen("+R+BGGSS+N+NK/LL7/PPPP5/9/9/9/9/9/k8 b 6P 1").unwrap();
+        assert!(can_declare_impasse(&board, Side::Sente));
+        assert!(!can_declare_impasse(&board, Side::Gote));
+
+        // Enough points, but one piece short of ten in the zone
+        let board = ShogiBoard::from_sfen("+R+BGGSS+N+NK/L8/9/9/9/9/9/9/k8 b 11P 1").unwrap();
+        assert!(!can_declare_impasse(&board, Side::Sente));
+    }
+}
@@ -0,0 +1,471 @@
+use serde_json::Value;
+use uuid::Uuid;
+
+use super::{HostedGame, SeatedGame};
+use crate::game::{
+    Clock, Color, DRAW_OFFER_LIFETIME_MS, DrawOffer, DrawOffers, DrawReason, GameResult,
+};
+use crate::utils::{current_timestamp, current_timestamp_millis};
+
+/// A hosted game and the players seated at it. Tables are timed, resigned
+/// and drawn the way chess games are, with seat 0 as White and seat 1 as
+/// Black on the clock and in draw offers.
+pub struct Table {
+    pub id: String,
+    /// Player ID in each seat.
+    pub players: Vec<Option<String>>,
+    pub spectators: Vec<String>,
+    pub game: Box<dyn HostedGame>,
+    /// Moves played so far.
+    pub move_count: usize,
+    /// The seats' clocks, in timed games.
+    pub clock: Option<Clock>,
+    pub draw_offers: DrawOffers,
+    /// How the game ended away from the board: by resignation, on time or
+    /// by agreement. Any other ending is the game's own outcome.
+    pub result: Option<GameResult>,
+    pub created_at: u64,
+    pub last_move_at: u64,
+}
+
+/// The color a seat plays as in the parts of the server shared with chess,
+/// such as draw offers. Seat 0 moves first, like White.
+pub fn seat_color(seat: usize) -> Color {
+    match seat {
+        0 => Color::White,
+        _ => Color::Black,
+    }
+}
+
+impl Table {
+    pub fn new(game: Box<dyn HostedGame>) -> Self {
+        let now = current_timestamp();
+        Self {
+            id: Uuid::new_v4().to_string(),
+            players: vec![None; game.seats()],
+            spectators: Vec::new(),
+            game,
+            move_count: 0,
+            clock: None,
+            draw_offers: DrawOffers::new(),
+            result: None,
+            created_at: now,
+            last_move_at: now,
+        }
+    }
+
+    pub fn kind(&self) -> &'static str {
+        self.game.kind()
+    }
+
+    pub fn seat_of(&self, player_id: &str) -> Option<usize> {
+        self.players
+            .iter()
+            .position(|player| player.as_deref() == Some(player_id))
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.players.iter().all(Option::is_some)
+    }
+
+    pub fn is_over(&self) -> bool {
+        self.result.is_some() || self.game.is_over()
+    }
+
+    /// The player in `color`'s seat.
+    pub fn player_id(&self, color: Color) -> Option<&String> {
+        self.players.get(color.index())?.as_ref()
+    }
+
+    fn player_color(&self, player_id: &str) -> Result<Color, String> {
+        self.seat_of(player_id)
+            .map(seat_color)
+            .ok_or_else(|| "Player not in this game".to_string())
+    }
+
+    /// What `player_id` sees: their seat's view, or the spectators' one.
+    pub fn view_for(&self, player_id: &str) -> Value {
+        self.game.view(self.seat_of(player_id))
+    }
+
+    /// Everyone seated.
+    pub fn player_ids(&self) -> Vec<String> {
+        self.players.iter().flatten().cloned().collect()
+    }
+
+    /// Everyone seated or spectating, who are sent each update.
+    pub fn watcher_ids(&self) -> Vec<String> {
+        let mut watchers = self.player_ids();
+        watchers.extend(self.spectators.iter().cloned());
+        watchers
+    }
+
+    /// Seats a player, in `seat` if given or else the first free one.
+    pub fn seat_player(&mut self, player_id: String, seat: Option<usize>) -> Result<usize, String> {
+        if let Some(seat) = self.seat_of(&player_id) {
+            return Ok(seat);
+        }
+
+        let seat = match seat {
+            Some(seat) if seat >= self.players.len() => return Err("No such seat".to_string()),
+            Some(seat) if self.players[seat].is_some() => {
+                return Err("Seat is already taken".to_string());
+            }
+            Some(seat) => seat,
+            None => self
+                .players
+                .iter()
+                .position(Option::is_none)
+                .ok_or("Game is full")?,
+        };
+
+        self.players[seat] = Some(player_id);
+        self.start_clock_if_ready();
+        Ok(seat)
+    }
+
+    /// Plays the game on `clock`, which starts once every seat is taken.
+    pub fn set_clock(&mut self, clock: Clock) {
+        self.clock = Some(clock);
+        self.start_clock_if_ready();
+    }
+
+    fn start_clock_if_ready(&mut self) {
+        if !self.is_full() || self.is_over() {
+            return;
+        }
+        let to_move = seat_color(self.game.to_move());
+        if let Some(clock) = &mut self.clock {
+            clock.start(to_move, current_timestamp_millis());
+        }
+    }
+
+    /// Adds a spectator, who is then sent every update. Seated players
+    /// already are.
+    pub fn add_spectator(&mut self, player_id: String) {
+        if self.seat_of(&player_id).is_none() && !self.spectators.contains(&player_id) {
+            self.spectators.push(player_id);
+        }
+    }
+
+    /// Plays a move sent as JSON in the game's own move format, making sure
+    /// `player_id` is the one to move.
+    pub fn play(&mut self, player_id: &str, payload: Value) -> Result<(), String> {
+        self.play_with_lag(player_id, payload, 0)
+    }
+
+    /// Like `play`, but gives the mover back up to `lag_ms` of the time the
+    /// move took as network lag, as far as the clock allows.
+    pub fn play_with_lag(
+        &mut self,
+        player_id: &str,
+        payload: Value,
+        lag_ms: u64,
+    ) -> Result<(), String> {
+        if self.is_over() {
+            return Err("Game is already finished".to_string());
+        }
+        if !self.is_full() {
+            return Err("Waiting for players".to_string());
+        }
+        let mover = self.player_color(player_id)?;
+        if self.seat_of(player_id) != Some(self.game.to_move()) {
+            return Err("Not your turn".to_string());
+        }
+
+        if self.check_flag_with_lag(lag_ms) {
+            return Err("Time has run out".to_string());
+        }
+
+        self.game.apply_move(payload)?;
+        self.move_count += 1;
+        self.last_move_at = current_timestamp();
+
+        self.draw_offers.cancel_on_move(mover);
+        let over = self.is_over();
+        if let Some(clock) = &mut self.clock {
+            match over {
+                false => clock.press_with_lag(current_timestamp_millis(), lag_ms),
+                true => clock.stop(current_timestamp_millis()),
+            }
+        }
+        Ok(())
+    }
+
+    pub fn resign(&mut self, player_id: &str) -> Result<(), String> {
+        if self.is_over() {
+            return Err("Game is already finished".to_string());
+        }
+
+        let player_color = self.player_color(player_id)?;
+        self.end(GameResult::Resignation(player_color));
+        Ok(())
+    }
+
+    /// Offers the opponent a draw, as in `GameState::offer_draw`. Returns
+    /// `true` if it answered the opponent's own offer and drew the game.
+    pub fn offer_draw(&mut self, player_id: &str, message: Option<String>) -> Result<bool, String> {
+        if self.is_over() {
+            return Err("Game is already finished".to_string());
+        }
+
+        let player_color = self.player_color(player_id)?;
+        if self
+            .draw_offers
+            .pending
+            .as_ref()
+            .is_some_and(|offer| offer.from != player_color)
+        {
+            self.respond_to_draw(player_id, true)?;
+            return Ok(true);
+        }
+
+        self.draw_offers.offer(
+            player_color,
+            self.move_count,
+            message,
+            current_timestamp_millis(),
+        )?;
+        Ok(false)
+    }
+
+    /// Accepts or declines the draw offered to `player_id`, returning the
+    /// offer answered.
+    pub fn respond_to_draw(&mut self, player_id: &str, accept: bool) -> Result<DrawOffer, String> {
+        if self.is_over() {
+            return Err("Game is already finished".to_string());
+        }
+
+        let player_color = self.player_color(player_id)?;
+        let offer = self.draw_offers.take_offer_to(player_color)?;
+        if accept {
+            self.end(GameResult::Draw(DrawReason::Agreement));
+        }
+        Ok(offer)
+    }
+
+    /// The draw offer standing, while the game goes on.
+    pub fn pending_draw_offer(&self) -> Option<&DrawOffer> {
+        match self.is_over() {
+            false => self.draw_offers.pending.as_ref(),
+            true => None,
+        }
+    }
+
+    /// Withdraws the draw offer standing if it has gone unanswered too
+    /// long, returning it.
+    pub fn expire_draw_offer(&mut self) -> Option<DrawOffer> {
+        if self.is_over() {
+            return None;
+        }
+        self.draw_offers
+            .expire(current_timestamp_millis(), DRAW_OFFER_LIFETIME_MS)
+    }
+
+    /// How long until the draw offer standing lapses.
+    pub fn time_to_draw_offer_expiry_ms(&self) -> Option<u64> {
+        self.pending_draw_offer()?;
+        self.draw_offers
+            .time_to_expiry(current_timestamp_millis(), DRAW_OFFER_LIFETIME_MS)
+    }
+
+    /// Ends the game on time if the seat to move has run out, returning
+    /// whether it did.
+    pub fn check_flag(&mut self) -> bool {
+        self.check_flag_with_lag(u64::MAX)
+    }
+
+    fn check_flag_with_lag(&mut self, lag_ms: u64) -> bool {
+        if self.is_over() {
+            return false;
+        }
+
+        let flagged = self
+            .clock
+            .as_ref()
+            .and_then(|clock| clock.flagged_with_lag(current_timestamp_millis(), lag_ms));
+        let Some(flagged) = flagged else {
+            return false;
+        };
+        self.end(GameResult::Timeout(flagged));
+        true
+    }
+
+    /// `seat`'s time left, in timed games.
+    pub fn remaining_ms(&self, seat: usize) -> Option<u64> {
+        let clock = self.clock.as_ref()?;
+        Some(clock.remaining_ms(seat_color(seat), current_timestamp_millis()))
+    }
+
+    /// How long until the seat to move's flag falls, while their clock runs.
+    pub fn time_to_flag_ms(&self) -> Option<u64> {
+        self.clock
+            .as_ref()?
+            .time_to_flag(current_timestamp_millis())
+    }
+
+    fn end(&mut self, result: GameResult) {
+        self.result = Some(result);
+        if let Some(clock) = &mut self.clock {
+            clock.stop(current_timestamp_millis());
+        }
+        self.last_move_at = current_timestamp();
+    }
+}
+
+impl SeatedGame for Table {
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn kind(&self) -> &'static str {
+        Table::kind(self)
+    }
+
+    fn is_over(&self) -> bool {
+        Table::is_over(self)
+    }
+
+    fn player_id(&self, color: Color) -> Option<&String> {
+        Table::player_id(self, color)
+    }
+
+    fn play(&mut self, player_id: &str, payload: Value, lag_ms: u64) -> Result<(), String> {
+        self.play_with_lag(player_id, payload, lag_ms)
+    }
+
+    fn resign(&mut self, player_id: &str) -> Result<(), String> {
+        Table::resign(self, player_id)
+    }
+
+    fn offer_draw(&mut self, player_id: &str, message: Option<String>) -> Result<bool, String> {
+        Table::offer_draw(self, player_id, message)
+    }
+
+    fn respond_to_draw(&mut self, player_id: &str, accept: bool) -> Result<DrawOffer, String> {
+        Table::respond_to_draw(self, player_id, accept)
+    }
+
+    fn pending_draw_offer(&self) -> Option<&DrawOffer> {
+        Table::pending_draw_offer(self)
+    }
+
+    fn expire_draw_offer(&mut self) -> Option<DrawOffer> {
+        Table::expire_draw_offer(self)
+    }
+
+    fn time_to_draw_offer_expiry_ms(&self) -> Option<u64> {
+        Table::time_to_draw_offer_expiry_ms(self)
+    }
+
+    fn check_flag(&mut self) -> bool {
+        Table::check_flag(self)
+    }
+
+    fn time_to_flag_ms(&self) -> Option<u64> {
+        Table::time_to_flag_ms(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    use crate::game::TimeControl;
+    use crate::turn_based::{Hosted, Shogi, TicTacToe, TurnBasedGame};
+    use serde_json::json;
+
+    fn table<G: TurnBasedGame>(game: G) -> Table {
+        Table::new(Box::new(Hosted::new(Arc::new(game))))
+    }
+
+    #[test]
+    fn test_turns() {
+        let mut table = table(TicTacToe);
+
+        assert_eq!(table.seat_player("a".to_string(), Some(1)), Ok(1));
+        assert!(table.play("a", json!(4)).is_err());
+        assert_eq!(table.seat_player("b".to_string(), None), Ok(0));
+        assert!(table.seat_player("c".to_string(), None).is_err());
+        table.add_spectator("c".to_string());
+        table.add_spectator("a".to_string());
+        assert_eq!(table.watcher_ids(), ["b", "a", "c"]);
+
+        // Seat 0 moves first, and moves must be in the game's own format
+        assert!(table.play("a", json!(4)).is_err());
+        assert!(table.play("b", json!("e4")).is_err());
+        for (player, cell) in [("b", 0), ("a", 3), ("b", 1), ("a", 4), ("b", 2)] {
+            table.play(player, json!(cell)).unwrap();
+        }
+
+        assert_eq!(table.game.outcome(), Some(json!({ "Win": 0 })));
+        assert_eq!(table.move_count, 5);
+        assert_eq!(table.view_for("a")["cells"][0], json!(0));
+        assert!(table.play("a", json!(5)).is_err());
+    }
+
+    fn seated() -> Table {
+        let mut table = table(Shogi::default());
+        table.seat_player("a".to_string(), None).unwrap();
+        table.seat_player("b".to_string(), None).unwrap();
+        table
+    }
+
+    #[test]
+    fn test_clock() {
+        let mut table = table(Shogi::default());
+        table.set_clock(TimeControl::new(60, 0).clock());
+        table.seat_player("a".to_string(), None).unwrap();
+        assert_eq!(table.time_to_flag_ms(), None);
+        table.seat_player("b".to_string(), None).unwrap();
+        assert!(table.time_to_flag_ms().is_some());
+        table.play("a", json!("7g7f")).unwrap();
+        assert_eq!(table.clock.as_ref().unwrap().active(), Some(Color::Black));
+
+        let mut table = table_with_no_time();
+        assert!(table.check_flag());
+        assert_eq!(table.result, Some(GameResult::Timeout(Color::White)));
+        assert!(table.play("a", json!("7g7f")).is_err());
+    }
+
+    fn table_with_no_time() -> Table {
+        let mut table = seated();
+        table.set_clock(TimeControl::new(0, 0).clock());
+        table
+    }
+
+    #[test]
+    fn test_resign() {
+        let mut table = seated();
+        assert!(table.resign("c").is_err());
+        table.resign("b").unwrap();
+        assert_eq!(table.result, Some(GameResult::Resignation(Color::Black)));
+        assert!(table.is_over());
+        assert!(table.resign("a").is_err());
+    }
+
+    #[test]
+    fn test_draw_offers() {
+        let mut table = seated();
+        assert_eq!(table.offer_draw("a", None), Ok(false));
+        assert!(table.respond_to_draw("a", true).is_err());
+        assert!(table.time_to_draw_offer_expiry_ms().unwrap() <= DRAW_OFFER_LIFETIME_MS);
+        assert_eq!(table.expire_draw_offer(), None);
+
+        // Moving on withdraws the offer
+        table.play("a", json!("7g7f")).unwrap();
+        assert_eq!(table.pending_draw_offer(), None);
+
+        assert_eq!(table.offer_draw("b", None), Ok(false));
+        let offer = table.respond_to_draw("a", false).unwrap();
+        assert_eq!(offer.from, Color::Black);
+        assert!(!table.is_over());
+
+        // Offering against a standing offer accepts it
+        let mut table = seated();
+        table.offer_draw("a", None).unwrap();
+        assert_eq!(table.offer_draw("b", None), Ok(true));
+        assert_eq!(table.result, Some(GameResult::Draw(DrawReason::Agreement)));
+    }
+}
@@ -0,0 +1,140 @@
+use serde::{Deserialize, Serialize};
+
+use super::TurnBasedGame;
+
+/// Rows, columns and diagonals, by cell index from the top left.
+const LINES: [[usize; 3]; 8] = [
+    [0, 1, 2],
+    [3, 4, 5],
+    [6, 7, 8],
+    [0, 3, 6],
+    [1, 4, 7],
+    [2, 5, 8],
+    [0, 4, 8],
+    [2, 4, 6],
+];
+
+/// Tic-tac-toe, the smallest `TurnBasedGame`. Seat 0 plays X and moves
+/// first; a move is the index of the cell to mark, row by row from the top
+/// left.
+pub struct TicTacToe;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TicTacToeState {
+    /// The seat that marked each cell.
+    pub cells: [Option<usize>; 9],
+}
+
+impl TicTacToeState {
+    fn marks(&self) -> usize {
+        self.cells.iter().flatten().count()
+    }
+
+    fn winner(&self) -> Option<usize> {
+        LINES.iter().find_map(|&[a, b, c]| {
+            let mark = self.cells[a]?;
+            (self.cells[b] == Some(mark) && self.cells[c] == Some(mark)).then_some(mark)
+        })
+    }
+}
+
+/// Nothing is hidden, so every seat sees the same.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TicTacToeView {
+    pub cells: [Option<usize>; 9],
+    pub to_move: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TicTacToeOutcome {
+    Win(usize),
+    Draw,
+}
+
+impl TurnBasedGame for TicTacToe {
+    type Move = usize;
+    type State = TicTacToeState;
+    type View = TicTacToeView;
+    type Outcome = TicTacToeOutcome;
+
+    fn kind(&self) -> &'static str {
+        "tic-tac-toe"
+    }
+
+    fn new_state(&self) -> TicTacToeState {
+        TicTacToeState::default()
+    }
+
+    fn to_move(&self, state: &TicTacToeState) -> usize {
+        state.marks() % 2
+    }
+
+    fn legal_moves(&self, state: &TicTacToeState) -> Vec<usize> {
+        if self.outcome(state).is_some() {
+            return Vec::new();
+        }
+        (0..9).filter(|&cell| state.cells[cell].is_none()).collect()
+    }
+
+    fn apply_move(&self, state: &mut TicTacToeState, cell: usize) -> Result<(), String> {
+        if self.outcome(state).is_some() {
+            return Err("Game is already finished".to_string());
+        }
+
+        match state.cells.get(cell) {
+            Some(None) => {
+                state.cells[cell] = Some(self.to_move(state));
+                Ok(())
+            }
+            Some(Some(_)) => Err(format!("Cell {} is already marked", cell)),
+            None => Err(format!("No cell {}", cell)),
+        }
+    }
+
+    fn view(&self, state: &TicTacToeState, _seat: Option<usize>) -> TicTacToeView {
+        TicTacToeView {
+            cells: state.cells,
+            to_move: self.to_move(state),
+        }
+    }
+
+    fn outcome(&self, state: &TicTacToeState) -> Option<TicTacToeOutcome> {
+        match state.winner() {
+            Some(seat) => Some(TicTacToeOutcome::Win(seat)),
+            None if state.marks() == 9 => Some(TicTacToeOutcome::Draw),
+            None => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn play(cells: &[usize]) -> TicTacToeState {
+        let mut state = TicTacToe.new_state();
+        for &cell in cells {
+            TicTacToe.apply_move(&mut state, cell).unwrap();
+        }
+        state
+    }
+
+    #[test]
+    fn test_win_and_draw() {
+        let won = play(&[0, 3, 1, 4, 2]);
+        assert_eq!(TicTacToe.outcome(&won), Some(TicTacToeOutcome::Win(0)));
+        assert!(TicTacToe.legal_moves(&won).is_empty());
+
+        let drawn = play(&[4, 0, 2, 6, 3, 5, 1, 7, 8]);
+        assert_eq!(TicTacToe.outcome(&drawn), Some(TicTacToeOutcome::Draw));
+    }
+
+    #[test]
+    fn test_illegal_moves() {
+        let mut state = play(&[4]);
+        assert_eq!(TicTacToe.to_move(&state), 1);
+        assert!(TicTacToe.apply_move(&mut state, 4).is_err());
+        assert!(TicTacToe.apply_move(&mut state, 9).is_err());
+        assert_eq!(TicTacToe.legal_moves(&state).len(), 8);
+    }
+}
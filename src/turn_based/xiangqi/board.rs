@@ -0,0 +1,474 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+/// The starting position in Xiangqi FEN.
+pub const XIANGQI_START: &str =
+    "rnbakabnr/9/1c5c1/p1p1p1p1p/9/9/P1P1P1P1P/1C5C1/9/RNBAKABNR w - - 0 1";
+
+/// The two players. Red moves first, sits in seat 0 and is written in upper
+/// case in FEN.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Side {
+    Red,
+    Black,
+}
+
+impl Side {
+    pub fn opposite(self) -> Self {
+        match self {
+            Side::Red => Side::Black,
+            Side::Black => Side::Red,
+        }
+    }
+
+    /// The seat this side plays from.
+    pub fn index(self) -> usize {
+        match self {
+            Side::Red => 0,
+            Side::Black => 1,
+        }
+    }
+
+    /// The rank step towards the opponent.
+    pub fn forward(self) -> i8 {
+        match self {
+            Side::Red => 1,
+            Side::Black => -1,
+        }
+    }
+}
+
+/// A point of the 9x10 board. Files run a-i from Red's left and ranks 0-9
+/// from Red's side, as in ICCS coordinates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Square(u8);
+
+impl Square {
+    pub fn new(file: u8, rank: u8) -> Option<Self> {
+        (file < 9 && rank < 10).then_some(Square(rank * 9 + file))
+    }
+
+    pub fn all() -> impl Iterator<Item = Square> {
+        (0..90).map(Square)
+    }
+
+    pub fn index(self) -> usize {
+        self.0 as usize
+    }
+
+    /// 0 for file a through 8 for file i.
+    pub fn file(self) -> u8 {
+        self.0 % 9
+    }
+
+    pub fn rank(self) -> u8 {
+        self.0 / 9
+    }
+
+    /// The square `files` and `ranks` away, if still on the board.
+    pub fn offset(self, files: i8, ranks: i8) -> Option<Self> {
+        let file = self.file() as i8 + files;
+        let rank = self.rank() as i8 + ranks;
+        Square::new(u8::try_from(file).ok()?, u8::try_from(rank).ok()?)
+    }
+
+    /// Whether this square is on `side`'s half of the river.
+    pub fn is_own_half(self, side: Side) -> bool {
+        match side {
+            Side::Red => self.rank() <= 4,
+            Side::Black => self.rank() >= 5,
+        }
+    }
+
+    /// Whether this square is in `side`'s palace, the 3x3 box the general
+    /// and advisors may not leave.
+    pub fn in_palace(self, side: Side) -> bool {
+        let ranks = match side {
+            Side::Red => 0..=2,
+            Side::Black => 7..=9,
+        };
+        (3..=5).contains(&self.file()) && ranks.contains(&self.rank())
+    }
+
+    /// The file as `side` numbers it in WXF notation: 1-9 from that
+    /// player's right.
+    pub fn wxf_file(self, side: Side) -> u8 {
+        match side {
+            Side::Red => 9 - self.file(),
+            Side::Black => self.file() + 1,
+        }
+    }
+
+    /// Reads ICCS notation, e.g. `h2`.
+    pub fn from_iccs(s: &str) -> Option<Self> {
+        let mut chars = s.chars();
+        let file = chars.next().filter(|c| ('a'..='i').contains(c))?;
+        let rank = chars.next()?.to_digit(10)?;
+        if chars.next().is_some() {
+            return None;
+        }
+        Square::new(file as u8 - b'a', rank as u8)
+    }
+
+    pub fn to_iccs(self) -> String {
+        format!("{}{}", (b'a' + self.file()) as char, self.rank())
+    }
+}
+
+impl fmt::Display for Square {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.to_iccs())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum PieceKind {
+    General,
+    Advisor,
+    Elephant,
+    Horse,
+    Chariot,
+    Cannon,
+    Soldier,
+}
+
+impl PieceKind {
+    pub fn to_fen_char(self) -> char {
+        match self {
+            PieceKind::General => 'k',
+            PieceKind::Advisor => 'a',
+            PieceKind::Elephant => 'b',
+            PieceKind::Horse => 'n',
+            PieceKind::Chariot => 'r',
+            PieceKind::Cannon => 'c',
+            PieceKind::Soldier => 'p',
+        }
+    }
+
+    /// Reads a FEN letter, also taking the `e` and `h` some tools write for
+    /// elephants and horses.
+    pub fn from_fen_char(c: char) -> Option<Self> {
+        match c.to_ascii_lowercase() {
+            'k' => Some(PieceKind::General),
+            'a' => Some(PieceKind::Advisor),
+            'b' | 'e' => Some(PieceKind::Elephant),
+            'n' | 'h' => Some(PieceKind::Horse),
+            'r' => Some(PieceKind::Chariot),
+            'c' => Some(PieceKind::Cannon),
+            'p' => Some(PieceKind::Soldier),
+            _ => None,
+        }
+    }
+
+    /// The letter WXF notation uses.
+    pub fn wxf_letter(self) -> char {
+        match self {
+            PieceKind::General => 'K',
+            PieceKind::Advisor => 'A',
+            PieceKind::Elephant => 'E',
+            PieceKind::Horse => 'H',
+            PieceKind::Chariot => 'R',
+            PieceKind::Cannon => 'C',
+            PieceKind::Soldier => 'P',
+        }
+    }
+
+    /// Whether the piece moves along files and ranks, so WXF gives the
+    /// distance of a forward or backward move rather than the file it ends
+    /// on.
+    pub fn moves_straight(self) -> bool {
+        !matches!(
+            self,
+            PieceKind::Advisor | PieceKind::Elephant | PieceKind::Horse
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Piece {
+    pub side: Side,
+    pub kind: PieceKind,
+}
+
+impl Piece {
+    pub fn new(side: Side, kind: PieceKind) -> Self {
+        Self { side, kind }
+    }
+
+    pub fn to_fen_char(self) -> char {
+        let c = self.kind.to_fen_char();
+        match self.side {
+            Side::Red => c.to_ascii_uppercase(),
+            Side::Black => c,
+        }
+    }
+}
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum XiangqiFenError {
+    #[error("expected 2 to 6 fields, found {0}")]
+    WrongFieldCount(usize),
+
+    #[error("invalid piece placement: {0}")]
+    InvalidPlacement(String),
+
+    #[error("invalid side to move: {0}")]
+    InvalidSideToMove(String),
+
+    #[error("invalid halfmove clock: {0}")]
+    InvalidHalfmoveClock(String),
+
+    #[error("invalid fullmove number: {0}")]
+    InvalidFullmoveNumber(String),
+
+    #[error("{0:?} must have exactly one general")]
+    GeneralCount(Side),
+
+    #[error("{0:?} piece off its allowed points at {1}")]
+    MisplacedPiece(PieceKind, String),
+}
+
+/// A Xiangqi position: the pieces on the board and whose move it is.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct XiangqiBoard {
+    cells: [Option<Piece>; 90],
+    pub to_move: Side,
+    /// Plies since the last capture.
+    pub halfmove_clock: u32,
+    pub fullmove_number: u32,
+}
+
+impl Default for XiangqiBoard {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl XiangqiBoard {
+    pub fn new() -> Self {
+        Self::from_fen(XIANGQI_START).expect("start position is valid FEN")
+    }
+
+    pub fn empty() -> Self {
+        Self {
+            cells: [None; 90],
+            to_move: Side::Red,
+            halfmove_clock: 0,
+            fullmove_number: 1,
+        }
+    }
+
+    pub fn piece_at(&self, square: Square) -> Option<Piece> {
+        self.cells[square.index()]
+    }
+
+    pub fn set_piece(&mut self, square: Square, piece: Option<Piece>) {
+        self.cells[square.index()] = piece;
+    }
+
+    pub fn pieces(&self) -> impl Iterator<Item = (Square, Piece)> + '_ {
+        Square::all().filter_map(|sq| self.piece_at(sq).map(|piece| (sq, piece)))
+    }
+
+    pub fn general_square(&self, side: Side) -> Option<Square> {
+        self.pieces()
+            .find(|(_, piece)| *piece == Piece::new(side, PieceKind::General))
+            .map(|(sq, _)| sq)
+    }
+
+    /// Reads Xiangqi FEN, e.g.
+    /// `rnbakabnr/9/1c5c1/p1p1p1p1p/9/9/P1P1P1P1P/1C5C1/9/RNBAKABNR w - - 0 1`.
+    /// Fields after the side to move may be left off, and `r` is taken for
+    /// Red as well as `w`.
+    pub fn from_fen(fen: &str) -> Result<Self, XiangqiFenError> {
+        let fields: Vec<&str> = fen.split_whitespace().collect();
+        if !(2..=6).contains(&fields.len()) {
+            return Err(XiangqiFenError::WrongFieldCount(fields.len()));
+        }
+
+        let mut board = Self::empty();
+        board.parse_placement(fields[0])?;
+
+        board.to_move = match fields[1] {
+            "w" | "r" => Side::Red,
+            "b" => Side::Black,
+            other => return Err(XiangqiFenError::InvalidSideToMove(other.to_string())),
+        };
+
+        if let Some(field) = fields.get(4) {
+            board.halfmove_clock = field
+                .parse()
+                .map_err(|_| XiangqiFenError::InvalidHalfmoveClock(field.to_string()))?;
+        }
+        if let Some(field) = fields.get(5) {
+            board.fullmove_number = field
+                .parse()
+                .ok()
+                .filter(|&n| n > 0)
+                .ok_or_else(|| XiangqiFenError::InvalidFullmoveNumber(field.to_string()))?;
+        }
+
+        board.validate()?;
+        Ok(board)
+    }
+
+    fn parse_placement(&mut self, placement: &str) -> Result<(), XiangqiFenError> {
+        let invalid = || XiangqiFenError::InvalidPlacement(placement.to_string());
+        let rows: Vec<&str> = placement.split('/').collect();
+        if rows.len() != 10 {
+            return Err(invalid());
+        }
+
+        for (rank, row) in (0..10).rev().zip(rows) {
+            let mut file = 0u8;
+            for c in row.chars() {
+                if let Some(skip) = c.to_digit(10) {
+                    file += skip as u8;
+                    continue;
+                }
+
+                let kind = PieceKind::from_fen_char(c).ok_or_else(invalid)?;
+                let side = match c.is_ascii_uppercase() {
+                    true => Side::Red,
+                    false => Side::Black,
+                };
+                let square = Square::new(file, rank).ok_or_else(invalid)?;
+                self.set_piece(square, Some(Piece::new(side, kind)));
+                file += 1;
+            }
+            if file != 9 {
+                return Err(invalid());
+            }
+        }
+        Ok(())
+    }
+
+    fn validate(&self) -> Result<(), XiangqiFenError> {
+        for side in [Side::Red, Side::Black] {
+            let generals = self
+                .pieces()
+                .filter(|(_, piece)| *piece == Piece::new(side, PieceKind::General))
+                .count();
+            if generals != 1 {
+                return Err(XiangqiFenError::GeneralCount(side));
+            }
+        }
+
+        for (square, piece) in self.pieces() {
+            let allowed = match piece.kind {
+                PieceKind::General | PieceKind::Advisor => square.in_palace(piece.side),
+                PieceKind::Elephant => square.is_own_half(piece.side),
+                // Soldiers never step back, so never stand behind their start
+                PieceKind::Soldier => match piece.side {
+                    Side::Red => square.rank() >= 3,
+                    Side::Black => square.rank() <= 6,
+                },
+                _ => true,
+            };
+            if !allowed {
+                return Err(XiangqiFenError::MisplacedPiece(
+                    piece.kind,
+                    square.to_iccs(),
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn to_fen(&self) -> String {
+        format!(
+            "{} - - {} {}",
+            self.position_key(),
+            self.halfmove_clock,
+            self.fullmove_number
+        )
+    }
+
+    /// The placement and side to move, which identify the position for
+    /// repetition.
+    pub fn position_key(&self) -> String {
+        let mut placement = String::new();
+        for rank in (0..10).rev() {
+            let mut empty = 0;
+            for file in 0..9 {
+                match Square::new(file, rank).and_then(|sq| self.piece_at(sq)) {
+                    Some(piece) => {
+                        if empty > 0 {
+                            placement.push_str(&empty.to_string());
+                            empty = 0;
+                        }
+                        placement.push(piece.to_fen_char());
+                    }
+                    None => empty += 1,
+                }
+            }
+            if empty > 0 {
+                placement.push_str(&empty.to_string());
+            }
+            if rank > 0 {
+                placement.push('/');
+            }
+        }
+
+        let side = match self.to_move {
+            Side::Red => 'w',
+            Side::Black => 'b',
+        };
+        format!("{} {}", placement, side)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_squares() {
+        let square = Square::from_iccs("h2").unwrap();
+        assert_eq!((square.file(), square.rank()), (7, 2));
+        assert_eq!(square.to_iccs(), "h2");
+        assert_eq!(square.wxf_file(Side::Red), 2);
+        assert_eq!(square.wxf_file(Side::Black), 8);
+        assert!(Square::from_iccs("e1").unwrap().in_palace(Side::Red));
+        assert!(!Square::from_iccs("e3").unwrap().in_palace(Side::Red));
+        assert!(Square::from_iccs("j0").is_none());
+    }
+
+    #[test]
+    fn test_fen_round_trip() {
+        assert_eq!(XiangqiBoard::new().to_fen(), XIANGQI_START);
+
+        let fen = "2bak4/4a4/4b1n2/p3p1p1p/2p6/6P2/P1P1c3P/4C1N2/4A4/2BAK4 b - - 3 14";
+        assert_eq!(XiangqiBoard::from_fen(fen).unwrap().to_fen(), fen);
+
+        // Elephants and horses may be written as E and H, and Red as r
+        let board = XiangqiBoard::from_fen("4k4/9/9/9/9/9/9/9/9/2E1K1H2 r").unwrap();
+        assert_eq!(board.to_fen(), "4k4/9/9/9/9/9/9/9/9/2B1K1N2 w - - 0 1");
+    }
+
+    #[test]
+    fn test_invalid_fen() {
+        for (fen, error) in [
+            (
+                "9/9/9/9/9/9/9/9/9/4K4 w",
+                XiangqiFenError::GeneralCount(Side::Black),
+            ),
+            (
+                "4k4/9/9/9/9/9/9/9/9/K8 w",
+                XiangqiFenError::MisplacedPiece(PieceKind::General, "a0".to_string()),
+            ),
+            (
+                "4k4/9/9/9/2B6/9/9/9/9/4K4 w",
+                XiangqiFenError::MisplacedPiece(PieceKind::Elephant, "c5".to_string()),
+            ),
+            (
+                "4k4/9/9/9/9/9/9/9/9/4K4 x",
+                XiangqiFenError::InvalidSideToMove("x".to_string()),
+            ),
+        ] {
+            assert_eq!(XiangqiBoard::from_fen(fen), Err(error));
+        }
+    }
+}
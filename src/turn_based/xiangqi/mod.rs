@@ -0,0 +1,304 @@
+pub mod board;
+pub mod rules;
+
+pub use board::*;
+pub use rules::*;
+
+use serde::{Deserialize, Serialize};
+
+use super::TurnBasedGame;
+
+/// How many times a position must occur before repetition is ruled on.
+pub const REPETITION_LIMIT: usize = 3;
+
+/// Xiangqi as a `TurnBasedGame`. Seat 0 is Red. Moves are sent in WXF
+/// notation or ICCS coordinates and listed in WXF, and views carry the
+/// position as Xiangqi FEN.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Xiangqi;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum XiangqiWinReason {
+    Checkmate,
+    /// The side to move has no legal move without being in check, which
+    /// loses in Xiangqi.
+    Stalemate,
+    /// The loser checked with every move through a repetition.
+    PerpetualCheck,
+    /// The loser chased an unprotected piece with every move through a
+    /// repetition.
+    PerpetualChase,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum XiangqiOutcome {
+    Win(usize, XiangqiWinReason),
+    /// A repetition where neither side, or both alike, kept up checks or
+    /// chases.
+    Draw,
+}
+
+/// A game of Xiangqi: the current position and every position before it,
+/// which repetition is ruled on.
+#[derive(Debug, Clone)]
+pub struct XiangqiGame {
+    pub board: XiangqiBoard,
+    /// Each move in WXF, as written before it was played.
+    pub moves: Vec<String>,
+    /// Every position so far, starting with the initial one.
+    history: Vec<Reached>,
+    outcome: Option<XiangqiOutcome>,
+}
+
+/// A position in a game's history and the move that led to it.
+#[derive(Debug, Clone)]
+struct Reached {
+    key: String,
+    mover: Option<Side>,
+    gave_check: bool,
+    chased: bool,
+}
+
+impl Reached {
+    /// How serious a violation a repetition made of this move would be:
+    /// perpetual check outweighs perpetual chase.
+    fn severity(&self) -> u8 {
+        match (self.gave_check, self.chased) {
+            (true, _) => 2,
+            (false, true) => 1,
+            (false, false) => 0,
+        }
+    }
+}
+
+impl Default for XiangqiGame {
+    fn default() -> Self {
+        Self::new(XiangqiBoard::new())
+    }
+}
+
+impl XiangqiGame {
+    pub fn new(board: XiangqiBoard) -> Self {
+        let mut game = Self {
+            history: vec![Reached {
+                key: board.position_key(),
+                mover: None,
+                gave_check: false,
+                chased: false,
+            }],
+            board,
+            moves: Vec::new(),
+            outcome: None,
+        };
+        game.outcome = game.judge_mate();
+        game
+    }
+
+    pub fn from_fen(fen: &str) -> Result<Self, XiangqiFenError> {
+        XiangqiBoard::from_fen(fen).map(Self::new)
+    }
+
+    pub fn to_fen(&self) -> String {
+        self.board.to_fen()
+    }
+
+    pub fn outcome(&self) -> Option<XiangqiOutcome> {
+        self.outcome
+    }
+
+    pub fn legal_moves(&self) -> Vec<XiangqiMove> {
+        match self.outcome {
+            Some(_) => Vec::new(),
+            None => legal_moves(&self.board),
+        }
+    }
+
+    /// Plays a move in WXF notation or ICCS coordinates.
+    pub fn make_move(&mut self, notation: &str) -> Result<(), String> {
+        if self.outcome.is_some() {
+            return Err("Game is already finished".to_string());
+        }
+        let xiangqi_move = parse_move(&self.board, notation)
+            .ok_or_else(|| format!("Illegal or unreadable move '{}'", notation))?;
+
+        let mover = self.board.to_move;
+        let chased = is_chase(&self.board, xiangqi_move);
+        self.moves.push(to_wxf(&self.board, xiangqi_move));
+        self.board = apply(&self.board, xiangqi_move);
+        self.history.push(Reached {
+            key: self.board.position_key(),
+            mover: Some(mover),
+            gave_check: is_in_check(&self.board, mover.opposite()),
+            chased,
+        });
+
+        self.outcome = self.judge_repetition().or_else(|| self.judge_mate());
+        Ok(())
+    }
+
+    fn judge_mate(&self) -> Option<XiangqiOutcome> {
+        let side = self.board.to_move;
+        if !legal_moves(&self.board).is_empty() {
+            return None;
+        }
+
+        let reason = match is_in_check(&self.board, side) {
+            true => XiangqiWinReason::Checkmate,
+            false => XiangqiWinReason::Stalemate,
+        };
+        Some(XiangqiOutcome::Win(side.opposite().index(), reason))
+    }
+
+    /// Rules on a position's third occurrence. A side that checked, or
+    /// failing that chased, with every move since the first occurrence
+    /// loses, unless the other side did the same; otherwise it is a draw.
+    fn judge_repetition(&self) -> Option<XiangqiOutcome> {
+        let key = &self.history.last()?.key;
+        let first = self
+            .history
+            .iter()
+            .position(|reached| &reached.key == key)?;
+        let occurrences = self.history[first..]
+            .iter()
+            .filter(|reached| &reached.key == key)
+            .count();
+        if occurrences < REPETITION_LIMIT {
+            return None;
+        }
+
+        let cycle = &self.history[first + 1..];
+        let violation = |side: Side| {
+            cycle
+                .iter()
+                .filter(|reached| reached.mover == Some(side))
+                .map(Reached::severity)
+                .min()
+                .unwrap_or(0)
+        };
+        let (red, black) = (violation(Side::Red), violation(Side::Black));
+        let (loser, severity) = match red.cmp(&black) {
+            std::cmp::Ordering::Equal => return Some(XiangqiOutcome::Draw),
+            std::cmp::Ordering::Greater => (Side::Red, red),
+            std::cmp::Ordering::Less => (Side::Black, black),
+        };
+
+        let reason = match severity {
+            2 => XiangqiWinReason::PerpetualCheck,
+            _ => XiangqiWinReason::PerpetualChase,
+        };
+        Some(XiangqiOutcome::Win(loser.opposite().index(), reason))
+    }
+}
+
+/// Nothing is hidden, so every seat sees the same.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct XiangqiView {
+    pub fen: String,
+    pub moves: Vec<String>,
+    pub to_move: usize,
+    pub is_check: bool,
+}
+
+impl TurnBasedGame for Xiangqi {
+    type Move = String;
+    type State = XiangqiGame;
+    type View = XiangqiView;
+    type Outcome = XiangqiOutcome;
+
+    fn kind(&self) -> &'static str {
+        "xiangqi"
+    }
+
+    fn new_state(&self) -> XiangqiGame {
+        XiangqiGame::default()
+    }
+
+    fn to_move(&self, state: &XiangqiGame) -> usize {
+        state.board.to_move.index()
+    }
+
+    fn legal_moves(&self, state: &XiangqiGame) -> Vec<String> {
+        state
+            .legal_moves()
+            .into_iter()
+            .map(|m| to_wxf(&state.board, m))
+            .collect()
+    }
+
+    fn apply_move(&self, state: &mut XiangqiGame, notation: String) -> Result<(), String> {
+        state.make_move(&notation)
+    }
+
+    fn view(&self, state: &XiangqiGame, _seat: Option<usize>) -> XiangqiView {
+        XiangqiView {
+            fen: state.to_fen(),
+            moves: state.moves.clone(),
+            to_move: self.to_move(state),
+            is_check: is_in_check(&state.board, state.board.to_move),
+        }
+    }
+
+    fn outcome(&self, state: &XiangqiGame) -> Option<XiangqiOutcome> {
+        state.outcome
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn play(game: &mut XiangqiGame, moves: &[&str]) {
+        for notation in moves {
+            game.make_move(notation).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_opening() {
+        let mut game = XiangqiGame::default();
+        play(&mut game, &["C2.5", "H8+7", "h0g2", "c9e7"]);
+        assert_eq!(game.moves, ["C2.5", "H8+7", "H2+3", "E3+5"]);
+        assert_eq!(
+            game.to_fen(),
+            "rn1akab1r/9/1c2b1nc1/p1p1p1p1p/9/9/P1P1P1P1P/1C2C1N2/9/RNBAKAB1R w - - 4 3"
+        );
+        assert!(game.make_move("C5+1").is_err());
+        assert_eq!(game.outcome(), None);
+    }
+
+    #[test]
+    fn test_checkmate() {
+        // Two chariots drive the general up, and the soldier covers its escape
+        let mut game = XiangqiGame::from_fen("4k4/9/R8/4P4/9/9/9/9/R8/5K3 w").unwrap();
+        play(&mut game, &["a7a9"]);
+        assert_eq!(game.outcome(), None);
+        play(&mut game, &["e9e8", "a1a8"]);
+        assert_eq!(
+            game.outcome(),
+            Some(XiangqiOutcome::Win(0, XiangqiWinReason::Checkmate))
+        );
+    }
+
+    #[test]
+    fn test_perpetual_check() {
+        // Red checks with every move, so the repetition loses for Red
+        let mut game = XiangqiGame::from_fen("3k5/9/9/9/9/9/9/9/9/4K3R w").unwrap();
+        let cycle = ["d9d8", "i9i8", "d8d9", "i8i9"];
+        play(&mut game, &["i0i9"]);
+        play(&mut game, &cycle);
+        assert_eq!(game.outcome(), None);
+        play(&mut game, &cycle);
+        assert_eq!(
+            game.outcome(),
+            Some(XiangqiOutcome::Win(1, XiangqiWinReason::PerpetualCheck))
+        );
+    }
+
+    #[test]
+    fn test_idle_repetition_draws() {
+        let mut game = XiangqiGame::default();
+        let shuffle = ["h0g2", "h9g7", "g2h0", "g7h9"];
+        play(&mut game, &[shuffle, shuffle].concat());
+        assert_eq!(game.outcome(), Some(XiangqiOutcome::Draw));
+    }
+}
@@ -0,0 +1,421 @@
+use std::fmt;
+
+use super::board::{Piece, PieceKind, Side, Square, XiangqiBoard};
+
+/// A move from one point to another, written in ICCS coordinates as
+/// `h2e2`. Players usually send WXF notation instead, see `to_wxf`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct XiangqiMove {
+    pub from: Square,
+    pub to: Square,
+}
+
+impl XiangqiMove {
+    pub fn new(from: Square, to: Square) -> Self {
+        Self { from, to }
+    }
+
+    /// Reads ICCS notation, with or without a dash: `h2e2` or `h2-e2`.
+    pub fn from_iccs(iccs: &str) -> Option<Self> {
+        let iccs = iccs.to_ascii_lowercase().replace('-', "");
+        if iccs.len() != 4 {
+            return None;
+        }
+        Some(Self::new(
+            Square::from_iccs(iccs.get(..2)?)?,
+            Square::from_iccs(iccs.get(2..)?)?,
+        ))
+    }
+
+    pub fn to_iccs(self) -> String {
+        format!("{}{}", self.from, self.to)
+    }
+}
+
+impl fmt::Display for XiangqiMove {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.to_iccs())
+    }
+}
+
+const ORTHOGONALS: [(i8, i8); 4] = [(0, 1), (1, 0), (0, -1), (-1, 0)];
+const DIAGONALS: [(i8, i8); 4] = [(1, 1), (1, -1), (-1, 1), (-1, -1)];
+
+/// The points the piece on `from` can move to, captures included, before
+/// considering its own general's safety.
+pub fn targets(board: &XiangqiBoard, from: Square) -> Vec<Square> {
+    let Some(piece) = board.piece_at(from) else {
+        return Vec::new();
+    };
+    let side = piece.side;
+    let empty = |sq: Square| board.piece_at(sq).is_none();
+    let step = |files: i8, ranks: i8| from.offset(files, ranks);
+
+    let mut targets = Vec::new();
+    match piece.kind {
+        PieceKind::General => targets.extend(
+            ORTHOGONALS
+                .iter()
+                .filter_map(|&(f, r)| step(f, r))
+                .filter(|sq| sq.in_palace(side)),
+        ),
+        PieceKind::Advisor => targets.extend(
+            DIAGONALS
+                .iter()
+                .filter_map(|&(f, r)| step(f, r))
+                .filter(|sq| sq.in_palace(side)),
+        ),
+        // Blocked when the point between, the elephant's eye, is taken
+        PieceKind::Elephant => targets.extend(DIAGONALS.iter().filter_map(|&(f, r)| {
+            let eye = step(f, r)?;
+            let to = step(2 * f, 2 * r)?;
+            (empty(eye) && to.is_own_half(side)).then_some(to)
+        })),
+        // Blocked when the point it first steps through, its leg, is taken
+        PieceKind::Horse => {
+            for (f, r) in ORTHOGONALS {
+                if step(f, r).is_some_and(empty) {
+                    let (side_f, side_r) = (r, f);
+                    targets.extend(step(2 * f + side_f, 2 * r + side_r));
+                    targets.extend(step(2 * f - side_f, 2 * r - side_r));
+                }
+            }
+        }
+        PieceKind::Chariot => {
+            for (f, r) in ORTHOGONALS {
+                let mut square = from;
+                while let Some(next) = square.offset(f, r) {
+                    targets.push(next);
+                    if !empty(next) {
+                        break;
+                    }
+                    square = next;
+                }
+            }
+        }
+        // Moves like a chariot, but captures by jumping exactly one piece,
+        // the screen
+        PieceKind::Cannon => {
+            for (f, r) in ORTHOGONALS {
+                let mut square = from;
+                let mut screened = false;
+                while let Some(next) = square.offset(f, r) {
+                    match (empty(next), screened) {
+                        (true, false) => targets.push(next),
+                        (false, false) => screened = true,
+                        (true, true) => {}
+                        (false, true) => {
+                            targets.push(next);
+                            break;
+                        }
+                    }
+                    square = next;
+                }
+            }
+        }
+        // Soldiers may also step sideways once across the river
+        PieceKind::Soldier => {
+            targets.extend(step(0, side.forward()));
+            if !from.is_own_half(side) {
+                targets.extend(step(1, 0));
+                targets.extend(step(-1, 0));
+            }
+        }
+    }
+
+    targets.retain(|&to| board.piece_at(to).is_none_or(|target| target.side != side));
+    targets
+}
+
+pub fn is_attacked(board: &XiangqiBoard, square: Square, by: Side) -> bool {
+    board
+        .pieces()
+        .filter(|(_, piece)| piece.side == by)
+        .any(|(from, _)| targets(board, from).contains(&square))
+}
+
+/// Whether the generals face each other on an open file, which neither
+/// side may allow.
+pub fn generals_face(board: &XiangqiBoard) -> bool {
+    let (Some(red), Some(black)) = (
+        board.general_square(Side::Red),
+        board.general_square(Side::Black),
+    ) else {
+        return false;
+    };
+
+    red.file() == black.file()
+        && (red.rank() + 1..black.rank())
+            .filter_map(|rank| Square::new(red.file(), rank))
+            .all(|sq| board.piece_at(sq).is_none())
+}
+
+pub fn is_in_check(board: &XiangqiBoard, side: Side) -> bool {
+    board
+        .general_square(side)
+        .is_some_and(|general| is_attacked(board, general, side.opposite()))
+}
+
+/// Plays a move without checking it, returning the board after it.
+pub fn apply(board: &XiangqiBoard, xiangqi_move: XiangqiMove) -> XiangqiBoard {
+    let mut next = board.clone();
+    let captured = next.piece_at(xiangqi_move.to).is_some();
+
+    next.set_piece(xiangqi_move.to, next.piece_at(xiangqi_move.from));
+    next.set_piece(xiangqi_move.from, None);
+
+    next.halfmove_clock = match captured {
+        true => 0,
+        false => board.halfmove_clock + 1,
+    };
+    if board.to_move == Side::Black {
+        next.fullmove_number += 1;
+    }
+    next.to_move = board.to_move.opposite();
+    next
+}
+
+/// Every legal move: those that leave the mover's general neither attacked
+/// nor facing the other general.
+pub fn legal_moves(board: &XiangqiBoard) -> Vec<XiangqiMove> {
+    let side = board.to_move;
+    board
+        .pieces()
+        .filter(|(_, piece)| piece.side == side)
+        .flat_map(|(from, _)| {
+            targets(board, from)
+                .into_iter()
+                .map(move |to| XiangqiMove::new(from, to))
+        })
+        .filter(|&xiangqi_move| {
+            let next = apply(board, xiangqi_move);
+            !is_in_check(&next, side) && !generals_face(&next)
+        })
+        .collect()
+}
+
+/// Whether a piece of `side` on `square` would be recaptured if taken.
+fn is_protected(board: &XiangqiBoard, square: Square, side: Side) -> bool {
+    let mut taken = board.clone();
+    taken.set_piece(
+        square,
+        Some(Piece::new(side.opposite(), PieceKind::Chariot)),
+    );
+    is_attacked(&taken, square, side)
+}
+
+/// Whether `xiangqi_move`, played on `board`, chases: the moved piece newly
+/// threatens an enemy piece that is unprotected, or a chariot with a horse
+/// or cannon. Generals and soldiers may chase freely, and soldiers still on
+/// their own side of the river may be chased.
+pub fn is_chase(board: &XiangqiBoard, xiangqi_move: XiangqiMove) -> bool {
+    let Some(mover) = board.piece_at(xiangqi_move.from) else {
+        return false;
+    };
+    if matches!(mover.kind, PieceKind::General | PieceKind::Soldier) {
+        return false;
+    }
+
+    let next = apply(board, xiangqi_move);
+    let threatened_before = targets(board, xiangqi_move.from);
+    targets(&next, xiangqi_move.to)
+        .into_iter()
+        .filter(|sq| !threatened_before.contains(sq))
+        .any(|sq| {
+            let Some(target) = next.piece_at(sq) else {
+                return false;
+            };
+            match target.kind {
+                PieceKind::General => false,
+                PieceKind::Soldier if sq.is_own_half(target.side) => false,
+                PieceKind::Chariot
+                    if matches!(mover.kind, PieceKind::Horse | PieceKind::Cannon) =>
+                {
+                    true
+                }
+                _ => !is_protected(&next, sq, target.side),
+            }
+        })
+}
+
+/// Writes a move in WXF notation: the piece letter, the file it stands on
+/// counted from the mover's right, `+`, `-` or `.` for forward, backward or
+/// sideways, then the file it lands on or, for pieces moving straight along
+/// a file, the number of ranks moved. With two like pieces on one file,
+/// `+` and `-` mark the front and rear piece in place of the file.
+pub fn to_wxf(board: &XiangqiBoard, xiangqi_move: XiangqiMove) -> String {
+    let Some(piece) = board.piece_at(xiangqi_move.from) else {
+        return xiangqi_move.to_iccs();
+    };
+    let side = piece.side;
+    let (from, to) = (xiangqi_move.from, xiangqi_move.to);
+    let advance = (to.rank() as i8 - from.rank() as i8) * side.forward();
+
+    let same_file: Vec<Square> = board
+        .pieces()
+        .filter(|&(sq, other)| other == piece && sq.file() == from.file())
+        .map(|(sq, _)| sq)
+        .collect();
+    let progress = |sq: Square| sq.rank() as i8 * side.forward();
+    let origin = if same_file.len() > 1
+        && same_file.iter().all(|&sq| progress(sq) <= progress(from))
+    {
+        "+".to_string()
+    } else if same_file.len() > 1 && same_file.iter().all(|&sq| progress(sq) >= progress(from)) {
+        "-".to_string()
+    } else {
+        from.wxf_file(side).to_string()
+    };
+
+    let (direction, amount) = match advance {
+        0 => ('.', to.wxf_file(side)),
+        _ if piece.kind.moves_straight() && from.file() == to.file() => {
+            (if advance > 0 { '+' } else { '-' }, advance.unsigned_abs())
+        }
+        _ => (if advance > 0 { '+' } else { '-' }, to.wxf_file(side)),
+    };
+
+    format!(
+        "{}{}{}{}",
+        piece.kind.wxf_letter(),
+        origin,
+        direction,
+        amount
+    )
+}
+
+/// Reads a move in WXF notation, also taking `B` and `N` for elephant and
+/// horse, `=` for sideways, and ICCS coordinates.
+pub fn parse_move(board: &XiangqiBoard, notation: &str) -> Option<XiangqiMove> {
+    let moves = legal_moves(board);
+    if let Some(xiangqi_move) = XiangqiMove::from_iccs(notation) {
+        return moves.contains(&xiangqi_move).then_some(xiangqi_move);
+    }
+
+    let wxf: String = notation
+        .trim()
+        .to_ascii_uppercase()
+        .chars()
+        .map(|c| match c {
+            'B' => 'E',
+            'N' => 'H',
+            '=' => '.',
+            other => other,
+        })
+        .collect();
+    moves
+        .into_iter()
+        .find(|&xiangqi_move| to_wxf(board, xiangqi_move) == wxf)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn iccs<T: fmt::Display>(items: &[T]) -> Vec<String> {
+        let mut iccs: Vec<String> = items.iter().map(T::to_string).collect();
+        iccs.sort();
+        iccs
+    }
+
+    #[test]
+    fn test_start_position() {
+        let board = XiangqiBoard::new();
+        assert_eq!(legal_moves(&board).len(), 44);
+
+        let central_cannon = parse_move(&board, "C2.5").unwrap();
+        assert_eq!(central_cannon.to_iccs(), "h2e2");
+        assert_eq!(parse_move(&board, "h2e2"), Some(central_cannon));
+        assert_eq!(to_wxf(&board, parse_move(&board, "b0c2").unwrap()), "H8+7");
+        assert_eq!(to_wxf(&board, parse_move(&board, "g0e2").unwrap()), "E3+5");
+        assert_eq!(to_wxf(&board, parse_move(&board, "a0a2").unwrap()), "R9+2");
+        assert_eq!(parse_move(&board, "h0g2").unwrap().to_iccs(), "h0g2");
+        assert_eq!(parse_move(&board, "N2+3"), parse_move(&board, "H2+3"));
+        assert!(parse_move(&board, "C2.9").is_none());
+
+        // Black counts files from its own right
+        let board = apply(&board, central_cannon);
+        assert_eq!(to_wxf(&board, parse_move(&board, "h7e7").unwrap()), "C8.5");
+    }
+
+    #[test]
+    fn test_blocking() {
+        // The horse's left leg is blocked by the elephant and the elephant's
+        // left eye by the chariot
+        let board = XiangqiBoard::from_fen("4k4/9/9/9/9/9/9/9/1R7/2BNK4 w").unwrap();
+        assert_eq!(
+            iccs(&targets(&board, Square::from_iccs("d0").unwrap())),
+            ["c2", "e2"]
+        );
+        assert_eq!(
+            iccs(&targets(&board, Square::from_iccs("c0").unwrap())),
+            ["e2"]
+        );
+
+        // Elephants stop at the river
+        let board = XiangqiBoard::from_fen("4k4/9/9/9/9/2B6/9/9/9/4K4 w").unwrap();
+        assert_eq!(
+            iccs(&targets(&board, Square::from_iccs("c4").unwrap())),
+            ["a2", "e2"]
+        );
+    }
+
+    #[test]
+    fn test_cannon_and_soldiers() {
+        // The cannon jumps the soldier to capture the chariot
+        let board = XiangqiBoard::from_fen("4k4/4r4/9/9/4p4/9/9/9/9/4CK3 w").unwrap();
+        assert_eq!(
+            iccs(&targets(&board, Square::from_iccs("e0").unwrap())),
+            ["a0", "b0", "c0", "d0", "e1", "e2", "e3", "e4", "e8"]
+        );
+
+        // A soldier steps sideways only once across the river
+        let board = XiangqiBoard::from_fen("4k4/9/9/9/2P6/9/P8/9/9/4K4 w").unwrap();
+        assert_eq!(
+            iccs(&targets(&board, Square::from_iccs("a3").unwrap())),
+            ["a4"]
+        );
+        assert_eq!(
+            iccs(&targets(&board, Square::from_iccs("c5").unwrap())),
+            ["b5", "c6", "d5"]
+        );
+    }
+
+    #[test]
+    fn test_flying_general() {
+        // The horse is all that stands between the generals, so it is pinned
+        let board = XiangqiBoard::from_fen("4k4/9/9/9/9/4N4/9/9/9/4K4 w").unwrap();
+        let from = Square::from_iccs("e4").unwrap();
+        assert!(!legal_moves(&board).iter().any(|m| m.from == from));
+
+        let board = XiangqiBoard::from_fen("3k5/9/9/9/9/9/9/9/9/4K4 w").unwrap();
+        let moves = iccs(&legal_moves(&board));
+        assert!(!moves.contains(&"e0d0".to_string()));
+        assert!(moves.contains(&"e0e1".to_string()));
+    }
+
+    #[test]
+    fn test_tandem_pieces() {
+        let board = XiangqiBoard::from_fen("3k5/9/9/9/9/9/9/2R6/9/2R1K4 w").unwrap();
+        let front = XiangqiMove::from_iccs("c2c5").unwrap();
+        let rear = XiangqiMove::from_iccs("c0d0").unwrap();
+        assert_eq!(to_wxf(&board, front), "R++3");
+        assert_eq!(to_wxf(&board, rear), "R-.6");
+        assert_eq!(parse_move(&board, "R++3"), Some(front));
+    }
+
+    #[test]
+    fn test_chase() {
+        let chase = XiangqiMove::from_iccs("b0a2").unwrap();
+
+        // The horse newly threatens the unprotected cannon
+        let board = XiangqiBoard::from_fen("3k5/9/9/9/9/9/2c6/9/9/1N2K4 w").unwrap();
+        assert!(is_chase(&board, chase));
+
+        // Not once the cannon is protected, nor a piece out of reach
+        let board = XiangqiBoard::from_fen("3k5/9/9/2r6/9/9/2c6/9/9/1N2K4 w").unwrap();
+        assert!(!is_chase(&board, chase));
+        let board = XiangqiBoard::from_fen("3k5/9/9/2c6/9/9/9/9/9/1N2K4 w").unwrap();
+        assert!(!is_chase(&board, chase));
+    }
+}
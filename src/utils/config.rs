@@ -1,5 +1,7 @@
+use crate::game::{LagLimits, TimeControl};
 use crate::utils::error::{ChessResult, ChessServerError};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::env;
 use std::fs;
 use std::path::Path;
@@ -21,6 +23,13 @@ pub struct NetworkConfig {
     pub connection_timeout_secs: u64,
     pub max_message_size: usize,
     pub heartbeat_interval_secs: u64,
+    /// How often clients are pinged to measure their lag.
+    #[serde(default = "default_ping_interval_secs")]
+    pub ping_interval_secs: u64,
+}
+
+fn default_ping_interval_secs() -> u64 {
+    10
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -32,6 +41,53 @@ pub struct GameConfig {
     pub max_concurrent_games: usize,
     pub allow_spectators: bool,
     pub auto_match: bool,
+    /// Time controls games can be created with by name, each in PGN
+    /// `[TimeControl]` syntax, e.g. `40/5400+30:1800+30`.
+    #[serde(default = "default_time_control_presets")]
+    pub time_control_presets: BTreeMap<String, String>,
+    /// Where correspondence games are stored between restarts. Without it
+    /// they are kept in memory only.
+    #[serde(default = "default_correspondence_dir")]
+    pub correspondence_dir: Option<String>,
+    /// How much network lag clocks give back. Without it, none is.
+    #[serde(default = "default_lag_compensation")]
+    pub lag_compensation: Option<LagLimits>,
+}
+
+fn default_lag_compensation() -> Option<LagLimits> {
+    Some(LagLimits::default())
+}
+
+fn default_correspondence_dir() -> Option<String> {
+    Some("data/correspondence".to_string())
+}
+
+fn default_time_control_presets() -> BTreeMap<String, String> {
+    [
+        ("bullet", "60"),
+        ("blitz", "180+2"),
+        ("rapid", "600+5"),
+        ("classical", "40/5400+30:1800+30"),
+    ]
+    .into_iter()
+    .map(|(name, pgn)| (name.to_string(), pgn.to_string()))
+    .collect()
+}
+
+impl GameConfig {
+    /// The time control preset called `name`.
+    pub fn time_control_preset(&self, name: &str) -> Option<TimeControl> {
+        let pgn = self.time_control_presets.get(name)?;
+        TimeControl::from_pgn(name, pgn).ok()
+    }
+
+    /// Every time control preset, by name.
+    pub fn time_control_preset_list(&self) -> Vec<TimeControl> {
+        self.time_control_presets
+            .keys()
+            .filter_map(|name| self.time_control_preset(name))
+            .collect()
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -82,6 +138,7 @@ impl Default for NetworkConfig {
             connection_timeout_secs: 30,
             max_message_size: 1024 * 1024, // 1MB
             heartbeat_interval_secs: 30,
+            ping_interval_secs: default_ping_interval_secs(),
         }
     }
 }
@@ -96,6 +153,9 @@ impl Default for GameConfig {
             max_concurrent_games: 10000,
             allow_spectators: true,
             auto_match: true,
+            time_control_presets: default_time_control_presets(),
+            correspondence_dir: default_correspondence_dir(),
+            lag_compensation: default_lag_compensation(),
         }
     }
 }
@@ -219,6 +279,12 @@ impl ServerConfig {
             });
         }
 
+        if self.server.ping_interval_secs == 0 {
+            return Err(ChessServerError::ConfigurationError {
+                details: "Ping interval must be greater than 0".to_string(),
+            });
+        }
+
         if self.security.max_player_name_length == 0 {
             return Err(ChessServerError::ConfigurationError {
                 details: "Max player name length must be greater than 0".to_string(),
@@ -236,6 +302,14 @@ impl ServerConfig {
             });
         }
 
+        for (name, pgn) in &self.game.time_control_presets {
+            if let Err(e) = TimeControl::from_pgn(name, pgn) {
+                return Err(ChessServerError::ConfigurationError {
+                    details: format!("Invalid time control preset '{}': {}", name, e),
+                });
+            }
+        }
+
         if let Some(ref db_config) = self.database {
             if db_config.url.is_empty() {
                 return Err(ChessServerError::ConfigurationError {
@@ -294,6 +368,7 @@ impl ServerConfig {
         config.security.require_authentication = false;
         config.game.max_games_per_player = 1;
         config.game.game_timeout_secs = 60;
+        config.game.correspondence_dir = None;
         config
     }
 
@@ -381,6 +456,25 @@ mod tests {
         let loaded_config = ServerConfig::from_file(temp_file.path()).unwrap();
         assert_eq!(config.server.host, loaded_config.server.host);
         assert_eq!(config.server.port, loaded_config.server.port);
+        assert_eq!(
+            config.game.time_control_presets,
+            loaded_config.game.time_control_presets
+        );
+    }
+
+    #[test]
+    fn test_time_control_presets() {
+        let mut config = ServerConfig::default();
+        let classical = config.game.time_control_preset("classical").unwrap();
+        assert_eq!(classical.name, "classical");
+        assert_eq!(classical.stages().len(), 2);
+        assert!(config.game.time_control_preset("armageddon").is_none());
+
+        config
+            .game
+            .time_control_presets
+            .insert("broken".to_string(), "40/".to_string());
+        assert!(config.validate().is_err());
     }
 
     #[test]